@@ -3,7 +3,7 @@ pragma solidity >=0.7.0 <0.9.0;
 
 
 // Uniswap v2 interface
-import {IUniswapV2Pair} from '../interfaces/Swaps.sol';  
+import {IUniswapV2Pair, IUniswapV3Pool} from '../interfaces/Swaps.sol';  
 
 // ERC20 interface
 import '../interfaces/IERC20.sol'; 
@@ -14,15 +14,25 @@ import {SafeERC20} from '../interfaces//SafeERC20/SafeERC20.sol';
 library Swapper {
 using SafeERC20 for IERC20;
 
+// Uniswap V3 factory and the init code hash of its pools
+address internal constant V3_FACTORY = 0x1F98431c8aD98523631AE4a59f267346ea31F984;
+bytes32 internal constant V3_POOL_INIT_CODE_HASH = 0xe34f199b19b2b4f47f68442619d555527d244f78a3297ea89325f843f87b8b54;
+
+// price limits from TickMath, we swap without a limit
+uint160 internal constant MIN_SQRT_RATIO = 4295128739;
+uint160 internal constant MAX_SQRT_RATIO = 1461446703485210103287273052203988822378723970342;
+
 
 
 // credits: https://github.com/mouseless-eth/rusty-sando/blob/master/contract/src/LilRouter.sol
 // swap input token for output token on uniswap v2 and forks, returns real balance of output token
+// fee is the fork's swap fee in hundredths of a bip, eg. 3000 is 0.3%
 function _swap_on_V2(
  address input_token,
  address output_token,
  uint256 amount_in,
-  address pool
+  address pool,
+  uint24 fee
   ) internal returns(uint256) {
 
    
@@ -56,7 +66,7 @@ function _swap_on_V2(
 
         // Find the actual amountIn sent to pair (accounts for tax if any) and amountOut
        uint actualAmountIn = IERC20(input_token).balanceOf(address(pool)) - reserveIn;
-       uint256 amountOut = _getAmountOut(actualAmountIn, reserveIn, reserveOut);
+       uint256 amountOut = _getAmountOut(actualAmountIn, reserveIn, reserveOut, fee);
 
         // Prepare swap variables and call pair.swap()
         (uint amount0Out, uint amount1Out) = input_token < output_token ? (uint(0), amountOut) : (amountOut, uint(0));
@@ -69,12 +79,71 @@ function _swap_on_V2(
 
 
 
-function _getAmountOut(uint amountIn, uint reserveIn, uint reserveOut) internal pure returns (uint amountOut) {
+// swap input token for output token on a uniswap v3 pool, returns real balance of output token
+// the pool pulls the input tokens through uniswapV3SwapCallback
+function _swap_on_V3(
+ address input_token,
+ address output_token,
+ uint256 amount_in,
+  address pool
+  ) internal returns(uint256) {
+
+        bool zeroForOne = input_token < output_token;
+
+        // the callback needs the tokens and the fee to verify the pool
+        IUniswapV3Pool(pool).swap(
+            address(this),
+            zeroForOne,
+            int256(amount_in),
+            zeroForOne ? MIN_SQRT_RATIO + 1 : MAX_SQRT_RATIO - 1,
+            abi.encode(input_token, output_token, IUniswapV3Pool(pool).fee())
+        );
+
+     return IERC20(output_token).balanceOf(address(this));
+
+}
+
+
+// pays the pool in the middle of a v3 swap
+// only a pool deployed by the v3 factory can get paid
+function _pay_V3_callback(
+ int256 amount0Delta,
+ int256 amount1Delta,
+ bytes calldata data
+  ) internal {
+
+        (address input_token, address output_token, uint24 fee) = abi.decode(data, (address, address, uint24));
+
+        require(msg.sender == _compute_V3_pool(input_token, output_token, fee), "Not a pool");
+
+        // the positive delta is what we owe to the pool
+        uint256 amount_to_pay = amount0Delta > 0 ? uint256(amount0Delta) : uint256(amount1Delta);
+
+        IERC20(input_token).safeTransfer(msg.sender, amount_to_pay);
+}
+
+
+// computes the CREATE2 address of a v3 pool
+function _compute_V3_pool(address token_a, address token_b, uint24 fee) internal pure returns (address) {
+    (address token0, address token1) = token_a < token_b ? (token_a, token_b) : (token_b, token_a);
+
+    return address(uint160(uint256(keccak256(abi.encodePacked(
+        hex"ff",
+        V3_FACTORY,
+        keccak256(abi.encode(token0, token1, fee)),
+        V3_POOL_INIT_CODE_HASH
+    )))));
+}
+
+
+
+
+function _getAmountOut(uint amountIn, uint reserveIn, uint reserveOut, uint24 fee) internal pure returns (uint amountOut) {
     require(amountIn > 0, 'UniswapV2Library: INSUFFICIENT_INPUT_AMOUNT');
     require(reserveIn > 0 && reserveOut > 0, 'UniswapV2Library: INSUFFICIENT_LIQUIDITY');
-    uint amountInWithFee = amountIn * 997;
+    uint amountInWithFee = amountIn * (1e6 - fee);
     uint numerator = amountInWithFee * reserveOut;
-    uint denominator = reserveIn * 1000 + amountInWithFee;
+    uint denominator = reserveIn * 1e6 + amountInWithFee;
     amountOut = numerator / denominator;
 }
 }
\ No newline at end of file
@@ -3,6 +3,7 @@ pragma solidity >=0.7.0 <0.9.0;
 
 // Interfaces
 import "../interfaces/IERC20.sol";
+import {IWETH} from "../interfaces/IWETH.sol";
 
 // Libraries
 import {Swapper} from "../libraries/Swapper.sol";
@@ -15,26 +16,66 @@ contract Sniper {
        // replace with your addresses
     address public constant SWAP_USER = 0x0000Fd55524058D96255053C0098397E59B9500d;
     address public constant ADMIN = 0x008712be3C996bb73008f0eA47C5742D653c10A8;
+    address private constant WETH = 0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2;
+
+    // the signer wallets allowed to swap, must stay the first storage slot
+    mapping(address => bool) public swap_users;
 
 
     constructor() {        
+        swap_users[SWAP_USER] = true;
 }
 
 
-// swaps directly on pool
-// swaps input for output
+    // authorizes or removes a signer wallet
+    function set_swap_user(address user, bool allowed) external {
+        require(msg.sender == ADMIN, "Hello Stranger!");
+
+        swap_users[user] = allowed;
+    }
+
+
+// swaps directly on a uniswap v2 (or fork) pool
+// swaps input for output, fee is the fork's swap fee in hundredths of a bip
 function snipaaaaaa(
     address input_token,
     address output_token,
     address pool,
     uint256 amount_in,
-    uint256 minimum_received
+    uint256 minimum_received,
+    uint24 fee
 ) external {
 
-    require(msg.sender == SWAP_USER, "Hello Stranger!");
+    require(swap_users[msg.sender], "Hello Stranger!");
 
         // returns real amount (considering any balance left in the contract)
        uint256 amount_out = Swapper._swap_on_V2(
+            input_token,
+            output_token,
+            amount_in,
+            pool,
+            fee
+        );
+
+        // passing 0 as minimum_received means we have no slippage set
+        require(amount_out >= minimum_received, "Yeeeeeeeeet");
+}
+
+
+// swaps directly on a uniswap v3 pool
+// swaps input for output
+function snipaaaaaa_v3(
+    address input_token,
+    address output_token,
+    address pool,
+    uint256 amount_in,
+    uint256 minimum_received
+) external {
+
+    require(swap_users[msg.sender], "Hello Stranger!");
+
+        // returns real amount (considering any balance left in the contract)
+       uint256 amount_out = Swapper._swap_on_V3(
             input_token,
             output_token,
             amount_in,
@@ -46,6 +87,68 @@ function snipaaaaaa(
 }
 
 
+// swaps through a base token on two pools
+// eg. weth -> usdc on the usdc/weth pair, then usdc -> token on the new pool
+function snipaaaaaa_via(
+    address[3] calldata tokens,
+    address[2] calldata pools,
+    bool[2] calldata is_v3,
+    uint24[2] calldata fees,
+    uint256 amount_in,
+    uint256 minimum_received
+) external {
+
+    require(swap_users[msg.sender], "Hello Stranger!");
+
+        // each hop returns the real amount we hold of its output token
+        uint256 amount_out = amount_in;
+        for (uint256 i = 0; i < 2; i++) {
+            amount_out = is_v3[i]
+                ? Swapper._swap_on_V3(tokens[i], tokens[i + 1], amount_out, pools[i])
+                : Swapper._swap_on_V2(tokens[i], tokens[i + 1], amount_out, pools[i], fees[i]);
+        }
+
+        // passing 0 as minimum_received means we have no slippage set
+        require(amount_out >= minimum_received, "Yeeeeeeeeet");
+}
+
+
+// runs one of the swaps above and pays the block builder directly
+// swap is the full call data of snipaaaaaa, snipaaaaaa_v3 or snipaaaaaa_via
+// the payment is unwrapped from our weth and only sent if the swap succeeded
+function snipaaaaaa_and_pay(bytes calldata swap, uint256 coinbase_payment) external {
+
+    require(swap_users[msg.sender], "Hello Stranger!");
+
+        // only our swaps, any other selector would hit the fallback and succeed without a swap
+        require(swap.length >= 4, "Yeeeeeeeeet");
+        bytes4 selector = bytes4(swap[:4]);
+        require(
+            selector == this.snipaaaaaa.selector ||
+            selector == this.snipaaaaaa_v3.selector ||
+            selector == this.snipaaaaaa_via.selector,
+            "Yeeeeeeeeet"
+        );
+
+        // delegatecall keeps msg.sender so the swap checks the same signer
+        (bool success, ) = address(this).delegatecall(swap);
+        require(success, "Yeeeeeeeeet");
+
+        IWETH(WETH).withdraw(coinbase_payment);
+        block.coinbase.transfer(coinbase_payment);
+}
+
+
+    // called by the v3 pool during the swap to collect the input tokens
+    function uniswapV3SwapCallback(
+        int256 amount0Delta,
+        int256 amount1Delta,
+        bytes calldata data
+    ) external {
+        Swapper._pay_V3_callback(amount0Delta, amount1Delta, data);
+    }
+
+
     // withdraws any ERC20 token from the contract
     function withdraw(address token, uint256 amount) external {
         require(msg.sender == ADMIN, "Hello Stranger!");
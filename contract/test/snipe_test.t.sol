@@ -9,6 +9,7 @@ import {IERC20} from "../interfaces/IERC20.sol";
 import {SafeERC20} from "../interfaces/SafeERC20/SafeERC20.sol";
 import {Swapper} from "../libraries/Swapper.sol";
 import {IWETH} from "../interfaces/IWETH.sol";
+import {Sniper} from "../src/sniper.sol";
 
 
 contract SniperTest is Test {
@@ -19,6 +20,7 @@ contract SniperTest is Test {
     address private constant WETH = 0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2;
     address private constant USDT = 0xdAC17F958D2ee523a2206206994597C13D831ec7;
     address private constant WETH_USDT_POOL = 0x0d4a11d5EEaaC28EC3F61d100daF4d40471f1852;
+    address private constant WETH_USDT_V3_POOL = 0x4e68Ccd3E89f51C3074ca5072bbAC773960dFa36;
 
 
 function test_withdraw() external {
@@ -65,7 +67,8 @@ function test_swap() external {
             input_token,
             output_token,
             amount_in,
-            pool
+            pool,
+            3000
         );
 
         require(amount_out >= minimum_received, "Yeeeeeeeeet");
@@ -77,6 +80,101 @@ function test_swap() external {
         withdraw_erc20(USDT, contract_balance);
 }
 
+function test_swap_v3() external {
+
+    uint256 eth_amount = 5000000000000000000;
+    vm.deal(address(this), eth_amount);
+
+    IWETH(WETH).deposit{value: 5000000000000000000}();
+
+    // swap weth for usdt on the 0.3% v3 pool
+    uint256 minimum_received = 0;
+
+           uint256 amount_out = Swapper._swap_on_V3(
+            WETH,
+            USDT,
+            eth_amount,
+            WETH_USDT_V3_POOL
+        );
+
+        require(amount_out >= minimum_received, "Yeeeeeeeeet");
+
+        uint256 contract_balance = IERC20(USDT).balanceOf(address(this));
+        console.log("Contract USDT Balance", contract_balance);
+
+        // swap it back to weth
+        Swapper._swap_on_V3(USDT, WETH, contract_balance, WETH_USDT_V3_POOL);
+        console.log("Contract WETH Balance", IERC20(WETH).balanceOf(address(this)));
+}
+
+function test_swap_and_pay_coinbase() external {
+
+    Sniper sniper = fund_sniper(5000000000000000000);
+
+    // buy usdt with part of the weth, the rest pays the builder
+    uint256 coinbase_payment = 100000000000000000;
+    address builder = address(0xbeef);
+    vm.coinbase(builder);
+
+    bytes memory swap = abi.encodeWithSelector(
+        Sniper.snipaaaaaa.selector, WETH, USDT, WETH_USDT_POOL, 4000000000000000000, 0, uint24(3000)
+    );
+
+        vm.prank(SWAP_USER);
+        sniper.snipaaaaaa_and_pay(swap, coinbase_payment);
+
+        require(builder.balance == coinbase_payment, "Coinbase not paid");
+        require(IERC20(USDT).balanceOf(address(sniper)) > 0, "Swap did not run");
+        console.log("Coinbase ETH Balance", builder.balance);
+}
+
+function test_swap_reverts_coinbase_unpaid() external {
+
+    Sniper sniper = fund_sniper(5000000000000000000);
+
+    uint256 coinbase_payment = 100000000000000000;
+    address builder = address(0xbeef);
+    vm.coinbase(builder);
+
+    // the minimum received can never be met so the swap reverts
+    bytes memory swap = abi.encodeWithSelector(
+        Sniper.snipaaaaaa.selector, WETH, USDT, WETH_USDT_POOL, 4000000000000000000, type(uint256).max, uint24(3000)
+    );
+
+        vm.prank(SWAP_USER);
+        vm.expectRevert();
+        sniper.snipaaaaaa_and_pay(swap, coinbase_payment);
+
+        // an unknown selector would hit the fallback, it must not pay either
+        vm.prank(SWAP_USER);
+        vm.expectRevert();
+        sniper.snipaaaaaa_and_pay(abi.encodeWithSignature("nope()"), coinbase_payment);
+
+        // only the signers can swap, even if the swap itself would succeed
+        bytes memory good_swap = abi.encodeWithSelector(
+            Sniper.snipaaaaaa.selector, WETH, USDT, WETH_USDT_POOL, 4000000000000000000, 0, uint24(3000)
+        );
+        vm.expectRevert();
+        sniper.snipaaaaaa_and_pay(good_swap, coinbase_payment);
+
+        require(builder.balance == 0, "Coinbase paid without a swap");
+}
+
+// deploys the sniper and funds it with weth
+function fund_sniper(uint256 eth_amount) internal returns (Sniper) {
+    Sniper sniper = new Sniper();
+
+    vm.deal(address(this), eth_amount);
+    IWETH(WETH).deposit{value: eth_amount}();
+    IERC20(WETH).safeTransfer(address(sniper), eth_amount);
+
+    return sniper;
+}
+
+// the v3 pool calls back into the test contract
+function uniswapV3SwapCallback(int256 amount0Delta, int256 amount1Delta, bytes calldata data) external {
+    Swapper._pay_V3_callback(amount0Delta, amount1Delta, data);
+}
 
 
 
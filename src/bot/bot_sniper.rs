@@ -6,12 +6,28 @@ use std::sync::Arc;
 
 use crate::oracles::block_oracle::BlockInfo;
 use crate::utils::constants::*;
-use crate::utils::evm::simulate::sim::{ tax_check, generate_tx_data, find_amount_in };
+use crate::utils::evm::simulate::sim::{
+    tax_check,
+    generate_tx_data,
+    find_amount_in,
+    simulate_competition,
+    env_sensitivity_check,
+};
+use crate::forked_db::fork_db::ForkDB;
+use std::time::Duration;
+use crate::utils::evm::executor::{ SIM_EXECUTOR, SimPriority };
 use crate::utils::helpers::*;
 use crate::utils::types::structs::snipe_tx::SnipeTx;
 use crate::bot::{ add_tx_to_oracles, remove_tx_from_oracles };
-use crate::utils::types::structs::{ bot::Bot, pool::Pool };
-use crate::utils::types::events::NewPairEvent;
+use crate::utils::types::structs::{
+    bot::Bot,
+    pool::Pool,
+    bundle_plan::{ BundlePlan, OurTx },
+    tx_outcome::TxOutcome,
+    tx_route::TxRoute,
+};
+use crate::utils::abi::encode_sell;
+use crate::utils::types::events::{ NewPairEvent, MemPoolEvent };
 
 use super::send_tx::send_tx;
 
@@ -32,25 +48,36 @@ pub fn start_sniper(
 
             // start the oracle by subscribing to new pairs
             while let Ok(event) = new_pair_receiver.recv().await {
-                let (pool, tx) = match event {
-                    NewPairEvent::NewPairWithTx { pool, tx } => (pool, tx),
+                let (pool, tx, launchers, token_info) = match event {
+                    NewPairEvent::NewPairWithTx { pool, tx, launchers, token_info } =>
+                        (pool, Some(tx), launchers, token_info),
+                    NewPairEvent::NewPairInBlock { pool, launchers, token_info } =>
+                        (pool, None, launchers, token_info),
                 };
 
                 // process the tx
-                match process_tx(bot.clone(), client.clone(), pool.clone(), tx).await {
-                    Ok(_) => log::trace!("Tx Sent Successfully"),
-                    Err(e) => log::error!("Snipe failed {:?}", e),
+                match process_tx(bot.clone(), client.clone(), pool, tx, launchers).await {
+                    Ok(_) => {
+                        log::trace!("Tx Sent Successfully");
+                        token_info.log_decision("SNIPE", "buy sent");
+                    }
+                    Err(e) => {
+                        log::error!("Snipe failed for {} {:?}", token_info.label(), e);
+                        token_info.log_decision("SKIP", &e.to_string());
+                    }
                 }
             } // end of while loop
         }
     });
 }
 
+// pending_tx is None if the pair is already in a block, then we dont backrun anything
 async fn process_tx(
     bot: Arc<RwLock<Bot>>,
     client: Arc<Provider<Ws>>,
     pool: Pool,
-    pending_tx: Transaction
+    pending_tx: Option<Transaction>,
+    launchers: Vec<Address>
 ) -> Result<(), anyhow::Error> {
     // get block info from oracle
 
@@ -61,76 +88,256 @@ async fn process_tx(
 
 
     // find the amount in in case the token has a min buy size
-    let amount_in = find_amount_in(
-        &pool,
-        &next_block,
-        Some(pending_tx.clone()),
-        fork_db.clone()
-    )?;
+    let amount_in = {
+        let next_block = next_block.clone();
+        let pending_tx = pending_tx.clone();
+        let fork_db = fork_db.clone();
+
+        SIM_EXECUTOR.run(SimPriority::PairDiscovery, move || {
+            find_amount_in(&pool, &next_block, pending_tx, fork_db)
+        }).await??
+    };
 
     // do tax check
-    let is_swap_success = tax_check(
-        &pool,
-        amount_in,
-        &next_block,
-        Some(pending_tx.clone()),
-        fork_db.clone()
-    )?;
+    let is_swap_success = {
+        let next_block = next_block.clone();
+        let pending_tx = pending_tx.clone();
+        let fork_db = fork_db.clone();
+
+        SIM_EXECUTOR.run(SimPriority::PairDiscovery, move || {
+            tax_check(&pool, amount_in, &next_block, pending_tx, fork_db)
+        }).await??
+    };
 
     // if swap fails push it to retry oracle
+    // we keep the launchers so we can backrun the tx that opens trading
     if !is_swap_success {
-        let snipe_tx = SnipeTx::default(pool, *TARGET_AMOUNT_TO_SELL, next_block.number);
+        let mut snipe_tx = SnipeTx::default(pool, *TARGET_AMOUNT_TO_SELL, next_block.number);
+        snipe_tx.launchers = launchers;
         let mut bot_guard = bot.write().await;
         bot_guard.add_tx_to_retry_oracle(snipe_tx).await;
         drop(bot_guard);
         return Err(anyhow::anyhow!("Swap failed, sent to retry oracle"));
     }
 
-    log::info!("Sniping with miner tip: {}", convert_wei_to_gwei(*MINER_TIP_TO_SNIPE));
+    // ** check for anti-bot traps
+    let passed_env_check = passes_env_sensitivity_check(
+        pool,
+        amount_in,
+        next_block.clone(),
+        pending_tx.clone(),
+        fork_db.clone()
+    ).await?;
+
+    if !passed_env_check {
+        return Err(anyhow::anyhow!("Token {:?} treats snipers differently, skipped", pool.token_1));
+    }
+
+    // ** adapt the buy to the other bots sniping the same pool
+    let (amount_in, miner_tip, minimum_received) = match
+        adapt_to_competitors(
+            bot.clone(),
+            pool,
+            amount_in,
+            next_block.clone(),
+            pending_tx.clone(),
+            fork_db.clone()
+        ).await?
+    {
+        Some(adapted) => adapted,
+        None => {
+            return Err(anyhow::anyhow!("Expected fill after competing snipers is too poor, skipped"));
+        }
+    };
+
+    log::info!("Sniping with miner tip: {}", convert_wei_to_gwei(miner_tip));
+
+    // backrun the pending tx if there is one
+    let plan = BundlePlan::backrun_or_standalone(pending_tx);
 
     // ** Generate TxData
-    let (snipe_tx, tx_data) = generate_tx_data(
-        &pool,
-        amount_in,
-        &next_block,
-        Some(pending_tx),
-        *MINER_TIP_TO_SNIPE,
-        1, // 1 for backrun
-        true, // yes we buy
-        fork_db
-    )?;
+    let (snipe_tx, tx_data) = {
+        let next_block = next_block.clone();
+        let fork_db = fork_db.clone();
+
+        SIM_EXECUTOR.run(SimPriority::PairDiscovery, move || {
+            generate_tx_data(
+                &pool,
+                amount_in,
+                &next_block,
+                plan,
+                miner_tip,
+                true, // yes we buy
+                minimum_received,
+                fork_db
+            )
+        }).await??
+    };
 
     // add snipe_tx to oracle
     add_tx_to_oracles(bot.clone(), snipe_tx.clone()).await;
 
     // get the nonce and update it
     let mut bot_guard = bot.write().await;
-    let nonce = bot_guard.get_nonce().await;
+    let tx_count = tx_data.our_tx_count();
+    let (signer, nonce) = bot_guard.get_nonces(false, tx_count).await;
     drop(bot_guard);
 
-    let is_bundle_included = send_tx(
+    let outcome = send_tx(
         client.clone(),
         tx_data,
         next_block,
-        *MINER_TIP_TO_SNIPE,
-        nonce
+        miner_tip,
+        signer,
+        nonce,
+        fork_db
     ).await?;
 
     // if bundle not included push it to retry oracle
+    // if we cant tell we keep the token in the oracles, a buy that never landed
+    // leaves nothing to sell and the sell oracle drops it
 
-    if is_bundle_included == false {
+    if let TxOutcome::Dropped { .. } = outcome {
         // remove the tx from oracles so we dont get bombarded with logs
         remove_tx_from_oracles(bot.clone(), snipe_tx.clone()).await;
 
-        // push to retry oracle
+        // push to retry oracle and free the nonce if nothing used it
         let mut bot_guard = bot.write().await;
         bot_guard.add_tx_to_retry_oracle(snipe_tx).await;
+        if outcome.frees_nonce() {
+            bot_guard.release_nonces(signer, nonce, tx_count).await;
+        }
         drop(bot_guard);
     }
 
     Ok(())
 }
 
+// Runs the anti-bot trap detection
+// returns false if the token should be skipped
+async fn passes_env_sensitivity_check(
+    pool: Pool,
+    amount_in: U256,
+    next_block: BlockInfo,
+    pending_tx: Option<Transaction>,
+    fork_db: ForkDB
+) -> Result<bool, anyhow::Error> {
+    if !*ENABLE_ENV_SENSITIVITY_CHECK {
+        return Ok(true);
+    }
+
+    let sensitivity = SIM_EXECUTOR.run(SimPriority::PairDiscovery, move || {
+        env_sensitivity_check(&pool, amount_in, &next_block, pending_tx, fork_db)
+    }).await??;
+
+    if !sensitivity.opcodes_read.is_empty() {
+        log::info!("Token {:?} reads {:?}", pool.token_1, sensitivity.opcodes_read);
+    }
+
+    if !sensitivity.is_sensitive() {
+        return Ok(true);
+    }
+
+    for (variation, outcome) in &sensitivity.flagged {
+        let kind = if variation.is_trap() { "Anti-bot trap" } else { "Env dependent outcome" };
+        log::warn!(
+            "{} on {:?}: {:?} changed the outcome from {:?} to {:?}",
+            kind,
+            pool.token_1,
+            variation,
+            sensitivity.baseline,
+            outcome
+        );
+    }
+
+    // the block variations are only reported
+    Ok(!(*REJECT_ENV_SENSITIVE_TOKENS && sensitivity.is_trap()))
+}
+
+// Simulates our buy after the other pending swaps on the same pool
+// returns the adapted amount in, miner tip and minimum received
+// or None if the expected fill is too poor
+async fn adapt_to_competitors(
+    bot: Arc<RwLock<Bot>>,
+    pool: Pool,
+    amount_in: U256,
+    next_block: BlockInfo,
+    pending_tx: Option<Transaction>,
+    fork_db: ForkDB
+) -> Result<Option<(U256, U256, Option<U256>)>, anyhow::Error> {
+    if !*ENABLE_COMPETITOR_DETECTION {
+        return Ok(Some((amount_in, *MINER_TIP_TO_SNIPE, None)));
+    }
+
+    // the pool is watched since the pair discovery so the checks and simulations
+    // already gave the competing swaps time to show up, we only wait for the rest
+    let bot_guard = bot.read().await;
+    let watched_for = bot_guard.watched_for(pool.address).await;
+    drop(bot_guard);
+
+    let min_wait = Duration::from_millis(*COMPETITOR_WAIT_MS);
+    if watched_for < min_wait {
+        tokio::time::sleep(min_wait - watched_for).await;
+    }
+
+    let mut bot_guard = bot.write().await;
+    let competing_txs = bot_guard.get_competing_txs(pool.address).await;
+    bot_guard.unwatch_pool(pool.address).await;
+    drop(bot_guard);
+
+    if competing_txs.is_empty() {
+        return Ok(Some((amount_in, *MINER_TIP_TO_SNIPE, None)));
+    }
+
+    let competition = {
+        let next_block = next_block.clone();
+        let pending_tx = pending_tx.clone();
+        let competing_txs = competing_txs.clone();
+        let fork_db = fork_db.clone();
+
+        SIM_EXECUTOR.run(SimPriority::PairDiscovery, move || {
+            simulate_competition(&pool, amount_in, &next_block, pending_tx, competing_txs, fork_db)
+        }).await??
+    };
+
+    log::info!(
+        "Competing snipers on {:?}: {}, max tip {} gwei, fill {}%",
+        pool.token_1,
+        competition.competitors,
+        convert_wei_to_gwei(competition.max_competitor_tip),
+        (competition.fill_bps() as f64) / 100.0
+    );
+
+    if competition.is_fill_too_poor() {
+        return Ok(None);
+    }
+
+    let adapted_amount_in = competition.adapt_amount_in(amount_in);
+    let miner_tip = competition.adapt_miner_tip(*MINER_TIP_TO_SNIPE);
+
+    // the slippage must hold for the amount we actually buy
+    let competition = if adapted_amount_in != amount_in {
+        SIM_EXECUTOR.run(SimPriority::PairDiscovery, move || {
+            simulate_competition(
+                &pool,
+                adapted_amount_in,
+                &next_block,
+                pending_tx,
+                competing_txs,
+                fork_db
+            )
+        }).await??
+    } else {
+        competition
+    };
+
+    if competition.competitors == 0 {
+        return Ok(Some((amount_in, *MINER_TIP_TO_SNIPE, None)));
+    }
+
+    Ok(Some((adapted_amount_in, miner_tip, Some(competition.minimum_received()))))
+}
+
 pub fn snipe_retry(
     bot: Arc<RwLock<Bot>>,
     mut new_block_receive: broadcast::Receiver<BlockInfo>
@@ -190,7 +397,7 @@ async fn process_retry_tx(
         }
 
         // first check if the weth reserve is changed
-        let current_reserve = match get_reserves(tx.pool.address, client.clone()).await {
+        let current_reserve = match get_reserves(&tx.pool, client.clone()).await {
             Ok(reserve) => reserve,
             Err(e) => {
                 log::error!("Failed to get reserves: {:?}", e);
@@ -210,16 +417,28 @@ async fn process_retry_tx(
 
         // spawn tasks
         tokio::spawn(async move {
-            if tx.is_pending {
+            // a backrun of the trading enable tx may be in flight
+            if tx.retry_pending {
                 return;
             }
             // find the amount in in case the token has a min buy size
-            let amount_in = find_amount_in(
-                &tx.pool,
-                &next_block,
-                None,
-                fork_db.clone()
-            ).expect("Failed to find amount in");
+            let amount_in = {
+                let pool = tx.pool;
+                let next_block = next_block.clone();
+                let fork_db = fork_db.clone();
+
+                match
+                    SIM_EXECUTOR.run(SimPriority::PairDiscovery, move || {
+                        find_amount_in(&pool, &next_block, None, fork_db)
+                    }).await
+                {
+                    Ok(Ok(amount_in)) => amount_in,
+                    Ok(Err(e)) | Err(e) => {
+                        log::error!("Failed to find amount in: {:?}", e);
+                        return;
+                    }
+                }
+            };
 
             // if amount in is zero skip
             if amount_in == U256::zero() {
@@ -230,13 +449,23 @@ async fn process_retry_tx(
             }
 
             // do tax check
-            let is_swap_success = tax_check(
-                &tx.pool,
-                amount_in,
-                &next_block,
-                None,
-                fork_db.clone()
-            ).expect("Failed to do tax check");
+            let is_swap_success = {
+                let pool = tx.pool;
+                let next_block = next_block.clone();
+                let fork_db = fork_db.clone();
+
+                match
+                    SIM_EXECUTOR.run(SimPriority::PairDiscovery, move || {
+                        tax_check(&pool, amount_in, &next_block, None, fork_db)
+                    }).await
+                {
+                    Ok(Ok(is_swap_success)) => is_swap_success,
+                    Ok(Err(e)) | Err(e) => {
+                        log::error!("Failed to do tax check: {:?}", e);
+                        return;
+                    }
+                }
+            };
 
             // if swap fails update counter
             if !is_swap_success {
@@ -246,17 +475,58 @@ async fn process_retry_tx(
                 return;
             }
 
+            // ** check for anti-bot traps
+            let passed_env_check = match
+                passes_env_sensitivity_check(
+                    tx.pool,
+                    amount_in,
+                    next_block.clone(),
+                    None,
+                    fork_db.clone()
+                ).await
+            {
+                Ok(passed) => passed,
+                Err(e) => {
+                    log::error!("Failed to do env sensitivity check: {:?}", e);
+                    return;
+                }
+            };
+
+            // the trap wont go away, stop retrying
+            if !passed_env_check {
+                let mut bot_guard = bot.write().await;
+                bot_guard.remove_tx_from_retry_oracle(tx.clone()).await;
+                drop(bot_guard);
+                return;
+            }
+
             // ** Generate TxData
-            let (snipe_tx, tx_data) = generate_tx_data(
-                &tx.pool,
-                amount_in,
-                &next_block,
-                None,
-                *MINER_TIP_TO_SNIPE,
-                2, // no backrun or frontrun
-                true, // yes we buy
-                fork_db
-            ).expect("Failed to generate tx data");
+            let (snipe_tx, tx_data) = {
+                let pool = tx.pool;
+                let next_block = next_block.clone();
+                let fork_db = fork_db.clone();
+
+                match
+                    SIM_EXECUTOR.run(SimPriority::PairDiscovery, move || {
+                        generate_tx_data(
+                            &pool,
+                            amount_in,
+                            &next_block,
+                            BundlePlan::standalone(),
+                            *MINER_TIP_TO_SNIPE,
+                            true, // yes we buy
+                            None,
+                            fork_db
+                        )
+                    }).await
+                {
+                    Ok(Ok(data)) => data,
+                    Ok(Err(e)) | Err(e) => {
+                        log::error!("Failed to generate tx data: {:?}", e);
+                        return;
+                    }
+                }
+            };
 
             // add tx to oracles
             add_tx_to_oracles(bot.clone(), snipe_tx.clone()).await;
@@ -264,35 +534,275 @@ async fn process_retry_tx(
             // set tx to pending and get the nonce
             let mut bot_guard = bot.write().await;
             bot_guard.update_retry_pending(tx.clone(), true).await;
-            let nonce = bot_guard.get_nonce().await;
+            let tx_count = tx_data.our_tx_count();
+            let (signer, nonce) = bot_guard.get_nonces(false, tx_count).await;
             drop(bot_guard);
 
             // send the tx
-            let is_bundle_included = send_tx(
+            let outcome = match send_tx(
                 client.clone(),
                 tx_data,
                 next_block,
                 *MINER_TIP_TO_SNIPE,
-                nonce
-            ).await.expect("Failed to send tx");
+                signer,
+                nonce,
+                fork_db
+            ).await {
+                Ok(outcome) => outcome,
+                Err(e) => {
+                    // we cant tell if anything went out, the nonce is left for the sync
+                    log::error!("Retry: Failed to send tx: {:?}", e);
+                    TxOutcome::Unknown
+                }
+            };
 
-            if is_bundle_included {
-                // remove it from retry
-                let mut bot_guard = bot.write().await;
-                bot_guard.remove_tx_from_retry_oracle(tx.clone()).await;
-                drop(bot_guard);
-            } else {
-                // update the counter and pending status
+            if let TxOutcome::Dropped { .. } = outcome {
+                // update the counter and pending status, free the nonce if nothing used it
                 let mut bot_guard = bot.write().await;
                 bot_guard.update_retry_counter(tx.clone()).await;
                 bot_guard.update_retry_pending(tx.clone(), false).await;
+                if outcome.frees_nonce() {
+                    bot_guard.release_nonces(signer, nonce, tx_count).await;
+                }
                 drop(bot_guard);
 
                 // remove the tx from oracles so we dont get bombarded with logs
                 remove_tx_from_oracles(bot.clone(), snipe_tx.clone()).await;
+            } else {
+                // remove it from retry, an unknown outcome may have bought already
+                let mut bot_guard = bot.write().await;
+                bot_guard.remove_tx_from_retry_oracle(tx.clone()).await;
+                drop(bot_guard);
             }
         }); // end of tokio
     } // end of for loop
 
     Ok(())
 }
+
+// Watches the mempool for the txs that open trading on the tokens in the retry oracle
+// a pending tx from the deployer or owner that makes our buy go through is backrun in the same block
+pub fn snipe_trading_enable(
+    bot: Arc<RwLock<Bot>>,
+    mut new_mempool_receiver: broadcast::Receiver<MemPoolEvent>
+) {
+    if !*ENABLE_TRADING_ENABLE_BACKRUN {
+        return;
+    }
+
+    tokio::spawn(async move {
+        loop {
+            let client = match create_local_client().await {
+                Ok(client) => client,
+                Err(e) => {
+                    log::error!("Failed to create local client: {}", e);
+                    tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+                    continue;
+                }
+            };
+
+            while let Ok(event) = new_mempool_receiver.recv().await {
+                let pending_tx = match event {
+                    MemPoolEvent::NewTx { tx } => tx,
+                };
+
+                let bot_guard = bot.read().await;
+                let snipe_txs = bot_guard.get_retry_oracle_tx_data().await;
+                drop(bot_guard);
+
+                // only the launchers of a token can open trading
+                for snipe_tx in snipe_txs {
+                    if snipe_tx.retry_pending || !snipe_tx.launchers.contains(&pending_tx.from) {
+                        continue;
+                    }
+
+                    let bot = bot.clone();
+                    let client = client.clone();
+                    let pending_tx = pending_tx.clone();
+
+                    tokio::spawn(async move {
+                        if let Err(e) = process_trading_enable(bot, client, snipe_tx, pending_tx).await {
+                            log::error!("Trading Enable Backrun failed {:?}", e);
+                        }
+                    });
+                }
+            } // end of while loop
+        } // end of loop
+    }); // end of tokio spawn
+}
+
+async fn process_trading_enable(
+    bot: Arc<RwLock<Bot>>,
+    client: Arc<Provider<Ws>>,
+    tx: SnipeTx,
+    pending_tx: Transaction
+) -> Result<(), anyhow::Error> {
+    let bot_guard = bot.read().await;
+    let (_, next_block) = bot_guard.get_block_info().await;
+    let fork_db = bot_guard.get_fork_db().await;
+    drop(bot_guard);
+
+    let pool = tx.pool;
+
+    // find the amount in after the pending tx, zero if we still cant buy
+    let amount_in = {
+        let next_block = next_block.clone();
+        let pending_tx = pending_tx.clone();
+        let fork_db = fork_db.clone();
+
+        SIM_EXECUTOR.run(SimPriority::PairDiscovery, move || {
+            find_amount_in(&pool, &next_block, Some(pending_tx), fork_db)
+        }).await??
+    };
+
+    if amount_in == U256::zero() {
+        return Ok(());
+    }
+
+    // ** only a tx that makes the buy go through opens trading
+    // if the buy already succeeds without it the retry oracle snipes it on the next block
+    let is_open_without_tx = {
+        let next_block = next_block.clone();
+        let fork_db = fork_db.clone();
+
+        matches!(
+            SIM_EXECUTOR.run(SimPriority::PairDiscovery, move || {
+                tax_check(&pool, amount_in, &next_block, None, fork_db)
+            }).await,
+            Ok(Ok(true))
+        )
+    };
+
+    if is_open_without_tx {
+        return Ok(());
+    }
+
+    // ** the buy reverts without the pending tx, check if it goes through after it
+    let is_swap_success = {
+        let next_block = next_block.clone();
+        let pending_tx = pending_tx.clone();
+        let fork_db = fork_db.clone();
+
+        SIM_EXECUTOR.run(SimPriority::PairDiscovery, move || {
+            tax_check(&pool, amount_in, &next_block, Some(pending_tx), fork_db)
+        }).await??
+    };
+
+    if !is_swap_success {
+        return Ok(());
+    }
+
+    log::info!("Trading Enabled for {:?} by {:?}", pool.token_1, pending_tx.hash);
+
+    // ** check for anti-bot traps
+    let passed_env_check = passes_env_sensitivity_check(
+        pool,
+        amount_in,
+        next_block.clone(),
+        Some(pending_tx.clone()),
+        fork_db.clone()
+    ).await?;
+
+    // the trap wont go away, stop retrying
+    if !passed_env_check {
+        let mut bot_guard = bot.write().await;
+        bot_guard.remove_tx_from_retry_oracle(tx.clone()).await;
+        drop(bot_guard);
+        return Err(anyhow::anyhow!("Token {:?} treats snipers differently, skipped", pool.token_1));
+    }
+
+    // ** another launcher tx or the next block may already be sniping it
+    let mut bot_guard = bot.write().await;
+    let is_pending = bot_guard
+        .get_retry_oracle_tx_data().await
+        .iter()
+        .any(|x| x.pool.token_1 == pool.token_1 && x.retry_pending);
+    if is_pending {
+        drop(bot_guard);
+        return Ok(());
+    }
+    bot_guard.update_retry_pending(tx.clone(), true).await;
+    drop(bot_guard);
+
+    // ** Generate TxData
+    let tx_data = {
+        let next_block = next_block.clone();
+        let pending_tx = pending_tx.clone();
+        let fork_db = fork_db.clone();
+
+        SIM_EXECUTOR.run(SimPriority::PairDiscovery, move || {
+            generate_tx_data(
+                &pool,
+                amount_in,
+                &next_block,
+                BundlePlan::backrun(vec![pending_tx]),
+                *MINER_TIP_TO_SNIPE,
+                true, // yes we buy
+                None,
+                fork_db
+            )
+        }).await?
+    };
+
+    let (snipe_tx, mut tx_data) = match tx_data {
+        Ok(data) => data,
+        Err(e) => {
+            let mut bot_guard = bot.write().await;
+            bot_guard.update_retry_pending(tx.clone(), false).await;
+            drop(bot_guard);
+            return Err(e);
+        }
+    };
+
+    // ** sell a part of the tokens right after our buy, the bundle only lands if the sell goes through
+    if *ENABLE_BUNDLE_SELL_CHECK && *TX_ROUTE != TxRoute::PublicMempool {
+        let sell_amount = (tx_data.expected_amount * U256::from(*BUNDLE_SELL_CHECK_BPS)) / 10000;
+        let sell_check = OurTx {
+            call_data: encode_sell(&pool, sell_amount, U256::zero()).into(),
+            gas_limit: *BUNDLE_SELL_CHECK_GAS_LIMIT,
+            access_list: Default::default(),
+        };
+
+        let position = tx_data.plan.swap_position() + 1;
+        tx_data.plan.insert_our_tx(position, sell_check);
+    }
+
+    // add tx to oracles and get the nonce
+    add_tx_to_oracles(bot.clone(), snipe_tx.clone()).await;
+
+    let mut bot_guard = bot.write().await;
+    let tx_count = tx_data.our_tx_count();
+    let (signer, nonce) = bot_guard.get_nonces(false, tx_count).await;
+    drop(bot_guard);
+
+    // an error leaves the outcome unknown, the tx may already be out
+    let outcome = send_tx(
+        client.clone(),
+        tx_data,
+        next_block,
+        *MINER_TIP_TO_SNIPE,
+        signer,
+        nonce,
+        fork_db
+    ).await.unwrap_or(TxOutcome::Unknown);
+
+    if let TxOutcome::Dropped { .. } = outcome {
+        // the retry oracle takes over again
+        let mut bot_guard = bot.write().await;
+        bot_guard.update_retry_pending(tx.clone(), false).await;
+        if outcome.frees_nonce() {
+            bot_guard.release_nonces(signer, nonce, tx_count).await;
+        }
+        drop(bot_guard);
+
+        // remove the tx from oracles so we dont get bombarded with logs
+        remove_tx_from_oracles(bot.clone(), snipe_tx.clone()).await;
+    } else {
+        // remove it from retry, an unknown outcome may have bought already
+        let mut bot_guard = bot.write().await;
+        bot_guard.remove_tx_from_retry_oracle(tx.clone()).await;
+        drop(bot_guard);
+    }
+
+    Ok(())
+}
@@ -3,17 +3,19 @@ use ethers::prelude::*;
 use crate::oracles::{
     oracle_status,
     mempool_stream::start_mempool_stream,
-    pair_oracle::start_pair_oracle,
+    pair_oracle::{ start_pair_oracle, start_block_pair_oracle },
     block_oracle::{ start_block_oracle, BlockInfo, BlockOracle },
     sell_oracle::start_sell_oracle,
     anti_rug_oracle::{ start_anti_rug, start_anti_honeypot },
     nonce_oracle::start_nonce_oracle,
     fork_db_oracle::start_forkdb_oracle,
+    competitor_oracle::start_competitor_oracle,
 };
 use crate::forked_db::fork_factory::ForkFactory;
 use revm::db::{ CacheDB, EmptyDB };
+use crate::utils::constants::{ SIGNER_WALLETS, CHAIN_ID };
 
-use super::bot_sniper::{ snipe_retry, start_sniper };
+use super::bot_sniper::{ snipe_retry, snipe_trading_enable, start_sniper };
 use crate::utils::types::{ structs::{ oracles::*, bot::Bot }, events::* };
 use std::sync::Arc;
 use tokio::sync::{ RwLock, broadcast };
@@ -22,6 +24,13 @@ use tokio::{ signal, task };
 pub async fn start(client: Arc<Provider<Ws>>) {
     log::info!("Starting Bot");
 
+    // ** our txs are signed for CHAIN_ID, the node must be on the same chain
+    let chain_id = client.get_chainid().await.unwrap();
+    if chain_id != U256::from(*CHAIN_ID) {
+        log::error!("Node is on chain {} but CHAIN_ID is {}", chain_id, *CHAIN_ID);
+        return;
+    }
+
     // ** prepare block oracle
     let block_oracle = BlockOracle::new(&client).await.unwrap();
     let mut block_oracle = Arc::new(RwLock::new(block_oracle));
@@ -39,8 +48,13 @@ pub async fn start(client: Arc<Provider<Ws>>) {
     // Use Arc<RwLock<>> to share Oracles across tasks.
     let sell_oracle = Arc::new(RwLock::new(SellOracle::new()));
     let retry_oracle = Arc::new(RwLock::new(RetryOracle::new()));
-    let nonce_oracle = Arc::new(RwLock::new(NonceOracle::new()));
+    let signers = SIGNER_WALLETS.iter()
+        .map(|wallet| wallet.address())
+        .collect();
+    let nonce_oracle = Arc::new(RwLock::new(NonceOracle::new(signers)));
     let fork_db_oracle = Arc::new(RwLock::new(ForkOracle::new(fork_db)));
+    let competitor_oracle = Arc::new(RwLock::new(CompetitorOracle::new()));
+    let pair_oracle = Arc::new(RwLock::new(PairOracle::new()));
 
     // hold all oracles inside bot struct
     let bot = Arc::new(
@@ -50,7 +64,9 @@ pub async fn start(client: Arc<Provider<Ws>>) {
                 nonce_oracle.clone(),
                 sell_oracle.clone(),
                 retry_oracle.clone(),
-                fork_db_oracle.clone()
+                fork_db_oracle.clone(),
+                competitor_oracle.clone(),
+                pair_oracle.clone()
             )
         )
     );
@@ -65,12 +81,15 @@ pub async fn start(client: Arc<Provider<Ws>>) {
     let new_block_receiver_3 = new_block_sender.0.subscribe();
     let new_block_receiver_4 = new_block_sender.0.subscribe();
     let new_block_receiver_5 = new_block_sender.0.subscribe();
+    let new_block_receiver_6 = new_block_sender.0.subscribe();
 
     // new mempool event channel
     let new_mempool_sender = broadcast::channel::<MemPoolEvent>(1000); // buffer size 1000
     let new_mempool_receiver = new_mempool_sender.0.subscribe();
     let new_mempool_receiver_2 = new_mempool_sender.0.subscribe();
     let new_mempool_receiver_3 = new_mempool_sender.0.subscribe();
+    let new_mempool_receiver_4 = new_mempool_sender.0.subscribe();
+    let new_mempool_receiver_5 = new_mempool_sender.0.subscribe();
 
     // ** start the block oracle
     start_block_oracle(&mut block_oracle, new_block_sender.0.clone());
@@ -91,6 +110,14 @@ pub async fn start(client: Arc<Provider<Ws>>) {
     // ** Sends new pairs to the sniper
     start_pair_oracle(bot.clone(), new_pair_sender.0.clone(), new_mempool_receiver);
 
+    // ** start the block pair oracle
+    // ** Sends the new pairs found in the block logs to the sniper
+    start_block_pair_oracle(bot.clone(), new_pair_sender.0.clone(), new_block_receiver_6);
+
+    // ** start the competitor oracle
+    // ** Collects other bots swaps on the pools we snipe
+    start_competitor_oracle(competitor_oracle.clone(), new_mempool_receiver_4);
+
     // ** start the sniper
     // ** Recieves new pairs from the pair oracle
     start_sniper(new_pair_receiver, bot.clone());
@@ -99,6 +126,9 @@ pub async fn start(client: Arc<Provider<Ws>>) {
     // ** Recieves new snipe tx data from the sniper
     snipe_retry(bot.clone(), new_block_receiver_3);
 
+    // ** backrun the txs that open trading on the tokens in the retry oracle
+    snipe_trading_enable(bot.clone(), new_mempool_receiver_5);
+
     // ** Start The Sell Oracle
     start_sell_oracle(bot.clone(), new_block_receiver_2);
 
@@ -0,0 +1,190 @@
+use std::collections::{ HashMap, VecDeque };
+use std::sync::Mutex;
+use std::time::{ Duration, Instant };
+use ethers::prelude::*;
+use lazy_static::lazy_static;
+
+use crate::utils::constants::{
+    BUILDERS,
+    BUILDER_STATS_WINDOW,
+    BUILDER_MIN_REQUESTS,
+    BUILDER_MAX_ERROR_RATE,
+    BUILDER_MAX_LATENCY_MS,
+    BUILDER_RETRY_SECS,
+};
+use crate::utils::types::structs::builder::Builder;
+
+// ** Global builder registry, every bundle is sent through it **
+lazy_static! {
+    pub static ref BUILDER_REGISTRY: BuilderRegistry = BuilderRegistry::new(BUILDERS.clone());
+}
+
+#[derive(Debug, Clone, Copy)]
+struct BuilderRequest {
+    latency: Duration,
+    is_error: bool,
+}
+
+// Rolling statistics of a single builder
+#[derive(Debug, Clone, Default)]
+pub struct BuilderStats {
+    requests: VecDeque<BuilderRequest>,
+    pub total_requests: u64,
+    pub total_errors: u64,
+    // bundles that landed in a block built by this builder
+    pub inclusions: u64,
+    // set while the builder is dropped
+    pub dropped_at: Option<Instant>,
+}
+
+impl BuilderStats {
+    pub fn avg_latency(&self) -> Duration {
+        if self.requests.is_empty() {
+            return Duration::ZERO;
+        }
+        let total: Duration = self.requests
+            .iter()
+            .map(|x| x.latency)
+            .sum();
+        total / (self.requests.len() as u32)
+    }
+
+    // share of the requests in the window that failed (in basis points)
+    pub fn error_rate_bps(&self) -> u64 {
+        if self.requests.is_empty() {
+            return 0;
+        }
+        let errors = self.requests
+            .iter()
+            .filter(|x| x.is_error)
+            .count() as u64;
+        (errors * 10000) / (self.requests.len() as u64)
+    }
+
+    // the builder is dead or too slow to be worth sending to
+    fn should_drop(&self) -> bool {
+        if self.requests.len() < *BUILDER_MIN_REQUESTS {
+            return false;
+        }
+        self.error_rate_bps() > *BUILDER_MAX_ERROR_RATE ||
+            self.avg_latency() > Duration::from_millis(*BUILDER_MAX_LATENCY_MS)
+    }
+}
+
+// The builders we send bundles to and their statistics
+//
+// A builder that fails or is slow too often is dropped for BUILDER_RETRY_SECS,
+// after that it gets a clean window and another chance
+pub struct BuilderRegistry {
+    builders: Vec<Builder>,
+    stats: Mutex<HashMap<&'static str, BuilderStats>>,
+}
+
+impl BuilderRegistry {
+    pub fn new(builders: Vec<Builder>) -> Self {
+        let stats = builders
+            .iter()
+            .map(|x| (x.name, BuilderStats::default()))
+            .collect();
+
+        Self {
+            builders,
+            stats: Mutex::new(stats),
+        }
+    }
+
+    // enabled builders that are not dropped
+    // if every one of them is dropped, eg. after a local network blip, the best of them is kept
+    pub fn active_builders(&self) -> Vec<Builder> {
+        let retry_after = Duration::from_secs(*BUILDER_RETRY_SECS);
+        let mut stats = self.stats.lock().unwrap();
+
+        let active: Vec<Builder> = self.builders
+            .iter()
+            .filter(|builder| builder.enabled)
+            .filter(|builder| {
+                let stats = stats.entry(builder.name).or_default();
+                match stats.dropped_at {
+                    Some(dropped_at) if dropped_at.elapsed() < retry_after => false,
+                    Some(_) => {
+                        log::info!("Builder {} gets another chance", builder.name);
+                        stats.dropped_at = None;
+                        stats.requests.clear();
+                        true
+                    }
+                    None => true,
+                }
+            })
+            .cloned()
+            .collect();
+
+        if !active.is_empty() {
+            return active;
+        }
+
+        // the lowest error rate, then the most inclusions, then the lowest latency
+        let best = self.builders
+            .iter()
+            .filter(|builder| builder.enabled)
+            .min_by_key(|builder| {
+                let stats = stats.entry(builder.name).or_default();
+                (stats.error_rate_bps(), std::cmp::Reverse(stats.inclusions), stats.avg_latency())
+            })
+            .cloned();
+
+        match best {
+            Some(builder) => {
+                log::warn!("Every builder is dropped, sending to {} anyway", builder.name);
+                vec![builder]
+            }
+            None => Vec::new(),
+        }
+    }
+
+    // records the outcome of a request and drops the builder if it is dead or slow
+    pub fn record_request(&self, name: &'static str, latency: Duration, is_error: bool) {
+        let mut stats = self.stats.lock().unwrap();
+        let stats = stats.entry(name).or_default();
+
+        stats.requests.push_back(BuilderRequest { latency, is_error });
+        while stats.requests.len() > *BUILDER_STATS_WINDOW {
+            stats.requests.pop_front();
+        }
+
+        stats.total_requests += 1;
+        if is_error {
+            stats.total_errors += 1;
+        }
+
+        if stats.dropped_at.is_none() && stats.should_drop() {
+            log::warn!(
+                "Dropped Builder {}: error rate {}%, avg latency {:?}",
+                name,
+                (stats.error_rate_bps() as f64) / 100.0,
+                stats.avg_latency()
+            );
+            stats.dropped_at = Some(Instant::now());
+        }
+    }
+
+    // attributes an inclusion to the builder of the block
+    // returns the builder name if we know its coinbase
+    pub fn record_inclusion(&self, coinbase: Address) -> Option<&'static str> {
+        let builder = self.builders.iter().find(|x| x.coinbase == Some(coinbase))?;
+
+        let mut stats = self.stats.lock().unwrap();
+        stats.entry(builder.name).or_default().inclusions += 1;
+
+        Some(builder.name)
+    }
+
+    // statistics of every builder
+    pub fn stats(&self) -> Vec<(&'static str, BuilderStats)> {
+        let stats = self.stats.lock().unwrap();
+
+        self.builders
+            .iter()
+            .map(|x| (x.name, stats.get(x.name).cloned().unwrap_or_default()))
+            .collect()
+    }
+}
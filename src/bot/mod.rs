@@ -8,8 +8,9 @@ use crate::utils::types::structs::{bot::Bot, snipe_tx::SnipeTx};
 
 pub mod bot_start;
 pub mod bot_sniper;
-//pub mod send_normal_tx;
+pub mod send_normal_tx;
 pub mod send_tx;
+pub mod builder_registry;
 
 
 
@@ -1,87 +1,242 @@
+use anyhow::anyhow;
 use std::sync::Arc;
+use std::time::{ Duration, Instant };
 use ethers::prelude::*;
-use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::utils::keccak256;
 use crate::oracles::block_oracle::BlockInfo;
-
+use crate::forked_db::fork_db::ForkDB;
+use crate::utils::constants::{
+    CONTRACT_ADDRESS,
+    CHAIN_ID,
+    RECEIPT_POLL_MS,
+    SPEED_UP_INTERVAL_SECS,
+    SPEED_UP_BUMP,
+    MAX_SPEED_UPS,
+};
 use crate::utils::types::structs::tx_data::TxData;
-use crate::utils::helpers::{ get_my_address, get_my_wallet, get_snipe_contract_address, sign_eip1559 };
-
+use crate::utils::types::structs::tx_outcome::TxOutcome;
+use crate::utils::types::structs::bundle_plan::BundlePlan;
+use crate::utils::helpers::{ sign_eip1559, get_signer_wallet };
+use super::send_tx::{ is_bundle_valid, fork_after_block };
 
 
 
+// Sends our tx to the public mempool
+// the tx is sped up with higher fees at the same nonce while it is not mined
+// and cancelled once the opportunity is gone or it was sped up too many times
 pub async fn send_normal_tx(
     client: Arc<Provider<Ws>>,
     tx_data: TxData,
     next_block: BlockInfo,
     miner_tip: U256,
+    signer: Address,
     nonce: U256,
-) -> Result<bool, anyhow::Error> {
-    let my_wallet = get_my_wallet();
+    fork_db: ForkDB
+) -> Result<TxOutcome, anyhow::Error> {
+    // speed ups and cancels replace a single nonce
+    if tx_data.our_tx_count() > 1 {
+        return Err(anyhow!("The public mempool route only sends a single tx"));
+    }
+
+    let mut miner_tip = miner_tip;
+    let mut max_fee = next_block.base_fee + miner_tip;
 
+    let mut signed_tx = sign_swap_tx(&tx_data, miner_tip, max_fee, signer, nonce).await?;
+    let mut tx_hash = H256::from(keccak256(&signed_tx));
+
+    // the foreign txs of the plan are already in the mempool, we only send ours
+    // but we still simulate it in the order we expect it to run
+    let signed_txs = tx_data.plan.order(vec![signed_tx.clone()]);
+    if !is_bundle_valid(&signed_txs, &[tx_hash], &tx_data, &next_block, fork_db).await? {
+        return Ok(TxOutcome::Dropped { nonce_used: false });
+    }
+
+    // the node may have taken the tx even if the request failed
+    if let Err(e) = client.send_raw_transaction(signed_tx.clone()).await {
+        log::error!("Error sending tx: {:?}", e);
+        return Ok(TxOutcome::Unknown);
+    }
 
+    // every replacement has its own hash, any of them can be mined
+    let mut tx_hashes = vec![tx_hash];
+    let mut speed_ups = 0;
 
-    // 500k gas limit, way more than enough for a swap
-    let gas_limit = U256::from(500000u128);
+    loop {
+        if let Some(receipt) = wait_for_receipt(client.clone(), &tx_hashes).await? {
+            return Ok(receipt_outcome(&receipt));
+        }
+
+        if speed_ups >= *MAX_SPEED_UPS {
+            log::warn!("Tx {:?} not mined after {} speed ups", tx_hash, speed_ups);
+            return cancel_and_wait(client, tx_hashes, signer, nonce, miner_tip, max_fee).await;
+        }
+
+        // ** check if the opportunity is still there
+        let latest_block = client.get_block_number().await?;
+        let (next_block, fork_db) = fork_after_block(client.clone(), latest_block).await?;
+
+        // the foreign txs that are already mined are part of the forked state
+        let plan = without_mined_txs(client.clone(), &tx_data.plan).await?;
+        let signed_txs = plan.order(vec![signed_tx.clone()]);
+        if !is_bundle_valid(&signed_txs, &[tx_hash], &tx_data, &next_block, fork_db).await? {
+            return cancel_and_wait(client, tx_hashes, signer, nonce, miner_tip, max_fee).await;
+        }
 
+        // ** speed up, the fees must cover the new base fee too
+        miner_tip = bump_fee(miner_tip);
+        max_fee = bump_fee(max_fee).max(next_block.base_fee + miner_tip);
+
+        signed_tx = sign_swap_tx(&tx_data, miner_tip, max_fee, signer, nonce).await?;
+        tx_hash = H256::from(keccak256(&signed_tx));
+
+        // if the tx was mined meanwhile the node rejects the replacement
+        // the receipt of the mined one is found on the next round
+        match client.send_raw_transaction(signed_tx.clone()).await {
+            Ok(_) => {
+                log::info!("Sped up tx to {:?} with miner tip {}", tx_hash, miner_tip);
+                tx_hashes.push(tx_hash);
+            }
+            Err(e) => {
+                log::warn!("Failed to speed up tx: {:?}", e);
+            }
+        }
+
+        speed_ups += 1;
+    }
+}
+
+// signs our swap tx with the given fees
+async fn sign_swap_tx(
+    tx_data: &TxData,
+    miner_tip: U256,
+    max_fee: U256,
+    signer: Address,
+    nonce: U256
+) -> Result<Bytes, anyhow::Error> {
     let tx_request = Eip1559TransactionRequest {
-        to: Some(NameOrAddress::Address(get_snipe_contract_address())),
-        from: Some(get_my_address()),
+        to: Some(NameOrAddress::Address(*CONTRACT_ADDRESS)),
+        from: Some(signer),
         data: Some(tx_data.tx_call_data.clone()),
-        chain_id: Some(U64::from(1)),
+        chain_id: Some(U64::from(*CHAIN_ID)),
         max_priority_fee_per_gas: Some(miner_tip),
-        max_fee_per_gas: Some(next_block.base_fee + miner_tip),
-        gas: Some(gas_limit),
+        max_fee_per_gas: Some(max_fee),
+        gas: Some(U256::from(tx_data.gas_limit)),
         nonce: Some(nonce),
         value: Some(U256::zero()),
         access_list: tx_data.access_list.clone(),
     };
 
-    let signed_tx = sign_eip1559(tx_request, &my_wallet).await?;
+    Ok(sign_eip1559(tx_request, &get_signer_wallet(signer)?).await?)
+}
 
-    let tx_hash = match client.send_raw_transaction(signed_tx).await {
-        Ok(tx) => tx,
+// replaces our tx with a zero value self transfer at the same nonce
+// returns the hash of the cancel tx
+pub async fn cancel_tx(
+    client: Arc<Provider<Ws>>,
+    signer: Address,
+    nonce: U256,
+    miner_tip: U256,
+    max_fee: U256
+) -> Result<TxHash, anyhow::Error> {
+    let tx_request = Eip1559TransactionRequest {
+        to: Some(NameOrAddress::Address(signer)),
+        from: Some(signer),
+        data: None,
+        chain_id: Some(U64::from(*CHAIN_ID)),
+        max_priority_fee_per_gas: Some(bump_fee(miner_tip)),
+        max_fee_per_gas: Some(bump_fee(max_fee)),
+        gas: Some(U256::from(21000u64)),
+        nonce: Some(nonce),
+        value: Some(U256::zero()),
+        access_list: Default::default(),
+    };
+
+    let signed_tx = sign_eip1559(tx_request, &get_signer_wallet(signer)?).await?;
+    let tx_hash = H256::from(keccak256(&signed_tx));
+
+    client.send_raw_transaction(signed_tx).await?;
+    log::warn!("Cancelling nonce {} of {:?} with tx {:?}", nonce, signer, tx_hash);
+
+    Ok(tx_hash)
+}
+
+// cancels our tx and waits to see which one got mined
+// if neither is mined in time the outcome is unknown, both may still land
+async fn cancel_and_wait(
+    client: Arc<Provider<Ws>>,
+    mut tx_hashes: Vec<TxHash>,
+    signer: Address,
+    nonce: U256,
+    miner_tip: U256,
+    max_fee: U256
+) -> Result<TxOutcome, anyhow::Error> {
+    let cancel_hash = match cancel_tx(client.clone(), signer, nonce, miner_tip, max_fee).await {
+        Ok(hash) => hash,
         Err(e) => {
-            log::error!("Error sending tx: {:?}", e);
-            return Ok(false);
+            log::error!("Failed to cancel tx: {:?}", e);
+            return Ok(TxOutcome::Unknown);
         }
     };
+    tx_hashes.push(cancel_hash);
 
-    let tx_hash = tx_hash.clone();
-
-    // a small delay to not spam the node
-    let delay = tokio::time::Duration::from_millis(10);
-    let mut tx_receipt = None;
+    match wait_for_receipt(client, &tx_hashes).await? {
+        Some(receipt) if receipt.transaction_hash == cancel_hash => {
+            Ok(TxOutcome::Dropped { nonce_used: true })
+        }
+        Some(receipt) => Ok(receipt_outcome(&receipt)),
+        None => {
+            log::warn!("Neither tx {:?} nor its cancel were mined yet", tx_hashes[0]);
+            Ok(TxOutcome::Unknown)
+        }
+    }
+}
 
-    // Wait until we get the receipt
-    loop {
-        match client.get_transaction_receipt(tx_hash).await {
-            Ok(Some(receipt)) => {
-                tx_receipt = Some(receipt);
-                // we got receipt, break out of loop
-                break;
-            }
-            Ok(None) => {
-                tokio::time::sleep(delay).await;
-            }
-            Err(e) => {
-                log::error!("Error getting tx receipt: {:?}", e);
-                return Ok(false);
+// polls the receipts of the txs until one of them is mined or the speed up interval passes
+async fn wait_for_receipt(
+    client: Arc<Provider<Ws>>,
+    tx_hashes: &[TxHash]
+) -> Result<Option<TransactionReceipt>, anyhow::Error> {
+    let started_at = Instant::now();
+    let timeout = Duration::from_secs(*SPEED_UP_INTERVAL_SECS);
+    let delay = Duration::from_millis(*RECEIPT_POLL_MS);
+
+    while started_at.elapsed() < timeout {
+        for tx_hash in tx_hashes {
+            if let Some(receipt) = client.get_transaction_receipt(*tx_hash).await? {
+                return Ok(Some(receipt));
             }
         }
+        tokio::time::sleep(delay).await;
     }
 
-    let tx_receipt = match tx_receipt {
-        Some(receipt) => receipt,
-        None => {
-            log::error!("Error unwrapping tx receipt");
-            return Ok(false);
-        }
-    };
+    Ok(None)
+}
 
-    if tx_receipt.status != Some(U64::from(1u64)) {
-        log::error!("Tx {:?} reverted", tx_hash);
-        return Ok(false);
+// a reverted tx still used its nonce
+pub fn receipt_outcome(receipt: &TransactionReceipt) -> TxOutcome {
+    if receipt.status != Some(U64::from(1u64)) {
+        log::error!("Tx {:?} reverted", receipt.transaction_hash);
+        return TxOutcome::Dropped { nonce_used: true };
     }
+    TxOutcome::Landed
+}
+
+// drops the foreign txs of the plan that already have a receipt
+async fn without_mined_txs(
+    client: Arc<Provider<Ws>>,
+    plan: &BundlePlan
+) -> Result<BundlePlan, anyhow::Error> {
+    let mut mined = Vec::new();
+    for tx in plan.foreign_txs() {
+        if client.get_transaction_receipt(tx.hash).await?.is_some() {
+            mined.push(tx.hash);
+        }
+    }
+
+    Ok(plan.without_txs(&mined))
+}
 
-    Ok(true)
-}
\ No newline at end of file
+// bumps a fee by the speed up percentage
+fn bump_fee(fee: U256) -> U256 {
+    (fee * U256::from(100 + *SPEED_UP_BUMP)) / 100
+}
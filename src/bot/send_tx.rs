@@ -1,234 +1,503 @@
 use anyhow::anyhow;
 use ethers_flashbots::*;
-use url::Url;
 use std::sync::Arc;
-use tokio::task::JoinError;
+use std::time::{ Duration, Instant };
 use ethers::prelude::*;
+use ethers::utils::keccak256;
+use revm::db::{ CacheDB, EmptyDB };
 use crate::oracles::block_oracle::BlockInfo;
+use crate::forked_db::{ fork_db::ForkDB, fork_factory::ForkFactory };
+use crate::utils::evm::executor::{ SIM_EXECUTOR, SimPriority };
+use crate::utils::evm::simulate::sim::simulate_bundle;
 use crate::utils::constants::{
-    CALLER_ADDRESS,
-    CALLER_WALLET,
     FLASHBOT_IDENTITY,
     FLASHBOT_SEARCHER,
     CONTRACT_ADDRESS,
+    CHAIN_ID,
+    INCLUSION_TIMEOUT_SECS,
+    BUNDLE_TARGET_BLOCKS,
+    TX_ROUTE,
+    BUILDER_PAYMENT,
+    COINBASE_PAYMENT_SHARE,
 };
 use crate::utils::types::structs::tx_data::TxData;
-use crate::utils::helpers::sign_eip1559;
+use crate::utils::types::structs::builder::{ Builder, BuilderMethod };
+use crate::utils::types::structs::tx_route::TxRoute;
+use crate::utils::types::structs::tx_outcome::TxOutcome;
+use crate::utils::types::structs::builder_payment::BuilderPayment;
+use crate::utils::helpers::{ sign_eip1559, max_base_fee_after, get_signer_wallet };
+use super::builder_registry::BUILDER_REGISTRY;
+use super::send_normal_tx::{ send_normal_tx, receipt_outcome };
+
+// A copy of the signed bundle for a single target block
+#[derive(Clone)]
+struct BundleCopy {
+    target_block: U64,
+    // lets the builders that support it cancel or replace the copy
+    replacement_uuid: String,
+    bundle: BundleRequest,
+}
 
-#[allow(unused_assignments)]
 pub async fn send_tx(
     client: Arc<Provider<Ws>>,
     tx_data: TxData,
     next_block: BlockInfo,
     miner_tip: U256,
-    nonce: U256
-) -> Result<bool, anyhow::Error> {
-    // 500k gas limit, way more than enough for a swap
-    let gas_limit = U256::from(500000u128);
-
-    let tx_request = Eip1559TransactionRequest {
-        to: Some(NameOrAddress::Address(*CONTRACT_ADDRESS)),
-        from: Some(*CALLER_ADDRESS),
-        data: Some(tx_data.tx_call_data.clone()),
-        chain_id: Some(U64::from(1)),
-        max_priority_fee_per_gas: Some(miner_tip),
-        max_fee_per_gas: Some(next_block.base_fee + miner_tip),
-        gas: Some(gas_limit),
-        nonce: Some(nonce),
-        value: Some(U256::zero()),
-        access_list: tx_data.access_list.clone(),
+    signer: Address,
+    nonce: U256,
+    fork_db: ForkDB
+) -> Result<TxOutcome, anyhow::Error> {
+    if *TX_ROUTE == TxRoute::PublicMempool {
+        return send_normal_tx(client, tx_data, next_block, miner_tip, signer, nonce, fork_db).await;
+    }
+
+    // ** Move the bid from the priority fee to a coinbase transfer if configured
+    let mut tx_data = tx_data;
+    let miner_tip = if *BUILDER_PAYMENT == BuilderPayment::CoinbaseTransfer {
+        pay_coinbase(&mut tx_data, miner_tip)
+    } else {
+        miner_tip
     };
-    let frontrun_or_backrun = tx_data.frontrun_or_backrun;
 
-    let signed_tx = sign_eip1559(tx_request, &CALLER_WALLET).await?;
+    // the same signed txs are valid for every target block
+    // so the max fee must cover the base fee of the last one
+    let target_blocks = (*BUNDLE_TARGET_BLOCKS).max(1);
+    let max_base_fee = max_base_fee_after(next_block.base_fee, target_blocks - 1);
+
+    // ** Sign our swap and our other txs with sequential nonces
+    let wallet = get_signer_wallet(signer)?;
+    let mut our_signed_txs = Vec::new();
+
+    for (i, our_tx) in tx_data.our_txs().into_iter().enumerate() {
+        let tx_request = Eip1559TransactionRequest {
+            to: Some(NameOrAddress::Address(*CONTRACT_ADDRESS)),
+            from: Some(signer),
+            data: Some(our_tx.call_data),
+            chain_id: Some(U64::from(*CHAIN_ID)),
+            max_priority_fee_per_gas: Some(miner_tip),
+            max_fee_per_gas: Some(max_base_fee + miner_tip),
+            // gas limit derived from the simulated gas
+            gas: Some(U256::from(our_tx.gas_limit)),
+            nonce: Some(nonce + i),
+            value: Some(U256::zero()),
+            access_list: our_tx.access_list,
+        };
+
+        our_signed_txs.push(sign_eip1559(tx_request, &wallet).await?);
+    }
 
-    let pending_tx = tx_data.pending_tx.rlp();
+    // our swap goes first in the hashes, the bundle copies are named after it
+    let mut tx_hashes: Vec<TxHash> = our_signed_txs
+        .iter()
+        .map(|x| H256::from(keccak256(x)))
+        .collect();
+    let tx_hash = tx_hashes.remove(tx_data.plan.swap_index());
+    tx_hashes.insert(0, tx_hash);
 
-    let bundle = construct_bundle(
-        frontrun_or_backrun,
-        signed_tx,
-        pending_tx,
-        next_block.number,
-        next_block.timestamp.as_u64()
-    );
+    // our txs and the foreign txs in bundle order
+    let signed_txs = tx_data.plan.order(our_signed_txs);
+
+    // ** Simulate the exact bundle locally before it leaves
+    if !is_bundle_valid(&signed_txs, &tx_hashes, &tx_data, &next_block, fork_db).await? {
+        return Ok(TxOutcome::Dropped { nonce_used: false });
+    }
 
-    let urls = get_all_urls();
-    let mut is_bundle_included = false;
+    // ** One copy of the bundle for every target block, each with its earliest timestamp
+    let copies: Vec<BundleCopy> = (0..target_blocks)
+        .map(|i| {
+            let target_block = next_block.number + i;
+            let target_timestamp = next_block.timestamp.as_u64() + 12 * i;
+
+            BundleCopy {
+                target_block,
+                replacement_uuid: replacement_uuid(tx_hash, target_block),
+                bundle: construct_bundle(signed_txs.clone(), target_block, target_timestamp),
+            }
+        })
+        .collect();
+
+    let builders = BUILDER_REGISTRY.active_builders();
 
     // ** Send the bundle concurently to all the MEV builders
     // ** Almost all builders support the same API eth_sendBundle
     let mut tasks = Vec::new();
 
-    for url in urls {
+    for builder in builders.clone() {
         let client = client.clone();
-        let bundle = bundle.clone();
+        let copies = copies.clone();
 
         let task = tokio::spawn(async move {
+            for copy in copies {
+                let started_at = Instant::now();
+                let result = send_bundle_to_builder(client.clone(), &builder, &copy).await;
+                BUILDER_REGISTRY.record_request(builder.name, started_at.elapsed(), result.is_err());
+
+                if let Err(e) = result {
+                    log::trace!("Failed to send bundle to {}: {:?}", builder.name, e);
+                }
+            }
+        }); // end of tokio spawn
+
+        tasks.push(task);
+    } // end of for loop
+
+    // Await all the requests
+    futures::future::join_all(tasks).await;
+
+    // ** Check if the bundle was included in one of the target blocks **
+    let outcome = match
+        wait_for_inclusion(
+            client.clone(),
+            &copies,
+            &builders,
+            &signed_txs,
+            &tx_hashes,
+            &tx_data,
+            signer,
+            nonce
+        ).await
+    {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            log::error!("Bundle Error: {:?}", e);
+            TxOutcome::Unknown
+        }
+    };
+
+    log::info!("Bundle Outcome: {:?}", outcome);
+
+    Ok(outcome)
+}
+
+// wraps our swap with a coinbase transfer sized from the simulated profit
+// without a profit we transfer what the miner tip would have cost us
+// returns the miner tip left to pay, none since the transfer is the bid
+fn pay_coinbase(tx_data: &mut TxData, miner_tip: U256) -> U256 {
+    let coinbase_payment = tx_data.profit
+        .map(|profit| (profit * U256::from(*COINBASE_PAYMENT_SHARE)) / 100)
+        .filter(|payment| !payment.is_zero())
+        .unwrap_or(miner_tip * U256::from(tx_data.gas_used));
+
+    tx_data.pay_coinbase(coinbase_payment);
+    log::info!("Paying {} wei to the coinbase instead of the miner tip", coinbase_payment);
+
+    U256::zero()
+}
+
+// simulates the bundle on the target block
+// returns false with the reason logged if it should not be sent
+pub async fn is_bundle_valid(
+    signed_txs: &[Bytes],
+    tx_hashes: &[TxHash],
+    tx_data: &TxData,
+    next_block: &BlockInfo,
+    fork_db: ForkDB
+) -> Result<bool, anyhow::Error> {
+    // bundles are about to be sent so they run before anything else
+    let simulation = {
+        let signed_txs = signed_txs.to_vec();
+        let tx_hashes = tx_hashes.to_vec();
+        let out_pool = tx_data.out_pool;
+        let minimum_received = tx_data.expected_amount;
+        let next_block = next_block.clone();
+
+        SIM_EXECUTOR.run(SimPriority::AntiRug, move || {
+            simulate_bundle(&signed_txs, &tx_hashes, out_pool, minimum_received, &next_block, fork_db)
+        }).await?
+    };
+
+    match simulation {
+        Ok(simulation) => {
+            log::info!(
+                "Simulated Bundle for block {}: amount out {}, coinbase payment {}, gas used {}",
+                next_block.number,
+                simulation.amount_out,
+                simulation.coinbase_payment,
+                simulation.gas_used
+            );
+            Ok(true)
+        }
+        Err(e) => {
+            log::warn!("Bundle aborted: {}", e);
+            Ok(false)
+        }
+    }
+}
+
+// sends a copy of the bundle to a single builder with its method
+async fn send_bundle_to_builder(
+    client: Arc<Provider<Ws>>,
+    builder: &Builder,
+    copy: &BundleCopy
+) -> Result<(), anyhow::Error> {
+    // copies for several blocks carry a replacement uuid so the later ones can be cancelled
+    if *BUNDLE_TARGET_BLOCKS > 1 {
+        return send_cancellable_bundle(builder, copy).await;
+    }
+
+    match builder.method {
+        BuilderMethod::FlashbotsBundle => {
             // Add signer to Flashbots middleware
             let flashbots_client = SignerMiddleware::new(
-                FlashbotsMiddleware::new(client.clone(), url.clone(), FLASHBOT_IDENTITY.clone()),
+                FlashbotsMiddleware::new(client.clone(), builder.url.clone(), FLASHBOT_IDENTITY.clone()),
                 FLASHBOT_SEARCHER.clone()
             );
 
-            // only simulate bundle for flashbot relay
-            if url == Url::parse("https://relay.flashbots.net/").unwrap() {
-                let simulated_bundle = flashbots_client.inner().simulate_bundle(&bundle).await;
-
-                match simulated_bundle {
-                    Ok(_sim_result) => {
-                        // log::info!("Simulated Bundle Result: {:?}", sim_result);
-                    }
-                    Err(e) => {
-                        log::error!("Failed to simulate bundle: {}", e);
-                    }
-                }
+            // send tx to MEV builders
+            // we check the inclusion ourselves so the pending bundle is not awaited
+            if let Err(e) = flashbots_client.inner().send_bundle(&copy.bundle).await {
+                return Err(anyhow!("Failed to send bundle:: {:?}", e));
             }
+        }
+        BuilderMethod::RawBundle => {
+            let mut params = serde_json::to_value(&copy.bundle)?;
+            params["replacementUuid"] = copy.replacement_uuid.clone().into();
 
-            // send tx to MEV builders
-            let pending_bundle = match flashbots_client.inner().send_bundle(&bundle).await {
-                Ok(pending_bundle) => pending_bundle,
-                Err(e) => {
-                    // log::info!("Failed to send bundle: {:?}", e);
-                    return Err(anyhow!("Failed to send bundle:: {:?}", e));
+            let provider = Provider::<Http>::try_from(builder.url.as_str())?;
+            provider.request::<_, serde_json::Value>("eth_sendBundle", [params]).await?;
+        }
+    }
+
+    Ok(())
+}
+
+// sends a copy that can be cancelled later, signed with the flashbots identity
+async fn send_cancellable_bundle(builder: &Builder, copy: &BundleCopy) -> Result<(), anyhow::Error> {
+    let mut params = serde_json::to_value(&copy.bundle)?;
+    params["replacementUuid"] = copy.replacement_uuid.clone().into();
+
+    send_builder_request(builder, "eth_sendBundle", params).await
+}
+
+// sends a json rpc request to the builder with its method
+// flashbots builders need the request signed with our identity
+async fn send_builder_request(
+    builder: &Builder,
+    method: &str,
+    params: serde_json::Value
+) -> Result<(), anyhow::Error> {
+    match builder.method {
+        BuilderMethod::FlashbotsBundle => {
+            let relay = Relay::new(builder.url.clone(), Some(FLASHBOT_IDENTITY.clone()));
+            relay
+                .request::<_, serde_json::Value>(method, [params]).await
+                .map_err(|e| anyhow!("{:?}", e))?;
+        }
+        BuilderMethod::RawBundle => {
+            let provider = Provider::<Http>::try_from(builder.url.as_str())?;
+            provider.request::<_, serde_json::Value>(method, [params]).await?;
+        }
+    }
+
+    Ok(())
+}
+
+// cancels the copies of the bundle that are still pending by their replacement uuid
+// builders that ignore the cancel drop the copies once they fail to execute
+async fn cancel_bundle_copies(builders: &[Builder], copies: &[BundleCopy]) {
+    let mut tasks = Vec::new();
+
+    for builder in builders {
+        for copy in copies {
+            let builder = builder.clone();
+            let replacement_uuid = copy.replacement_uuid.clone();
+
+            let task = tokio::spawn(async move {
+                let params = serde_json::json!({ "replacementUuid": replacement_uuid });
+
+                if let Err(e) = send_builder_request(&builder, "eth_cancelBundle", params).await {
+                    log::trace!("Failed to cancel bundle on {}: {:?}", builder.name, e);
                 }
-            };
-
-            // ** Check if the bundle was included **
-            is_bundle_included = match pending_bundle.await {
-                Ok(_) => true,
-                Err(ethers_flashbots::PendingBundleError::BundleNotIncluded) => false,
-                Err(e) => {
-                    log::error!("Bundle Error: {:?}", e);
-                    false
+            });
+
+            tasks.push(task);
+        }
+    }
+
+    futures::future::join_all(tasks).await;
+}
+
+// waits for the target blocks and checks if our txs are in one of them
+// before every later target the bundle is simulated again on the new state
+// and the remaining copies are cancelled if it no longer passes
+async fn wait_for_inclusion(
+    client: Arc<Provider<Ws>>,
+    copies: &[BundleCopy],
+    builders: &[Builder],
+    signed_txs: &[Bytes],
+    tx_hashes: &[TxHash],
+    tx_data: &TxData,
+    signer: Address,
+    nonce: U256
+) -> Result<TxOutcome, anyhow::Error> {
+    let last_target = copies
+        .last()
+        .map(|x| x.target_block)
+        .ok_or(anyhow!("Bundle has no copies"))?;
+
+    for (i, copy) in copies.iter().enumerate() {
+        wait_for_block(client.clone(), copy.target_block).await?;
+
+        // every copy uses the same nonces, once one lands the others can no longer execute
+        if let Some(receipt) = client.get_transaction_receipt(tx_hashes[0]).await? {
+            let included_in = receipt.block_number.unwrap_or_default();
+
+            if let Some(block) = client.get_block(included_in).await? {
+                let coinbase = block.author.unwrap_or_default();
+                match BUILDER_REGISTRY.record_inclusion(coinbase) {
+                    Some(name) => log::info!("Bundle included in block {} by {}", included_in, name),
+                    None => {
+                        log::info!(
+                            "Bundle included in block {} by unknown builder {:?}",
+                            included_in,
+                            coinbase
+                        );
+                    }
                 }
-            };
-            // check if bundle is included and return the result
-            if is_bundle_included {
-                Ok::<bool, anyhow::Error>(true)
-            } else {
-                Err(anyhow!("Bundle was not included"))
             }
-        }); // end of tokio spawn
 
-        tasks.push(task);
-    } // end of for loop
+            cancel_bundle_copies(builders, &copies[i + 1..]).await;
+            return our_txs_outcome(client, &receipt, tx_hashes).await;
+        }
 
-    // Await all tasks and check their results
-    let results: Vec<Result<Result<bool, anyhow::Error>, JoinError>> = futures::future::join_all(
-        tasks
-    ).await;
-
-    for task_result in results {
-        if let Ok(inner_result) = task_result {
-            if let Ok(included) = inner_result {
-                if included {
-                    is_bundle_included = true;
-                    break; // Exit the loop once a bundle is confirmed as included
-                }
+        let remaining = &copies[i + 1..];
+        let next_copy = match remaining.first() {
+            Some(next_copy) => next_copy,
+            None => {
+                break;
             }
+        };
+
+        // ** conditions may have changed, simulate the bundle again for the next target
+        let (next_block, fork_db) = fork_after_block(client.clone(), copy.target_block).await?;
+
+        if !is_bundle_valid(signed_txs, tx_hashes, tx_data, &next_block, fork_db).await? {
+            log::warn!("Cancelling the bundle copies from block {}", next_copy.target_block);
+            cancel_bundle_copies(builders, remaining).await;
+            break;
         }
     }
 
-    log::info!("Is Bundle Included: {:?}", is_bundle_included);
+    expired_outcome(client, last_target, tx_hashes, signer, nonce).await
+}
+
+// the outcome of our swap once it has a receipt
+// the receipts of our other txs are checked too, a bundle lands all or nothing
+async fn our_txs_outcome(
+    client: Arc<Provider<Ws>>,
+    swap_receipt: &TransactionReceipt,
+    tx_hashes: &[TxHash]
+) -> Result<TxOutcome, anyhow::Error> {
+    for tx_hash in &tx_hashes[1..] {
+        let outcome = match client.get_transaction_receipt(*tx_hash).await? {
+            Some(receipt) => receipt_outcome(&receipt),
+            None => TxOutcome::Unknown,
+        };
+
+        if outcome != TxOutcome::Landed {
+            log::warn!("Our tx {:?} next to the swap: {:?}", tx_hash, outcome);
+        }
+    }
 
-    Ok(is_bundle_included)
+    Ok(receipt_outcome(swap_receipt))
 }
 
-fn construct_bundle(
-    frontrun_or_backrun: U256,
-    signed_tx: Bytes,
-    signed_pending_tx: Bytes,
-    target_block: U64,
-    target_timestamp: u64
-) -> BundleRequest {
-    let mut bundle_request = BundleRequest::new();
+// decides what happened to our txs once every copy of the bundle expired
+// a cancelled copy may still have landed so we wait for the last target first
+// the nonces are only free if nothing was mined with them and nothing is pending with them
+async fn expired_outcome(
+    client: Arc<Provider<Ws>>,
+    last_target: U64,
+    tx_hashes: &[TxHash],
+    signer: Address,
+    nonce: U256
+) -> Result<TxOutcome, anyhow::Error> {
+    wait_for_block(client.clone(), last_target).await?;
 
-    //** frontrun_or backrun Legend
-    //** 0 = frontrun
-    //** 1 = backrun
-    //** 2 = normal sell
+    if let Some(receipt) = client.get_transaction_receipt(tx_hashes[0]).await? {
+        return our_txs_outcome(client, &receipt, tx_hashes).await;
+    }
 
-    // ** When we snipe we do backrun **
-    // ** When we normally sell we dont do frontrun or backrun **
-    // ** When we panic sell we do frontrun **
+    let mined_count = client.get_transaction_count(signer, Some(BlockNumber::Latest.into())).await?;
+    if mined_count > nonce {
+        return Ok(TxOutcome::Dropped { nonce_used: true });
+    }
 
-    // ** If we do frontrun we push our tx first
-    if frontrun_or_backrun == U256::zero() {
-        // ** First we push our tx
-        bundle_request = bundle_request.push_transaction(signed_tx);
+    let pending_count = client.get_transaction_count(signer, Some(BlockNumber::Pending.into())).await?;
+    if pending_count > nonce {
+        return Ok(TxOutcome::Unknown);
+    }
 
-        // ** Then we push the pending_tx
-        bundle_request = bundle_request.push_transaction(signed_pending_tx);
+    for tx_hash in tx_hashes {
+        if client.get_transaction(*tx_hash).await?.is_some() {
+            return Ok(TxOutcome::Unknown);
+        }
+    }
 
-        bundle_request = bundle_request
-            .set_block(target_block)
-            .set_simulation_block(target_block - 1)
-            .set_simulation_timestamp(target_timestamp)
-            .set_min_timestamp(target_timestamp)
-            .set_max_timestamp(target_timestamp);
+    Ok(TxOutcome::Dropped { nonce_used: false })
+}
 
-        bundle_request
-    } else if frontrun_or_backrun == U256::from(1u128) {
-        // ** If we do backrun we push the pending_tx first **
+// waits until the block is mined
+async fn wait_for_block(client: Arc<Provider<Ws>>, block_number: U64) -> Result<(), anyhow::Error> {
+    let started_at = Instant::now();
+    let timeout = Duration::from_secs(*INCLUSION_TIMEOUT_SECS);
 
-        // ** push the pending_tx
-        bundle_request = bundle_request.push_transaction(signed_pending_tx);
+    while client.get_block_number().await? < block_number {
+        if started_at.elapsed() > timeout {
+            return Err(anyhow!("Timed out waiting for block {}", block_number));
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
 
-        // ** Then we push our tx
-        bundle_request = bundle_request.push_transaction(signed_tx);
+    Ok(())
+}
 
-        bundle_request = bundle_request
-            .set_block(target_block)
-            .set_simulation_block(target_block - 1)
-            .set_simulation_timestamp(target_timestamp)
-            .set_min_timestamp(target_timestamp)
-            .set_max_timestamp(target_timestamp);
+// forks the state after a mined block, returns the info of the block after it too
+pub async fn fork_after_block(
+    client: Arc<Provider<Ws>>,
+    block_number: U64
+) -> Result<(BlockInfo, ForkDB), anyhow::Error> {
+    let block = client
+        .get_block(block_number).await?
+        .ok_or(anyhow!("Block {} not found", block_number))?;
+    let next_block = BlockInfo::find_next_block_info(block);
+
+    let fork_factory = ForkFactory::new_sandbox_factory(
+        client.clone(),
+        CacheDB::new(EmptyDB::default()),
+        Some(BlockId::Number(BlockNumber::Number(block_number)))
+    );
 
-        bundle_request
-    } else {
-        // ** Else if is 2 we do normal sell, we just push our tx
-        bundle_request = bundle_request.push_transaction(signed_tx);
+    Ok((next_block, fork_factory.new_sandbox_fork()))
+}
 
-        bundle_request = bundle_request
-            .set_block(target_block)
-            .set_simulation_block(target_block - 1)
-            .set_simulation_timestamp(target_timestamp)
-            .set_min_timestamp(target_timestamp)
-            .set_max_timestamp(target_timestamp);
+// a uuid for a copy of the bundle, derived from our tx and the target block
+fn replacement_uuid(tx_hash: TxHash, target_block: U64) -> String {
+    let mut seed = tx_hash.as_bytes().to_vec();
+    seed.extend_from_slice(&target_block.as_u64().to_be_bytes());
+    let hash = keccak256(seed);
 
-        bundle_request
-    }
+    let hex: String = hash[..16]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
 }
 
-fn get_all_urls() -> Vec<Url> {
-    let endpoints = vec![
-        "https://relay.flashbots.net/",
-        "http://builder0x69.io/",
-        "http://rpc.titanbuilder.xyz",
-        "https://api.edennetwork.io/v1/bundle",
-        "https://rpc.beaverbuild.org/",
-        "https://rpc.lightspeedbuilder.info/",
-        "https://eth-builder.com/",
-        "https://relay.ultrasound.money/",
-        "https://agnostic-relay.net/",
-        "https://relayooor.wtf/",
-        "https://rsync-builder.xyz/",
-        "https://buildai.net/",
-        "http://mainnet.aestus.live/",
-        "https://mainnet-relay.securerpc.com",
-        "https://builder.gmbit.co/rpc",
-        "https://mev.api.blxrbdn.com/",
-        "https://boba-builder.com/searcher/",
-        "https://blockbeelder.com/rpc",
-        "https://rpc.lokibuilder.xyz"
-    ];
-
-    let mut urls: Vec<Url> = vec![];
-
-    for endpoint in endpoints {
-        let url = Url::parse(endpoint).unwrap();
-        urls.push(url);
-    }
-
-    urls
+// builds the bundle from the signed txs, in the order they are given
+// the target timestamp assumes no missed slot so it is only a lower bound
+// set_block does the targeting, a max timestamp would void the later copies after a missed slot
+fn construct_bundle(signed_txs: Vec<Bytes>, target_block: U64, target_timestamp: u64) -> BundleRequest {
+    let mut bundle_request = BundleRequest::new();
+
+    for signed_tx in signed_txs {
+        bundle_request = bundle_request.push_transaction(signed_tx);
+    }
+
+    bundle_request
+        .set_block(target_block)
+        .set_simulation_block(target_block - 1)
+        .set_simulation_timestamp(target_timestamp)
+        .set_min_timestamp(target_timestamp)
 }
@@ -15,6 +15,17 @@ use super::{
     BackendFetchRequest,
 };
 
+// Waits for the backend to answer
+// simulations run on the simulation executor threads where we can block freely,
+// only tell tokio about it if we are called from inside the runtime
+fn block_on_backend<R>(f: impl FnOnce() -> R) -> R {
+    if tokio::runtime::Handle::try_current().is_ok() {
+        tokio::task::block_in_place(f)
+    } else {
+        f()
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct ForkDB {
     // used to make calls for missing data
@@ -27,8 +38,34 @@ impl ForkDB {
         Self { backend, db }
     }
 
+    // Overrides the account info of an address, eg. to insert contract bytecode
+    pub fn insert_account_info(&mut self, address: rAddress, info: AccountInfo) {
+        self.db.insert_account_info(address, info);
+    }
+
+    // Overrides a single storage slot of an address
+    // the account info and the other slots are still fetched from the backend
+    pub fn insert_account_storage(
+        &mut self,
+        address: rAddress,
+        index: rU256,
+        value: rU256
+    ) -> DatabaseResult<()> {
+        // load the account first so the override doesnt wipe its code
+        if !self.db.accounts.contains_key(&address) {
+            if let Some(info) = self.do_get_basic(address)? {
+                self.db.insert_account_info(address, info);
+            }
+        }
+
+        // can unwrap safely as cacheDB always returns true
+        self.db.insert_account_storage(address, index, value).unwrap();
+
+        Ok(())
+    }
+
     fn do_get_basic(&self, address: rAddress) -> DatabaseResult<Option<AccountInfo>> {
-        tokio::task::block_in_place(|| {
+        block_on_backend(|| {
             let (sender, rx) = oneshot_channel();
             let req = BackendFetchRequest::Basic(address, sender);
             self.backend.clone().try_send(req)?;
@@ -37,7 +74,7 @@ impl ForkDB {
     }
 
     fn do_get_storage(&self, address: rAddress, index: rU256) -> DatabaseResult<rU256> {
-        tokio::task::block_in_place(|| {
+        block_on_backend(|| {
             let (sender, rx) = oneshot_channel();
             let req = BackendFetchRequest::Storage(address, index, sender);
             self.backend.clone().try_send(req)?;
@@ -46,7 +83,7 @@ impl ForkDB {
     }
 
     fn do_get_block_hash(&self, number: rU256) -> DatabaseResult<B256> {
-        tokio::task::block_in_place(|| {
+        block_on_backend(|| {
             let (sender, rx) = oneshot_channel();
             let req = BackendFetchRequest::BlockHash(number, sender);
             self.backend.clone().try_send(req)?;
@@ -5,9 +5,11 @@ pub use global_backend::*;
 
 pub mod fork_db;
 pub mod fork_factory;
+pub mod revert_decoder;
 
 use revm::primitives::{ExecutionResult, Output, Bytes};
 use anyhow::anyhow;
+use revert_decoder::decode_revert_reason;
 
 
 
@@ -20,7 +22,7 @@ pub fn match_output(result: ExecutionResult) -> Result<Bytes, anyhow::Error> {
                 Output::Create(o, _) => Ok(o.into()),
             }
         ExecutionResult::Revert { output, gas_used } => {
-            return Err(anyhow!("Call Reverted: {:?} Gas Used {}", bytes_to_string(output), gas_used));
+            return Err(anyhow!("Call Reverted: {} Gas Used {}", decode_revert_reason(&output), gas_used));
         }
         ExecutionResult::Halt { reason,.. } => {
             return Err(anyhow!("Halt Reason: {:?}", reason));
@@ -33,23 +35,10 @@ pub fn match_output_reverted(result: ExecutionResult) -> bool {
     let bool = match result {
          ExecutionResult::Success { .. } => false,
          ExecutionResult::Revert { output, .. } => {
-              log::error!("Call Reverted: {:?}", bytes_to_string(output));
+              log::error!("Call Reverted: {}", decode_revert_reason(&output));
              true
          }
          ExecutionResult::Halt { .. } => true,
      };
      bool
  }
-
-
- pub fn bytes_to_string(bytes: revm::primitives::Bytes) -> String {
-    if bytes.len() < 4 {
-        return "EVM Returned 0x (Empty Bytes)".to_string();
-    }
-    let error_data = &bytes[4..];
-
-    match String::from_utf8(error_data.to_vec()) {
-        Ok(s) => s.trim_matches(char::from(0)).to_string(),
-        Err(_) => "EVM Returned 0x (Empty Bytes)".to_string(),
-    }
-}
\ No newline at end of file
@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+use std::fs;
+use std::sync::RwLock;
+use ethers::types::{ I256, U256 };
+use ethers::utils::{ hex, keccak256 };
+use ethabi::{ param_type::Reader, ParamType, Token };
+use lazy_static::lazy_static;
+use anyhow::anyhow;
+
+use crate::utils::constants::ERROR_SIGNATURES_FILE;
+
+// Error(string)
+const ERROR_STRING_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+// Panic(uint256)
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+// A custom error we know how to decode
+#[derive(Debug, Clone)]
+struct ErrorSignature {
+    name: String,
+    params: Vec<ParamType>,
+}
+
+// ** Local database of custom error signatures, keyed by selector **
+lazy_static! {
+    static ref ERROR_SIGNATURES: RwLock<HashMap<[u8; 4], ErrorSignature>> = RwLock::new(
+        load_error_signatures()
+    );
+}
+
+// loads the signatures from the signatures file
+fn load_error_signatures() -> HashMap<[u8; 4], ErrorSignature> {
+    let mut signatures = HashMap::new();
+
+    let content = match fs::read_to_string(&*ERROR_SIGNATURES_FILE) {
+        Ok(content) => content,
+        Err(e) => {
+            log::warn!("Failed to load error signatures from {}: {}", *ERROR_SIGNATURES_FILE, e);
+            return signatures;
+        }
+    };
+
+    for line in content.lines() {
+        let line = line.trim();
+
+        // skip comments and empty lines
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        match parse_signature(line) {
+            Ok((selector, signature)) => {
+                signatures.insert(selector, signature);
+            }
+            Err(e) => log::warn!("Skipped error signature {}: {:?}", line, e),
+        }
+    }
+
+    signatures
+}
+
+// Adds a custom error signature to the database
+// eg. "MaxWalletExceeded(uint256,uint256)"
+#[allow(dead_code)]
+pub fn register_error_signature(signature: &str) -> Result<(), anyhow::Error> {
+    let (selector, signature) = parse_signature(signature)?;
+
+    let mut signatures = ERROR_SIGNATURES.write().map_err(|_| anyhow!("Error signatures lock poisoned"))?;
+    signatures.insert(selector, signature);
+
+    Ok(())
+}
+
+// parses "Name(type1,type2)" into its selector and param types
+fn parse_signature(signature: &str) -> Result<([u8; 4], ErrorSignature), anyhow::Error> {
+    let signature: String = signature.chars().filter(|c| !c.is_whitespace()).collect();
+
+    let open = signature.find('(').ok_or(anyhow!("Missing ("))?;
+    if !signature.ends_with(')') {
+        return Err(anyhow!("Missing )"));
+    }
+
+    let name = signature[..open].to_string();
+    let inner = &signature[open + 1..signature.len() - 1];
+
+    // split the params on the top level commas so tuples stay in one piece
+    let mut params = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in inner.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                params.push(Reader::read(&inner[start..i])?);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    if !inner.is_empty() {
+        params.push(Reader::read(&inner[start..])?);
+    }
+
+    let mut selector = [0u8; 4];
+    selector.copy_from_slice(&keccak256(signature.as_bytes())[0..4]);
+
+    Ok((selector, ErrorSignature { name, params }))
+}
+
+// Decodes the output of a reverted call into a readable reason
+//
+// Handles Error(string), Panic(uint256) and the custom errors in the signature database
+pub fn decode_revert_reason(output: &[u8]) -> String {
+    if output.is_empty() {
+        return "EVM Returned 0x (Empty Bytes)".to_string();
+    }
+
+    if output.len() < 4 {
+        return format!("Invalid revert data 0x{}", hex::encode(output));
+    }
+
+    let mut selector = [0u8; 4];
+    selector.copy_from_slice(&output[0..4]);
+    let data = &output[4..];
+
+    if selector == ERROR_STRING_SELECTOR {
+        if let Ok(tokens) = ethabi::decode(&[ParamType::String], data) {
+            if let Some(Token::String(reason)) = tokens.into_iter().next() {
+                return reason;
+            }
+        }
+        return format!("Invalid Error(string) 0x{}", hex::encode(output));
+    }
+
+    if selector == PANIC_SELECTOR {
+        if let Ok(tokens) = ethabi::decode(&[ParamType::Uint(256)], data) {
+            if let Some(Token::Uint(code)) = tokens.into_iter().next() {
+                return format!("Panic(0x{:02x}): {}", code, panic_reason(code));
+            }
+        }
+        return format!("Invalid Panic(uint256) 0x{}", hex::encode(output));
+    }
+
+    // look up the custom error
+    let signature = match ERROR_SIGNATURES.read() {
+        Ok(signatures) => signatures.get(&selector).cloned(),
+        Err(_) => None,
+    };
+
+    match signature {
+        Some(signature) =>
+            match ethabi::decode(&signature.params, data) {
+                Ok(tokens) => {
+                    let args: Vec<String> = tokens.iter().map(format_token).collect();
+                    format!("{}({})", signature.name, args.join(", "))
+                }
+                Err(_) => format!("{}(0x{})", signature.name, hex::encode(data)),
+            }
+        None =>
+            format!(
+                "Unknown custom error 0x{} data: 0x{}",
+                hex::encode(selector),
+                hex::encode(data)
+            ),
+    }
+}
+
+// https://docs.soliditylang.org/en/latest/control-structures.html#panic-via-assert-and-error-via-require
+fn panic_reason(code: U256) -> &'static str {
+    if code > U256::from(0xffu64) {
+        return "Unknown panic code";
+    }
+
+    match code.as_u64() {
+        0x00 => "Generic compiler panic",
+        0x01 => "Assertion failed",
+        0x11 => "Arithmetic overflow or underflow",
+        0x12 => "Division or modulo by zero",
+        0x21 => "Invalid enum value",
+        0x22 => "Invalid storage byte array encoding",
+        0x31 => "Pop on empty array",
+        0x32 => "Array index out of bounds",
+        0x41 => "Out of memory",
+        0x51 => "Call to zero-initialized function",
+        _ => "Unknown panic code",
+    }
+}
+
+fn format_token(token: &Token) -> String {
+    match token {
+        Token::Address(address) => format!("{:?}", address),
+        Token::Uint(value) => value.to_string(),
+        Token::Int(value) => I256::from_raw(*value).to_string(),
+        Token::Bool(value) => value.to_string(),
+        Token::String(value) => format!("{:?}", value),
+        Token::Bytes(value) | Token::FixedBytes(value) => format!("0x{}", hex::encode(value)),
+        Token::Array(tokens) | Token::FixedArray(tokens) => {
+            let items: Vec<String> = tokens.iter().map(format_token).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Token::Tuple(tokens) => {
+            let items: Vec<String> = tokens.iter().map(format_token).collect();
+            format!("({})", items.join(", "))
+        }
+    }
+}
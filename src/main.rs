@@ -95,11 +95,24 @@ let console = Dispatch::new()
         .level(LevelFilter::Error) 
         .chain(fern::log_file("errors.log")?);
 
+    // Decision log, why every new token was sniped or skipped with its token info
+    let decision_file = Dispatch::new()
+        .format(move |out, message, _record| {
+            out.finish(format_args!(
+                "{} {}",
+                Local::now().format("[%Y-%m-%d %H:%M:%S]"),
+                message
+            ))
+        })
+        .filter(|metadata| metadata.target() == "decisions")
+        .chain(fern::log_file("decisions.log")?);
+
     
     Dispatch::new()
         .chain(console) 
         .chain(file)    
         .chain(error_file) 
+        .chain(decision_file)
         .apply()?;
 
     Ok(())
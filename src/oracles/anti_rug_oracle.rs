@@ -11,9 +11,14 @@ use crate::{
 use crate::utils::constants::*;
 use crate::utils::helpers::*;
 use crate::utils::evm::simulate::
-    sim::{ generate_tx_data, simulate_sell, get_touched_pools};
+    sim::{ generate_tx_data, simulate_sell, seed_expected_balance, get_touched_pools, get_storage_changes };
 
 use crate::bot::send_tx::send_tx;
+use crate::utils::evm::executor::{ SIM_EXECUTOR, SimPriority };
+use crate::utils::types::structs::{ snipe_tx::SnipeTx, tx_data::TxData, bundle_plan::BundlePlan, tx_outcome::TxOutcome };
+use crate::utils::types::structs::storage_change::{ SlotKind, get_suspicious_pools };
+use crate::oracles::block_oracle::BlockInfo;
+use crate::forked_db::fork_db::ForkDB;
 
 
 
@@ -80,14 +85,17 @@ async fn process_anti_rug(
 
     // ** first see if the pending_tx touches one of the pools in the oracle
     // ** We want to check if the DeV is trying to rug by removing liquidity
-    let touched_pools = if
-        let Ok(Some(tp)) = get_touched_pools(
-            &pending_tx,
-            &next_block,
-            vec_pools,
-            fork_db.clone()
-        )
-    {
+    let touched_pools_result = {
+        let pending_tx = pending_tx.clone();
+        let next_block = next_block.clone();
+        let fork_db = fork_db.clone();
+
+        SIM_EXECUTOR.run(SimPriority::AntiRug, move || {
+            get_touched_pools(&pending_tx, &next_block, vec_pools, fork_db)
+        }).await
+    };
+
+    let touched_pools = if let Ok(Ok(Some(tp))) = touched_pools_result {
         tp
     } else {
         // log::info!("No pools touched");
@@ -108,21 +116,16 @@ async fn process_anti_rug(
 
             tokio::spawn(async move {
 
-                // ** get the amount_out in weth before the pending tx
-                let amount_out_before = simulate_sell(
-                    None,
-                    pool,
-                    next_block.clone(),
-                    fork_db.clone()
-                ).expect("Failed to simulate sell");
-
-                // ** get the amount_out after the pending tx
-                let amount_out_after = simulate_sell(
-                    Some(pending_tx.clone()),
-                    pool,
-                    next_block.clone(),
-                    fork_db.clone()
-                ).expect("Failed to simulate sell");
+                // ** get the amount_out in weth before and after the pending tx
+                let (amount_out_before, amount_out_after) = match
+                    simulate_sell_before_after(&pending_tx, pool, &snipe_txs, &next_block, &fork_db).await
+                {
+                    Ok(amounts) => amounts,
+                    Err(e) => {
+                        log::error!("Anti-Rug: Failed to simulate sell for {:?}: {:?}", pool.token_1, e);
+                        return;
+                    }
+                };
 
                 // ** EXTRA SAFE VERSION
                 // ** compare the amount_out_before and amount_out_after
@@ -156,19 +159,21 @@ async fn process_anti_rug(
                     }
 
                     // ** generate tx data
-                    let (tx_snipe, mut tx_data) = generate_tx_data(
-                        &pool,
-                        U256::zero(),
-                        &next_block,
-                        None,
-                        miner_tip,
-                        0, // frontrun
-                        false, // we sell
-                        fork_db
-                    ).expect("Failed to generate tx data");
-
-                    // add pending tx
-                    tx_data.pending_tx = pending_tx.clone();
+                    let (tx_snipe, tx_data) = match
+                        generate_escape_tx_data(
+                            pool,
+                            pending_tx.clone(),
+                            &next_block,
+                            miner_tip,
+                            fork_db.clone()
+                        ).await
+                    {
+                        Ok(data) => data,
+                        Err(e) => {
+                            log::error!("Anti-Rug: Failed to generate tx data: {:?}", e);
+                            return;
+                        }
+                    };
 
                     // ** First check if its worth it to frontrun the tx
                     if tx_snipe.gas_cost > tx_data.expected_amount {
@@ -182,30 +187,50 @@ async fn process_anti_rug(
 
                     // get the nonce
                     let mut bot_guard = bot.write().await;
-                    let nonce = bot_guard.get_nonce().await;
+                    let tx_count = tx_data.our_tx_count();
+                    let (signer, nonce) = bot_guard.get_nonces(true, tx_count).await;
                     drop(bot_guard);
 
                     // ** Send Tx
-                    let is_bundle_included = send_tx(
+                    let outcome = match send_tx(
                         client.clone(),
                         tx_data,
                         next_block,
                         miner_tip,
-                        nonce
-                    ).await.expect("Failed to send tx");
-
-                    if is_bundle_included {
-                        log::info!("Bundle included we escaped the rug pool!🚀");
-                        // ** find the corrosponding SnipeTx from the pool address
-                        let snipe_tx = snipe_txs
-                            .iter()
-                            .find(|&x| x.pool.address == pool.address)
-                            .unwrap();
+                        signer,
+                        nonce,
+                        fork_db
+                    ).await {
+                        Ok(outcome) => outcome,
+                        Err(e) => {
+                            // we cant tell if anything went out, the nonce is left for the sync
+                            log::error!("Anti-Rug: Failed to send tx: {:?}", e);
+                            TxOutcome::Unknown
+                        }
+                    };
 
-                        remove_tx_from_oracles(bot, snipe_tx.clone()).await;
-                    } else {
-                        log::warn!("Bundle not included, we are getting rugged! GG");
-                        return;
+                    match outcome {
+                        TxOutcome::Landed => {
+                            log::info!("Bundle included we escaped the rug pool!🚀");
+                            // ** find the corrosponding SnipeTx from the pool address
+                            let snipe_tx = snipe_txs
+                                .iter()
+                                .find(|&x| x.pool.address == pool.address)
+                                .unwrap();
+
+                            remove_tx_from_oracles(bot, snipe_tx.clone()).await;
+                        }
+                        TxOutcome::Unknown => {
+                            log::warn!("Anti-Rug: escape tx may still land, keeping the nonce");
+                        }
+                        TxOutcome::Dropped { .. } => {
+                            log::warn!("Bundle not included, we are getting rugged! GG");
+                            if outcome.frees_nonce() {
+                                let mut bot_guard = bot.write().await;
+                                bot_guard.release_nonces(signer, nonce, tx_count).await;
+                                drop(bot_guard);
+                            }
+                        }
                     }
                 }
             }); // end of tokio::spawn
@@ -266,55 +291,85 @@ async fn process_anti_honeypot(
         .map(|x| x.pool)
         .collect::<Vec<Pool>>();
 
-    // ** Check if pending_tx.to matches one of the token addresses in vec_pools
-    let is_pending_to_token = vec_pools.iter().any(|x| pending_tx.to == Some(x.token_1));
+    // ** get the block info from the oracle
+    let bot_guard = bot.read().await;
+    let (_, next_block) = bot_guard.get_block_info().await;
+    let fork_db = bot_guard.get_fork_db().await;
+    drop(bot_guard);
 
-    // ** Clone vars
-    let client = client.clone();
-    let bot = bot.clone();
+    // ** find which slots of our tokens and pools the pending tx changes
+    // ** this is cheap compared to the before and after sell simulation
+    let changes = {
+        let pending_tx = pending_tx.clone();
+        let next_block = next_block.clone();
+        let fork_db = fork_db.clone();
 
-    tokio::spawn(async move {
-        // ** if pending_tx.to matches one of the token addresses in vec_pools
-        if is_pending_to_token {
-            // ** get the pool that matches the pending_tx.to
-            let touched_pool = vec_pools
+        SIM_EXECUTOR.run(SimPriority::AntiRug, move || {
+            get_storage_changes(&pending_tx, &next_block, vec_pools, fork_db)
+        }).await??
+    };
+
+    // ** only simulate the sells for pools with suspicious changes
+    let suspicious_pools = get_suspicious_pools(&changes);
+
+    for touched_pool in suspicious_pools {
+        for change in changes.iter().filter(|x| x.pool == touched_pool) {
+            log::info!(
+                "Anti-HoneyPot: {:?} changes {:?} slot {:?} of {:?}: {:?} -> {:?}",
+                pending_tx.hash,
+                change.kind,
+                change.slot,
+                change.address,
+                change.old_value,
+                change.new_value
+            );
+        }
+
+        // ** Clone vars
+        let client = client.clone();
+        let bot = bot.clone();
+        let snipe_txs = snipe_txs.clone();
+        let pending_tx = pending_tx.clone();
+        let next_block = next_block.clone();
+        let fork_db = fork_db.clone();
+        let changes = changes.clone();
+
+        tokio::spawn(async move {
+            // ** simulate the sell tx before and after the pending tx
+            let (amount_out_before, amount_out_after) = match
+                simulate_sell_before_after(&pending_tx, touched_pool, &snipe_txs, &next_block, &fork_db).await
+            {
+                Ok(amounts) => amounts,
+                Err(e) => {
+                    log::error!(
+                        "Anti-HoneyPot: Failed to simulate sell for {:?}: {:?}",
+                        touched_pool.token_1,
+                        e
+                    );
+                    return;
+                }
+            };
+
+            // ** check if the pending tx lowers our token balance
+            let balance_change = changes
                 .iter()
-                .find(|x| pending_tx.to == Some(x.token_1))
-                .unwrap();
-
-            // get the blockinfo
-            let bot_guard = bot.read().await;
-            let (_, next_block) = bot_guard.get_block_info().await;
-            let fork_db = bot_guard.get_fork_db().await;
-            drop(bot_guard);
-
-            // ** First simulate the sell tx before the pending tx
-            let amount_out_before = simulate_sell(
-                None,
-                *touched_pool,
-                next_block.clone(),
-                fork_db.clone()
-            ).expect("Failed to simulate sell");
-
-            // ** get the amount_out in weth after the pending tx
-            // ** here we use the backend with the empty db
-            let amount_out_after = simulate_sell(
-                Some(pending_tx.clone()),
-                *touched_pool,
-                next_block.clone(),
-                fork_db.clone()
-            ).expect("Failed to simulate sell");
+                .find(|x| x.pool == touched_pool && x.kind == SlotKind::OurBalance)
+                .map(|x| (x.old_value, x.new_value));
+            let is_balance_lowered = matches!(balance_change, Some((before, after)) if after < before);
 
             // ** EXTRA SAFE VERSION
             // ** compare the amount_out_before and amount_out_after
             // ** if amount_out_after is at least 20% less than amount_out_before
-            // ** Frontrun the pending tx
+            // ** or our balance got lowered, Frontrun the pending tx
 
-            if amount_out_after < (amount_out_before * 8) / 10 {
+            if amount_out_after < (amount_out_before * 8) / 10 || is_balance_lowered {
                 log::info!("Anti-HoneyPot Alert!🚨 Possible rug detected!");
                 log::info!("Detected Tx Hash: {:?}", pending_tx.hash);
                 log::info!("Amount out Before: ETH {:?}", convert_wei_to_ether(amount_out_before));
                 log::info!("Amount out After: ETH {:?}", convert_wei_to_ether(amount_out_after));
+                if let Some((before, after)) = balance_change {
+                    log::info!("Token Balance Before: {:?} After: {:?}", before, after);
+                }
 
                 let pending_tx_priority_fee = match pending_tx.transaction_type {
                     Some(t) if t == U64::from(2) => pending_tx.max_priority_fee_per_gas.unwrap_or_default(),
@@ -331,19 +386,21 @@ async fn process_anti_honeypot(
                 }
 
                 // ** generate tx data
-                let (tx_snipe, mut tx_data) = generate_tx_data(
-                    touched_pool,
-                    U256::zero(),
-                    &next_block,
-                    None,
-                    miner_tip,
-                    0, // frontrun
-                    false, // we sell
-                    fork_db
-                ).expect("Failed to generate tx data");
-
-                // add pending tx
-                tx_data.pending_tx = pending_tx.clone();
+                let (tx_snipe, tx_data) = match
+                    generate_escape_tx_data(
+                        touched_pool,
+                        pending_tx.clone(),
+                        &next_block,
+                        miner_tip,
+                        fork_db.clone()
+                    ).await
+                {
+                    Ok(data) => data,
+                    Err(e) => {
+                        log::error!("Anti-HoneyPot: Failed to generate tx data: {:?}", e);
+                        return;
+                    }
+                };
 
                 // ** First check if its worth it to frontrun the tx
                 if tx_snipe.gas_cost > tx_data.expected_amount {
@@ -357,34 +414,132 @@ async fn process_anti_honeypot(
 
                 // get the nonce
                 let mut bot_guard = bot.write().await;
-                let nonce = bot_guard.get_nonce().await;
+                let tx_count = tx_data.our_tx_count();
+                let (signer, nonce) = bot_guard.get_nonces(true, tx_count).await;
                 drop(bot_guard);
 
                 // ** Send Tx
-                let is_bundle_included = send_tx(
+                let outcome = match send_tx(
                     client.clone(),
                     tx_data,
                     next_block,
                     miner_tip,
-                    nonce
-                ).await.expect("Failed to send tx");
-
-                if is_bundle_included {
-                    log::info!("Bundle included we escaped the rug pool!🚀");
-                    // ** find the corrosponding SnipeTx from the pool address
-                    let snipe_tx = snipe_txs
-                        .iter()
-                        .find(|&x| x.pool.address == touched_pool.address)
-                        .unwrap();
-
-                    remove_tx_from_oracles(bot, snipe_tx.clone()).await;
-                } else {
-                    log::warn!("Bundle not included, we are getting rugged! GG");
-                    return;
+                    signer,
+                    nonce,
+                    fork_db
+                ).await {
+                    Ok(outcome) => outcome,
+                    Err(e) => {
+                        // we cant tell if anything went out, the nonce is left for the sync
+                        log::error!("Anti-HoneyPot: Failed to send tx: {:?}", e);
+                        TxOutcome::Unknown
+                    }
+                };
+
+                match outcome {
+                    TxOutcome::Landed => {
+                        log::info!("Bundle included we escaped the rug pool!🚀");
+                        // ** find the corrosponding SnipeTx from the pool address
+                        let snipe_tx = snipe_txs
+                            .iter()
+                            .find(|&x| x.pool.address == touched_pool.address)
+                            .unwrap();
+
+                        remove_tx_from_oracles(bot, snipe_tx.clone()).await;
+                    }
+                    TxOutcome::Unknown => {
+                        log::warn!("Anti-Rug: escape tx may still land, keeping the nonce");
+                    }
+                    TxOutcome::Dropped { .. } => {
+                        log::warn!("Bundle not included, we are getting rugged! GG");
+                        if outcome.frees_nonce() {
+                            let mut bot_guard = bot.write().await;
+                            bot_guard.release_nonces(signer, nonce, tx_count).await;
+                            drop(bot_guard);
+                        }
+                    }
                 }
             } // end of if amount_out_after < (amount_out_before * 8) / 10
-        } // end of if is_pending_to_token
-    }); // end of tokio::spawn
+        }); // end of tokio::spawn
+    } // end of for loop
 
     Ok(())
 }
+
+// ** simulates our sell before and after the pending tx on the simulation executor
+// ** while our buy is in flight we sell the tokens we expect from it
+async fn simulate_sell_before_after(
+    pending_tx: &Transaction,
+    pool: Pool,
+    snipe_txs: &[SnipeTx],
+    next_block: &BlockInfo,
+    fork_db: &ForkDB
+) -> Result<(U256, U256), anyhow::Error> {
+    let expected_tokens = snipe_txs
+        .iter()
+        .find(|x| x.pool.address == pool.address)
+        .map(|x| x.expected_amount_of_tokens)
+        .unwrap_or_default();
+
+    let fork_db = {
+        let next_block = next_block.clone();
+        let fork_db = fork_db.clone();
+        SIM_EXECUTOR.run(SimPriority::AntiRug, move || {
+            match seed_expected_balance(pool, expected_tokens, next_block, fork_db.clone()) {
+                Ok(seeded) => seeded,
+                Err(e) => {
+                    log::warn!("Failed to seed the expected balance of {:?}: {:?}", pool.token_1, e);
+                    fork_db
+                }
+            }
+        }).await?
+    };
+    let fork_db = &fork_db;
+
+    let before = {
+        let next_block = next_block.clone();
+        let fork_db = fork_db.clone();
+        SIM_EXECUTOR.run(SimPriority::AntiRug, move || {
+            simulate_sell(None, pool, next_block, fork_db)
+        })
+    };
+
+    let after = {
+        let pending_tx = pending_tx.clone();
+        let next_block = next_block.clone();
+        let fork_db = fork_db.clone();
+        SIM_EXECUTOR.run(SimPriority::AntiRug, move || {
+            simulate_sell(Some(pending_tx), pool, next_block, fork_db)
+        })
+    };
+
+    // run both simulations in parallel
+    let (before, after) = tokio::join!(before, after);
+
+    Ok((before??, after??))
+}
+
+// ** generates the tx data to frontrun the pending tx on the simulation executor
+async fn generate_escape_tx_data(
+    pool: Pool,
+    pending_tx: Transaction,
+    next_block: &BlockInfo,
+    miner_tip: U256,
+    fork_db: ForkDB
+) -> Result<(SnipeTx, TxData), anyhow::Error> {
+    let next_block = next_block.clone();
+
+    // no profit is set, so a coinbase payment for the rescue is capped to what the miner tip costs
+    SIM_EXECUTOR.run(SimPriority::AntiRug, move || {
+        generate_tx_data(
+            &pool,
+            U256::zero(),
+            &next_block,
+            BundlePlan::frontrun(vec![pending_tx]),
+            miner_tip,
+            false, // we sell
+            None,
+            fork_db
+        )
+    }).await?
+}
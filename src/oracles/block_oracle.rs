@@ -23,7 +23,6 @@ impl BlockInfo {
         }
     }
 
-    #[allow(dead_code)]
     // Find the next block ahead of `prev_block`
     pub fn find_next_block_info(prev_block: Block<TxHash>) -> Self {
         let number = prev_block.number.unwrap_or_default() + 1;
@@ -0,0 +1,33 @@
+use tokio::sync::RwLock;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+use crate::utils::types::structs::oracles::CompetitorOracle;
+use crate::utils::types::events::MemPoolEvent;
+
+
+
+// Collects pending swaps from other bots that target the new pools we are sniping
+pub fn start_competitor_oracle(
+    oracle: Arc<RwLock<CompetitorOracle>>,
+    mut new_mempool_receiver: broadcast::Receiver<MemPoolEvent>
+) {
+    tokio::spawn(async move {
+        loop {
+            while let Ok(event) = new_mempool_receiver.recv().await {
+                let tx = match event {
+                    MemPoolEvent::NewTx { tx } => tx,
+                };
+
+                // most of the time we dont watch any pool
+                if oracle.read().await.is_empty() {
+                    continue;
+                }
+
+                let mut oracle_guard = oracle.write().await;
+                oracle_guard.add_pending_tx(&tx);
+                drop(oracle_guard);
+            }
+        }
+    });
+}
@@ -1,8 +1,9 @@
-use crate::utils::types::structs::{ bot::Bot, snipe_tx::SnipeTx };
+use crate::utils::types::structs::{ bot::Bot, snipe_tx::SnipeTx, bundle_plan::BundlePlan, tx_outcome::TxOutcome };
 use ethers::prelude::*;
 use crate::oracles::block_oracle::BlockInfo;
 use crate::utils::evm::simulate::sim::{ generate_tx_data, profit_taker };
-use crate::bot::{ send_tx::send_tx, remove_tx_from_oracles };
+use crate::utils::evm::executor::{ SIM_EXECUTOR, SimPriority };
+use crate::bot::{ send_tx::send_tx, remove_tx_from_oracles, builder_registry::BUILDER_REGISTRY };
 use crate::utils::{ constants::*, helpers::* };
 
 use std::sync::Arc;
@@ -16,6 +17,7 @@ pub mod nonce_oracle;
 pub mod mempool_stream;
 pub mod pair_oracle;
 pub mod fork_db_oracle;
+pub mod competitor_oracle;
 
 // monitor the status of the oracles
 pub fn oracle_status(
@@ -32,6 +34,38 @@ pub fn oracle_status(
             drop(bot_guard);
 
             log::info!("Sell Oracle: {:?} txs", sell_oracle_txs);
+
+            // ** simulation executor metrics
+            for priority in SimPriority::ALL {
+                let metrics = SIM_EXECUTOR.metrics(priority);
+                log::info!(
+                    "Sim Executor {:?}: queued {}, completed {}, rejected {}, evicted {}, avg wait {:?}, max wait {:?}, avg exec {:?}",
+                    priority,
+                    SIM_EXECUTOR.queue_depth_for(priority),
+                    metrics.completed,
+                    metrics.rejected,
+                    metrics.evicted,
+                    metrics.avg_wait(),
+                    metrics.max_wait,
+                    metrics.avg_exec()
+                );
+            }
+
+            // ** builder statistics
+            for (name, stats) in BUILDER_REGISTRY.stats() {
+                if stats.total_requests == 0 {
+                    continue;
+                }
+                log::info!(
+                    "Builder {}: requests {}, errors {}%, avg latency {:?}, inclusions {}, dropped {}",
+                    name,
+                    stats.total_requests,
+                    (stats.error_rate_bps() as f64) / 100.0,
+                    stats.avg_latency(),
+                    stats.inclusions,
+                    stats.dropped_at.is_some()
+                );
+            }
         }
     });
 }
@@ -64,7 +98,7 @@ pub async fn time_check(
 
     let reserve_difference;
 
-    let current_reserve = get_reserves(snipe_tx.pool.address.clone(), client.clone()).await?;
+    let current_reserve = get_reserves(&snipe_tx.pool, client.clone()).await?;
 
     if is_10_min_passed {
         // ** if 10 mins passed, set the target reserve to 20% up
@@ -122,37 +156,67 @@ pub async fn take_profit(
     let amount_in = snipe_tx.amount_in + snipe_tx.gas_cost;
 
     // ** generate tx_data
-    let tx_data = profit_taker(
-        &next_block,
-        snipe_tx.pool,
-        amount_in,
-        fork_db
-    )?;
+    let mut tx_data = {
+        let next_block = next_block.clone();
+        let pool = snipe_tx.pool;
+        let fork_db = fork_db.clone();
+
+        SIM_EXECUTOR.run(SimPriority::SellOracle, move || {
+            profit_taker(&next_block, pool, amount_in, fork_db)
+        }).await??
+    };
+
+    // what we get back above the initial buy and its gas
+    tx_data.profit = Some(tx_data.expected_amount.saturating_sub(amount_in));
 
     // update tx to pending
     let mut bot_guard = bot.write().await;
     bot_guard.set_tx_is_pending(snipe_tx.clone(), true).await;
-    let nonce = bot_guard.get_nonce().await;
+    let tx_count = tx_data.our_tx_count();
+    let (signer, nonce) = bot_guard.get_nonces(false, tx_count).await;
     drop(bot_guard);
 
     // ** send the tx
-    let is_bundle_included = send_tx(client, tx_data.clone(), next_block, *MINER_TIP_TO_SELL, nonce).await?;
+    let outcome = send_tx(
+        client,
+        tx_data.clone(),
+        next_block,
+        *MINER_TIP_TO_SELL,
+        signer,
+        nonce,
+        fork_db
+    ).await?;
 
-    if is_bundle_included {
-        log::info!("Bundle included, took profit for {:?}", snipe_tx.pool.token_1);
-        log::info!("Expected amount: {}", convert_wei_to_ether(tx_data.expected_amount));
+    match outcome {
+        TxOutcome::Landed => {
+            log::info!("Bundle included, took profit for {:?}", snipe_tx.pool.token_1);
+            log::info!("Expected amount: {}", convert_wei_to_ether(tx_data.expected_amount));
 
-        // update status
-        let mut bot_guard = bot.write().await;
-        bot_guard.set_tx_is_pending(snipe_tx.clone(), false).await;
-        bot_guard.update_got_initial_out(snipe_tx, true).await;
-        drop(bot_guard);
-    } else {
-        let mut bot_guard = bot.write().await;
-        bot_guard.set_tx_is_pending(snipe_tx.clone(), false).await;
-        bot_guard.update_got_initial_out(snipe_tx, false).await;
-        drop(bot_guard);
-        return Err(anyhow!("Bundle not included, will try again in the next block"));
+            // update status
+            let mut bot_guard = bot.write().await;
+            bot_guard.set_tx_is_pending(snipe_tx.clone(), false).await;
+            bot_guard.update_got_initial_out(snipe_tx, true).await;
+            drop(bot_guard);
+        }
+        // we dont take the profit twice, the sell oracle sells the rest at the target
+        TxOutcome::Unknown => {
+            log::warn!("Profit taking tx for {:?} may still land", snipe_tx.pool.token_1);
+
+            let mut bot_guard = bot.write().await;
+            bot_guard.set_tx_is_pending(snipe_tx.clone(), false).await;
+            bot_guard.update_got_initial_out(snipe_tx, true).await;
+            drop(bot_guard);
+        }
+        TxOutcome::Dropped { .. } => {
+            let mut bot_guard = bot.write().await;
+            bot_guard.set_tx_is_pending(snipe_tx.clone(), false).await;
+            bot_guard.update_got_initial_out(snipe_tx, false).await;
+            if outcome.frees_nonce() {
+                bot_guard.release_nonces(signer, nonce, tx_count).await;
+            }
+            drop(bot_guard);
+            return Err(anyhow!("Bundle not included, will try again in the next block"));
+        }
     }
 
     Ok(())
@@ -172,16 +236,24 @@ pub async fn process_tx(
     drop(bot_guard);
 
     // ** generate tx_data
-    let (tx_snipe, tx_data) = generate_tx_data(
-        &snipe_tx.pool,
-        U256::zero(),
-        &next_block,
-        None,
-        *MINER_TIP_TO_SELL,
-        2, // no frontrun or backrun
-        false, // we sell
-        fork_db
-    ).expect("Failed to generate tx data");
+    let (tx_snipe, mut tx_data) = {
+        let next_block = next_block.clone();
+        let pool = snipe_tx.pool;
+        let fork_db = fork_db.clone();
+
+        SIM_EXECUTOR.run(SimPriority::SellOracle, move || {
+            generate_tx_data(
+                &pool,
+                U256::zero(),
+                &next_block,
+                BundlePlan::standalone(),
+                *MINER_TIP_TO_SELL,
+                false, // we sell
+                None,
+                fork_db
+            )
+        }).await??
+    };
 
     // ** First check if its worth it to sell it
     if tx_snipe.gas_cost > tx_data.expected_amount {
@@ -190,30 +262,49 @@ pub async fn process_tx(
         );
     }
 
+    // what we get back above the buy and its gas
+    let cost = snipe_tx.amount_in + snipe_tx.gas_cost;
+    tx_data.profit = Some(tx_data.expected_amount.saturating_sub(cost));
+
     // get the nonce and update it
     let mut bot_guard = bot.write().await;
-    let nonce = bot_guard.get_nonce().await;
+    let tx_count = tx_data.our_tx_count();
+    let (signer, nonce) = bot_guard.get_nonces(false, tx_count).await;
     drop(bot_guard);
 
     // ** Send The Tx
-    let is_bundle_included = send_tx(
+    let outcome = send_tx(
         client,
         tx_data.clone(),
         next_block,
         *MINER_TIP_TO_SELL,
-        nonce
+        signer,
+        nonce,
+        fork_db
     ).await?;
 
-    if is_bundle_included {
-        log::info!(
-            "Bundle included, sold token {:?} for {} ETH",
-            snipe_tx.pool.token_1,
-            convert_wei_to_ether(tx_data.expected_amount)
-        );
-        // ** remove the tx from the oracle
-        remove_tx_from_oracles(bot.clone(), snipe_tx.clone()).await;
-    } else {
-        return Err(anyhow!("Bundle not included, will try again in the next block"));
+    match outcome {
+        TxOutcome::Landed => {
+            log::info!(
+                "Bundle included, sold token {:?} for {} ETH",
+                snipe_tx.pool.token_1,
+                convert_wei_to_ether(tx_data.expected_amount)
+            );
+            // ** remove the tx from the oracle
+            remove_tx_from_oracles(bot.clone(), snipe_tx.clone()).await;
+        }
+        // if the sell lands there is nothing left to sell and the sell oracle drops the token
+        TxOutcome::Unknown => {
+            log::warn!("Sell tx for {:?} may still land", snipe_tx.pool.token_1);
+        }
+        TxOutcome::Dropped { .. } => {
+            if outcome.frees_nonce() {
+                let mut bot_guard = bot.write().await;
+                bot_guard.release_nonces(signer, nonce, tx_count).await;
+                drop(bot_guard);
+            }
+            return Err(anyhow!("Bundle not included, will try again in the next block"));
+        }
     }
 
     Ok(())
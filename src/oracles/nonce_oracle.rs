@@ -1,9 +1,9 @@
 use ethers::prelude::*;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use crate::utils::helpers::create_local_client;
+use crate::utils::helpers::{ create_local_client, convert_wei_to_ether };
+use crate::utils::constants::MIN_SIGNER_BALANCE;
 use crate::utils::types::structs::oracles::NonceOracle;
-use crate::utils::constants::CALLER_ADDRESS;
 
 
 use super::block_oracle::BlockInfo;
@@ -27,24 +27,54 @@ pub fn start_nonce_oracle(
 
             // start the nonce oracle by subscribing to new blocks
             while let Ok(latest_block) = new_block_receive.recv().await {
-                let block_id = Some(BlockId::Number(BlockNumber::Number(latest_block.number)));
+                let signers = oracle.read().await.get_signers();
 
-                let mut oracle_guard = oracle.write().await;
-                // get the nonce
-                let nonce = match client
-                    .get_transaction_count(*CALLER_ADDRESS, block_id).await {
-                        Ok(nonce) => nonce,
+                for signer in signers {
+                    // the pending count so txs in the mempool are not handed out again
+                    let pending_count = match
+                        client.get_transaction_count(signer, Some(BlockNumber::Pending.into())).await
+                    {
+                        Ok(count) => count,
                         Err(e) => {
                             // this should not happen
                             log::error!("Failed to get nonce: {}", e);
                             continue;
                         }
                     };
-                    
 
-                // update the nonce
-                oracle_guard.update_nonce(nonce);
-                drop(oracle_guard);
+                    // the mined count, a lower pending count means a tx is stuck in the mempool
+                    let mined_count = match
+                        client.get_transaction_count(signer, Some(BlockNumber::Latest.into())).await
+                    {
+                        Ok(count) => count,
+                        Err(e) => {
+                            log::error!("Failed to get nonce: {}", e);
+                            continue;
+                        }
+                    };
+
+                    let balance = match client.get_balance(signer, None).await {
+                        Ok(balance) => balance,
+                        Err(e) => {
+                            log::error!("Failed to get balance of {:?}: {}", signer, e);
+                            continue;
+                        }
+                    };
+
+                    if balance < *MIN_SIGNER_BALANCE {
+                        log::warn!(
+                            "Signer {:?} is low on ETH: {}",
+                            signer,
+                            convert_wei_to_ether(balance)
+                        );
+                    }
+
+                    // resync the signer and reclaim the nonces of expired txs
+                    let mut oracle_guard = oracle.write().await;
+                    oracle_guard.sync(signer, mined_count, pending_count, latest_block.number);
+                    oracle_guard.update_balance(signer, balance);
+                    drop(oracle_guard);
+                }
             } // end of while loop
         } // end of loop
     }); // end of tokio::spawn
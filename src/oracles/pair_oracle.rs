@@ -1,12 +1,18 @@
 use tokio::sync::broadcast::Sender;
 use tokio::sync::RwLock;
 use ethers::prelude::*;
+use ethabi::RawLog;
 use std::sync::Arc;
 use tokio::sync::broadcast;
+use revm::db::{ CacheDB, EmptyDB };
 
-use crate::utils::{ helpers::*, types::structs::{ pool::Pool, bot::Bot }, types::events::* };
+use crate::utils::{ helpers::*, types::structs::{ bot::Bot, pool::Pool, token_info::TokenInfo }, types::events::* };
 use crate::utils::constants::*;
-use crate::utils::evm::simulate::sim::get_pair;
+use crate::utils::abi::{ PAIR_CREATED_EVENT, MINT_EVENT, V3_INITIALIZE_EVENT, V3_MINT_EVENT };
+use crate::utils::evm::simulate::sim::{ get_pair, get_pair_in_block, get_token_checks, get_token_info };
+use crate::utils::evm::executor::{ SIM_EXECUTOR, SimPriority };
+use crate::forked_db::{ fork_factory::ForkFactory, fork_db::ForkDB };
+use super::block_oracle::BlockInfo;
 
 
 
@@ -18,6 +24,14 @@ pub fn start_pair_oracle(
 ) {
     tokio::spawn(async move {
         loop {
+            let client = match create_local_client().await {
+                Ok(client) => client,
+                Err(e) => {
+                    log::error!("Failed to create local client: {}", e);
+                    tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+                    continue;
+                }
+            };
 
             // define transfer method
             let transfer_id: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
@@ -41,70 +55,402 @@ pub fn start_pair_oracle(
                     continue;
                 }
 
-                // get the block info
-                let bot_guard = bot.read().await;
-                let (_, next_block) = bot_guard.get_block_info().await;
-                let fork_db = bot_guard.get_fork_db().await;
-                drop(bot_guard);
-
+                let bot = bot.clone();
+                let client = client.clone();
                 let new_pair_sender = new_pair_sender.clone();
 
-                // now we need to simulate the tx with revm to get the pair address from the logs
-                let (pool_address, weth, token_1, weth_reserve) = match
-                    get_pair(
-                        next_block,
-                        &tx,
-                        fork_db
-                    )
-                {
-                    Ok(address) => address,
-                    Err(_e) => {
-                        // log::error!(" {:?}", e);
-                        continue;
+                // ** simulate every tx concurrently on the simulation executor
+                tokio::spawn(async move {
+                    if let Err(e) = process_pending_tx(bot, client, new_pair_sender, tx).await {
+                        log::error!("Pair Oracle Err {:?}", e);
                     }
-                };
+                });
+            }
+        } // end of main loop
+    });
+}
 
-                if pool_address == Address::zero() {
-                    continue;
-                }
+async fn process_pending_tx(
+    bot: Arc<RwLock<Bot>>,
+    client: Arc<Provider<Ws>>,
+    new_pair_sender: Sender<NewPairEvent>,
+    tx: Transaction
+) -> Result<(), anyhow::Error> {
+    // get the block info
+    let bot_guard = bot.read().await;
+    let (_, next_block) = bot_guard.get_block_info().await;
+    let fork_db = bot_guard.get_fork_db().await;
+    let initialized_pools = bot_guard.get_initialized_pools().await;
+    drop(bot_guard);
 
-                // adjust these numbers as you like
-                if weth_reserve < *MIN_WETH_RESERVE {
-                    log::error!(
-                        "Weth Reserve < {:?} MIN_WETH Token Address:{:?}",
-                        convert_wei_to_ether(*MIN_WETH_RESERVE),
-                        token_1
-                    );
-                    continue;
-                }
+    // now we need to simulate the tx with revm to get the pair address from the logs
+    let pool = {
+        let next_block = next_block.clone();
+        let fork_db = fork_db.clone();
+        let tx = tx.clone();
+
+        match
+            SIM_EXECUTOR.run(SimPriority::PairDiscovery, move || {
+                get_pair(next_block, &tx, initialized_pools, fork_db)
+            }).await?
+        {
+            Ok(Some(pool)) => pool,
+            Ok(None) => {
+                return Ok(());
+            }
+            Err(_e) => {
+                // log::error!(" {:?}", e);
+                return Ok(());
+            }
+        }
+    };
+
+    process_new_pool(bot, client, new_pair_sender, pool, tx.from, tx.hash, Some(tx), fork_db).await
+}
 
-                if weth_reserve > *MAX_WETH_RESERVE {
-                    log::error!(
-                        "Weth Reserve > {:?} MAX_WETH Token Address {:?}",
-                        convert_wei_to_ether(*MAX_WETH_RESERVE),
-                        token_1
-                    );
+// Monitor new blocks for new pairs created
+// catches the pairs from txs that never were in the public mempool
+pub fn start_block_pair_oracle(
+    bot: Arc<RwLock<Bot>>,
+    new_pair_sender: Sender<NewPairEvent>,
+    mut new_block_receiver: broadcast::Receiver<BlockInfo>
+) {
+    if !*ENABLE_BLOCK_PAIR_DISCOVERY {
+        return;
+    }
+
+    tokio::spawn(async move {
+        loop {
+            let client = match create_local_client().await {
+                Ok(client) => client,
+                Err(e) => {
+                    log::error!("Failed to create local client: {}", e);
+                    tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
                     continue;
                 }
+            };
+
+            while let Ok(latest_block) = new_block_receiver.recv().await {
+                let bot = bot.clone();
+                let client = client.clone();
+                let new_pair_sender = new_pair_sender.clone();
 
-                // create a new pool
-                // token_a is always weth
-                let pool = Pool::new(
-                    pool_address,
-                    weth, // token_0 is always weth
-                    token_1, // token_1 is the shitcoin
-                    weth_reserve
-                );
-
-                log::info!("New Pool Found!🚀");
-                log::info!("Pool Address: {:?}", pool.address);
-                log::info!("Token Address: {:?}", pool.token_1);
-
-                // send the new pair event
-                new_pair_sender
-                    .send(NewPairEvent::NewPairWithTx { pool: pool, tx: tx.clone() })
-                    .unwrap();
+                tokio::spawn(async move {
+                    if let Err(e) = process_block(bot, client, new_pair_sender, latest_block).await {
+                        log::error!("Block Pair Oracle Err {:?}", e);
+                    }
+                });
             }
         } // end of main loop
     });
 }
+
+async fn process_block(
+    bot: Arc<RwLock<Bot>>,
+    client: Arc<Provider<Ws>>,
+    new_pair_sender: Sender<NewPairEvent>,
+    latest_block: BlockInfo
+) -> Result<(), anyhow::Error> {
+    // ** find the txs that could have created a pool
+    // ** Sync is emitted by every swap so we only filter on the creation events
+    let creation_topics = vec![
+        Some(PAIR_CREATED_EVENT.signature()),
+        Some(MINT_EVENT.signature()),
+        Some(V3_INITIALIZE_EVENT.signature()),
+        Some(V3_MINT_EVENT.signature())
+    ];
+    let filter = Filter::new()
+        .select(latest_block.number)
+        .topic0(ValueOrArray::Array(creation_topics));
+    let logs = client.get_logs(&filter).await?;
+
+    // ** remember the initialized V3 pools, their first liquidity can come in a later tx
+    // only mined pools are tracked, a pending tx that initializes a pool may never land
+    // a pool minted in the same tx is already complete
+    let is_event = |log: &Log, event: &ethabi::Event| log.topics.first() == Some(&event.signature());
+    let newly_initialized: Vec<Address> = logs
+        .iter()
+        .filter(|log| is_event(log, &V3_INITIALIZE_EVENT))
+        .filter(|init| {
+            !logs
+                .iter()
+                .any(|log| {
+                    is_event(log, &V3_MINT_EVENT) &&
+                        log.address == init.address &&
+                        log.transaction_hash == init.transaction_hash
+                })
+        })
+        .map(|log| log.address)
+        .collect();
+
+    let mut bot_guard = bot.write().await;
+    bot_guard.mark_pools_initialized(newly_initialized, latest_block.number).await;
+    let initialized_pools = bot_guard.get_initialized_pools().await;
+    drop(bot_guard);
+
+    let mut tx_hashes: Vec<TxHash> = Vec::new();
+    for log in logs {
+        // every V3 liquidity add emits a Mint, we only want the ones on the initialized pools
+        let is_v3_mint = is_event(&log, &V3_MINT_EVENT);
+        if is_v3_mint && !initialized_pools.contains(&log.address) {
+            continue;
+        }
+
+        if let Some(hash) = log.transaction_hash {
+            if !tx_hashes.contains(&hash) {
+                tx_hashes.push(hash);
+            }
+        }
+    }
+
+    if tx_hashes.is_empty() {
+        return Ok(());
+    }
+
+    // fork at the block so the pools are already created
+    let cache_db = CacheDB::new(EmptyDB::default());
+    let fork_factory = ForkFactory::new_sandbox_factory(
+        client.clone(),
+        cache_db,
+        Some(BlockId::Number(BlockNumber::Number(latest_block.number)))
+    );
+    let fork_db = fork_factory.new_sandbox_fork();
+
+    let bot_guard = bot.read().await;
+    let (_, next_block) = bot_guard.get_block_info().await;
+    drop(bot_guard);
+
+    for hash in tx_hashes {
+        // the receipt has all the logs of the tx and the deployer
+        let receipt = match client.get_transaction_receipt(hash).await? {
+            Some(receipt) => receipt,
+            None => {
+                continue;
+            }
+        };
+
+        let logs: Vec<(Address, RawLog)> = receipt.logs
+            .iter()
+            .map(|log| (log.address, RawLog { topics: log.topics.clone(), data: log.data.to_vec() }))
+            .collect();
+
+        let pool = {
+            let next_block = next_block.clone();
+            let initialized_pools = initialized_pools.clone();
+            let fork_db = fork_db.clone();
+
+            match
+                SIM_EXECUTOR.run(SimPriority::PairDiscovery, move || {
+                    get_pair_in_block(next_block, &logs, initialized_pools, fork_db)
+                }).await?
+            {
+                Ok(Some(pool)) => pool,
+                Ok(None) | Err(_) => {
+                    continue;
+                }
+            }
+        };
+
+        log::info!("Found Pool {:?} in Block {}", pool.address, latest_block.number);
+
+        if
+            let Err(e) = process_new_pool(
+                bot.clone(),
+                client.clone(),
+                new_pair_sender.clone(),
+                pool,
+                receipt.from,
+                hash,
+                None,
+                fork_db.clone()
+            ).await
+        {
+            log::error!("Block Pair Oracle Err {:?}", e);
+        }
+    }
+
+    Ok(())
+}
+
+// Runs the filters and the token checks on a new pool and sends it to the sniper
+// pending_tx is None if the pool was found in a block, then fork_db must be forked at that block
+async fn process_new_pool(
+    bot: Arc<RwLock<Bot>>,
+    client: Arc<Provider<Ws>>,
+    new_pair_sender: Sender<NewPairEvent>,
+    pool: Pool,
+    deployer: Address,
+    pair_tx: TxHash,
+    pending_tx: Option<Transaction>,
+    fork_db: ForkDB
+) -> Result<(), anyhow::Error> {
+    let bot_guard = bot.read().await;
+    let (latest_block, next_block) = bot_guard.get_block_info().await;
+    drop(bot_guard);
+
+    // ** the mempool and the block logs can both find the pool, only process it once
+    let mut bot_guard = bot.write().await;
+    let is_new_pool = bot_guard.mark_pool_seen(pool.address, next_block.number).await;
+    drop(bot_guard);
+
+    if !is_new_pool {
+        return Ok(());
+    }
+
+    // adjust these numbers as you like
+    // ** token_0 is the base token, token_1 is the shitcoin
+    if pool.weth_liquidity < *MIN_WETH_RESERVE {
+        log::error!(
+            "Weth Reserve < {:?} MIN_WETH Token Address:{:?}",
+            convert_wei_to_ether(*MIN_WETH_RESERVE),
+            pool.token_1
+        );
+        return Ok(());
+    }
+
+    if pool.weth_liquidity > *MAX_WETH_RESERVE {
+        log::error!(
+            "Weth Reserve > {:?} MAX_WETH Token Address {:?}",
+            convert_wei_to_ether(*MAX_WETH_RESERVE),
+            pool.token_1
+        );
+        return Ok(());
+    }
+
+    // ** start collecting other bots swaps on the pool while we run the checks
+    if *ENABLE_COMPETITOR_DETECTION {
+        let mut bot_guard = bot.write().await;
+        bot_guard.watch_pool(pool, pair_tx).await;
+        drop(bot_guard);
+    }
+
+    // ** get the metadata, the top holders and the deployer's funding source
+    // the holder scan is rpc bound so it runs next to the token checks instead of before them
+    let token_info = get_pool_token_info(
+        client.clone(),
+        pool,
+        deployer,
+        latest_block.number,
+        next_block.clone(),
+        pending_tx.clone(),
+        fork_db.clone()
+    );
+
+    // ** check ownership, who holds the LP tokens and the deployer share
+    let token_checks = {
+        let next_block = next_block.clone();
+        let pending_tx = pending_tx.clone();
+
+        SIM_EXECUTOR.run(SimPriority::PairDiscovery, move || {
+            get_token_checks(&pool, deployer, &next_block, pending_tx, fork_db)
+        })
+    };
+
+    let (token_info, token_checks) = tokio::join!(token_info, token_checks);
+
+    let token_info = match token_info {
+        Ok(info) => info,
+        Err(e) => {
+            log::error!("Token Info Failed for {:?}: {:?}", pool.token_1, e);
+            return Ok(());
+        }
+    };
+
+    log::info!("New Pool Found!🚀");
+    log::info!("Pool Address: {:?} ({}, fee {})", pool.address, pool.dex_name(), pool.fee());
+    log::info!("Token: {}", token_info.label());
+    log::info!(
+        "Decimals: {:?}, Total Supply: {}, Top {} Holders: {}%, Funded By: {:?}",
+        token_info.decimals,
+        token_info.total_supply,
+        token_info.top_holders.len(),
+        (token_info.top_holders_share_bps as f64) / 100.0,
+        token_info.funding_source.as_ref().and_then(|x| x.funder)
+    );
+
+    let token_checks = match token_checks? {
+        Ok(checks) => checks,
+        Err(e) => {
+            log::error!("Token Checks Failed for {}: {:?}", token_info.label(), e);
+            token_info.log_decision("SKIP", &format!("token checks failed: {}", e));
+            return Ok(());
+        }
+    };
+
+    log::info!(
+        "Owner: {:?}, Renounced: {}, LP: {:?}, Deployer Share: {}%",
+        token_checks.owner,
+        token_checks.is_renounced,
+        token_checks.lp_status,
+        (token_checks.deployer_share_bps as f64) / 100.0
+    );
+
+    if let Err(e) = token_checks.check_filters(next_block.timestamp) {
+        log::error!("Token {} skipped: {}", token_info.label(), e);
+        token_info.log_decision("SKIP", &e.to_string());
+        return Ok(());
+    }
+
+    if let Err(e) = token_info.check_filters() {
+        log::error!("Token {} skipped: {}", token_info.label(), e);
+        token_info.log_decision("SKIP", &e.to_string());
+        return Ok(());
+    }
+
+    token_info.log_decision("PASS", "sent to the sniper");
+
+    // ** the txs that can open trading later come from these addresses
+    let mut launchers = vec![deployer];
+    if let Some(owner) = token_checks.owner {
+        if !token_checks.is_renounced && owner != deployer {
+            launchers.push(owner);
+        }
+    }
+
+    // send the new pair event
+    let event = match pending_tx {
+        Some(tx) => NewPairEvent::NewPairWithTx { pool, tx, launchers, token_info },
+        None => NewPairEvent::NewPairInBlock { pool, launchers, token_info },
+    };
+    new_pair_sender.send(event)?;
+
+    Ok(())
+}
+
+// Enriches the token of a new pool
+// the holders come from the recent Transfer logs, the metadata and balances from the fork
+async fn get_pool_token_info(
+    client: Arc<Provider<Ws>>,
+    pool: Pool,
+    deployer: Address,
+    latest_block: U64,
+    next_block: BlockInfo,
+    pending_tx: Option<Transaction>,
+    fork_db: ForkDB
+) -> Result<TokenInfo, anyhow::Error> {
+    let from_block = latest_block.saturating_sub(U64::from(*HOLDER_SCAN_BLOCKS));
+    let mut holders = get_transfer_recipients(pool.token_1, from_block, latest_block, client.clone()).await?;
+
+    // the deployer usually holds the supply before the launch
+    if !holders.contains(&deployer) {
+        holders.push(deployer);
+    }
+
+    let mut token_info = SIM_EXECUTOR.run(SimPriority::PairDiscovery, move || {
+        get_token_info(&pool, holders, &next_block, pending_tx, fork_db)
+    }).await??;
+
+    if *ENABLE_FUNDING_SOURCE_LOOKUP {
+        token_info.funding_source = match
+            get_funding_source(deployer, latest_block, *FUNDING_LOOKBACK_BLOCKS, client.clone()).await
+        {
+            Ok(funding_source) => funding_source,
+            Err(e) => {
+                log::warn!("Funding Source Lookup Failed for {:?}: {:?}", deployer, e);
+                None
+            }
+        };
+    }
+
+    Ok(token_info)
+}
@@ -11,7 +11,8 @@ use super::{ time_check, take_profit, process_tx };
 
 use crate::utils::types::structs::bot::Bot;
 use crate::oracles::block_oracle::BlockInfo;
-use crate::utils::evm::simulate::sim::simulate_sell;
+use crate::utils::evm::simulate::sim::quote_sell;
+use crate::utils::evm::executor::{ SIM_EXECUTOR, SimPriority };
 
 
 
@@ -78,12 +79,41 @@ async fn process(
             let blocks_passed = latest_block.number - tx.block_bought;
 
             // ** get current amount out
-            let current_amount_out = simulate_sell(
-                None,
-                tx.pool,
-                next_block.clone(),
-                fork_db.clone()
-            ).expect("Failed to simulate sell");
+            // ** tokens with a fixed tax use the V2 math fast path, the rest run a full EVM sell
+            let quote = {
+                let snipe_tx = tx.clone();
+                let latest_block_number = latest_block.number;
+                let next_block = next_block.clone();
+                let fork_db = fork_db.clone();
+
+                match
+                    SIM_EXECUTOR.run(SimPriority::SellOracle, move || {
+                        quote_sell(&snipe_tx, latest_block_number, next_block, fork_db)
+                    }).await
+                {
+                    Ok(Ok(quote)) => quote,
+                    Ok(Err(e)) | Err(e) => {
+                        log::error!("Failed to simulate sell for {:?}: {:?}", tx.pool.token_1, e);
+                        return;
+                    }
+                }
+            };
+            let current_amount_out = quote.amount_out;
+
+            // ** keep the measured tax and the last quote for the next block
+            let mut bot_guard = bot.write().await;
+            if quote.from_evm {
+                bot_guard.record_evm_sell(
+                    tx.clone(),
+                    quote.sell_tax_bps,
+                    quote.balance_slot,
+                    current_amount_out,
+                    latest_block.number
+                ).await;
+            } else {
+                bot_guard.record_fast_sell(tx.clone(), current_amount_out).await;
+            }
+            drop(bot_guard);
 
             // ** see if we got taxed
             if current_amount_out < (tx.amount_in * 9) / 100 {
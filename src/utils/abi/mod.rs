@@ -3,24 +3,107 @@ use ethers::abi::parse_abi;
 use ethers::utils::keccak256;
 use std::fs;
 use lazy_static::lazy_static;
+use crate::utils::types::structs::pool::{ Pool, PoolKind };
+use crate::utils::constants::{ WETH, BASE_PAIR_FEE };
 
 
+// encodes a swap on the pool, V3 pools go through the entry point with the swap callback
+// V2 swaps pass the fork's fee so the contract can compute the amount out
 pub fn encode_swap(
     input_token: Address,
     output_token: Address,
-    pool_address: Address,
+    pool: &Pool,
     amount_in: U256,
     expected_amount: U256
 ) -> Vec<u8> {
     // The method's signature hash (first 4 bytes of the keccak256 hash of the signature).
-    let method_id = &keccak256(b"snipaaaaaa(address,address,address,uint256,uint256)")[0..4];
+    let signature: &[u8] = match pool.kind {
+        PoolKind::UniswapV2 { .. } =>
+            b"snipaaaaaa(address,address,address,uint256,uint256,uint24)",
+        PoolKind::UniswapV3 { .. } => b"snipaaaaaa_v3(address,address,address,uint256,uint256)",
+    };
+    let method_id = &keccak256(signature)[0..4];
+
+    // ABI-encode the arguments
+    let mut args = vec![
+        ethabi::Token::Address(input_token),
+        ethabi::Token::Address(output_token),
+        ethabi::Token::Address(pool.address),
+        ethabi::Token::Uint(amount_in),
+        ethabi::Token::Uint(expected_amount)
+    ];
+
+    if !pool.is_v3() {
+        args.push(ethabi::Token::Uint(pool.fee().into()));
+    }
+
+    let encoded_args = ethabi::encode(&args);
+
+    let mut payload = vec![];
+    payload.extend_from_slice(method_id);
+    payload.extend_from_slice(&encoded_args);
+
+    payload
+}
+
+// encodes a buy of the pool's token with weth
+// pools quoted in another base token are bought through the base token's weth pair
+pub fn encode_buy(pool: &Pool, amount_in: U256, minimum_received: U256) -> Vec<u8> {
+    match pool.base_pair() {
+        Some(base_pair) =>
+            encode_routed_swap(
+                [*WETH, pool.token_0, pool.token_1],
+                [base_pair, pool.address],
+                [false, pool.is_v3()],
+                [*BASE_PAIR_FEE, pool.fee()],
+                amount_in,
+                minimum_received
+            ),
+        None => encode_swap(*WETH, pool.token_1, pool, amount_in, minimum_received),
+    }
+}
+
+// encodes a sell of the pool's token for weth
+pub fn encode_sell(pool: &Pool, amount_in: U256, minimum_received: U256) -> Vec<u8> {
+    match pool.base_pair() {
+        Some(base_pair) =>
+            encode_routed_swap(
+                [pool.token_1, pool.token_0, *WETH],
+                [pool.address, base_pair],
+                [pool.is_v3(), false],
+                [pool.fee(), *BASE_PAIR_FEE],
+                amount_in,
+                minimum_received
+            ),
+        None => encode_swap(pool.token_1, *WETH, pool, amount_in, minimum_received),
+    }
+}
+
+// encodes a two hop swap, tokens[0] -> tokens[1] on pools[0] then tokens[1] -> tokens[2] on pools[1]
+// fees are only used by the V2 hops
+pub fn encode_routed_swap(
+    tokens: [Address; 3],
+    pools: [Address; 2],
+    is_v3: [bool; 2],
+    fees: [u32; 2],
+    amount_in: U256,
+    expected_amount: U256
+) -> Vec<u8> {
+    // The method's signature hash (first 4 bytes of the keccak256 hash of the signature).
+    let method_id = &keccak256(b"snipaaaaaa_via(address[3],address[2],bool[2],uint24[2],uint256,uint256)")[0..4];
 
     // ABI-encode the arguments
     let encoded_args = ethabi::encode(
         &[
-            ethabi::Token::Address(input_token),
-            ethabi::Token::Address(output_token),
-            ethabi::Token::Address(pool_address),
+            ethabi::Token::FixedArray(tokens.iter().map(|x| ethabi::Token::Address(*x)).collect()),
+            ethabi::Token::FixedArray(pools.iter().map(|x| ethabi::Token::Address(*x)).collect()),
+            ethabi::Token::FixedArray(is_v3.iter().map(|x| ethabi::Token::Bool(*x)).collect()),
+            ethabi::Token::FixedArray(
+                fees
+                    .iter()
+                    .map(|x| ethabi::Token::Uint((*x).into()))
+                    .collect()
+            ),
             ethabi::Token::Uint(amount_in),
             ethabi::Token::Uint(expected_amount),
         ]
@@ -33,6 +116,24 @@ pub fn encode_swap(
     payload
 }
 
+// wraps the call data of any swap above so the contract pays the builder after it
+// the payment is only sent if the swap succeeds
+pub fn encode_swap_and_pay(swap_call_data: Vec<u8>, coinbase_payment: U256) -> Vec<u8> {
+    // The method's signature hash (first 4 bytes of the keccak256 hash of the signature).
+    let method_id = &keccak256(b"snipaaaaaa_and_pay(bytes,uint256)")[0..4];
+
+    // ABI-encode the arguments
+    let encoded_args = ethabi::encode(
+        &[ethabi::Token::Bytes(swap_call_data), ethabi::Token::Uint(coinbase_payment)]
+    );
+
+    let mut payload = vec![];
+    payload.extend_from_slice(method_id);
+    payload.extend_from_slice(&encoded_args);
+
+    payload
+}
+
 #[allow(dead_code)]
 pub fn encode_withdraw(input_token: Address, amount_in: U256) -> Vec<u8> {
     // The method's signature hash (first 4 bytes of the keccak256 hash of the signature).
@@ -64,6 +165,22 @@ const TRANSFER_EVENT_ABI: &str =
 const V2_SWAP_EVENT_ABI: &str =
     "[{\"anonymous\":false,\"inputs\":[{\"indexed\":true,\"name\":\"sender\",\"type\":\"address\"},{\"indexed\":false,\"name\":\"amount0In\",\"type\":\"uint256\"},{\"indexed\":false,\"name\":\"amount1In\",\"type\":\"uint256\"},{\"indexed\":false,\"name\":\"amount0Out\",\"type\":\"uint256\"},{\"indexed\":false,\"name\":\"amount1Out\",\"type\":\"uint256\"},{\"indexed\":true,\"name\":\"to\",\"type\":\"address\"}],\"name\":\"Swap\",\"type\":\"event\"}]";
 
+// Uniswap V3 PoolCreated event abi
+const POOL_CREATED_ABI: &str =
+    "[{\"anonymous\":false,\"inputs\":[{\"indexed\":true,\"name\":\"token0\",\"type\":\"address\"},{\"indexed\":true,\"name\":\"token1\",\"type\":\"address\"},{\"indexed\":true,\"name\":\"fee\",\"type\":\"uint24\"},{\"indexed\":false,\"name\":\"tickSpacing\",\"type\":\"int24\"},{\"indexed\":false,\"name\":\"pool\",\"type\":\"address\"}],\"name\":\"PoolCreated\",\"type\":\"event\"}]";
+
+// Uniswap V3 Initialize event abi
+const V3_INITIALIZE_ABI: &str =
+    "[{\"anonymous\":false,\"inputs\":[{\"indexed\":false,\"name\":\"sqrtPriceX96\",\"type\":\"uint160\"},{\"indexed\":false,\"name\":\"tick\",\"type\":\"int24\"}],\"name\":\"Initialize\",\"type\":\"event\"}]";
+
+// Uniswap V3 Mint event abi
+const V3_MINT_ABI: &str =
+    "[{\"anonymous\":false,\"inputs\":[{\"indexed\":false,\"name\":\"sender\",\"type\":\"address\"},{\"indexed\":true,\"name\":\"owner\",\"type\":\"address\"},{\"indexed\":true,\"name\":\"tickLower\",\"type\":\"int24\"},{\"indexed\":true,\"name\":\"tickUpper\",\"type\":\"int24\"},{\"indexed\":false,\"name\":\"amount\",\"type\":\"uint128\"},{\"indexed\":false,\"name\":\"amount0\",\"type\":\"uint256\"},{\"indexed\":false,\"name\":\"amount1\",\"type\":\"uint256\"}],\"name\":\"Mint\",\"type\":\"event\"}]";
+
+// Uniswap V3 Swap event abi
+const V3_SWAP_EVENT_ABI: &str =
+    "[{\"anonymous\":false,\"inputs\":[{\"indexed\":true,\"name\":\"sender\",\"type\":\"address\"},{\"indexed\":true,\"name\":\"recipient\",\"type\":\"address\"},{\"indexed\":false,\"name\":\"amount0\",\"type\":\"int256\"},{\"indexed\":false,\"name\":\"amount1\",\"type\":\"int256\"},{\"indexed\":false,\"name\":\"sqrtPriceX96\",\"type\":\"uint160\"},{\"indexed\":false,\"name\":\"liquidity\",\"type\":\"uint128\"},{\"indexed\":false,\"name\":\"tick\",\"type\":\"int24\"}],\"name\":\"Swap\",\"type\":\"event\"}]";
+
 
 // ** HOLDS ALL THE ABIS WE ARE GOING TO USE **
 lazy_static! {
@@ -72,10 +189,20 @@ lazy_static! {
     pub static ref PAIR_CREATED_EVENT: ethabi::Event = get_pair_created_event();
     pub static ref V2_SWAP_EVENT: ethabi::Event = get_v2_swap_event();
     pub static ref TRANSFER_EVENT: ethabi::Event = get_transfer_event();
+    pub static ref POOL_CREATED_EVENT: ethabi::Event = get_event(POOL_CREATED_ABI, "PoolCreated");
+    pub static ref V3_INITIALIZE_EVENT: ethabi::Event = get_event(V3_INITIALIZE_ABI, "Initialize");
+    pub static ref V3_MINT_EVENT: ethabi::Event = get_event(V3_MINT_ABI, "Mint");
+    pub static ref V3_SWAP_EVENT: ethabi::Event = get_event(V3_SWAP_EVENT_ABI, "Swap");
 
     pub static ref ERC20_BALANCE_OF: BaseContract = get_erc20_balanceof();
     pub static ref TOKEN0: BaseContract = get_token0();
     pub static ref TOKEN1: BaseContract = get_token1();
+    pub static ref OWNER: BaseContract = get_owner();
+    pub static ref TOTAL_SUPPLY: BaseContract = get_total_supply();
+    pub static ref ERC20_METADATA: BaseContract = get_erc20_metadata();
+    pub static ref UNICRYPT_LOCKER: BaseContract = get_unicrypt_locker();
+    pub static ref V3_POOL: BaseContract = get_v3_pool();
+    pub static ref V3_FACTORY: BaseContract = get_v3_factory();
 }
 
 
@@ -112,6 +239,11 @@ pub fn get_transfer_event() -> ethabi::Event {
     transfer_event.clone()
 }
 
+fn get_event(abi: &str, name: &str) -> ethabi::Event {
+    let contract = ethabi::Contract::load(abi.as_bytes()).unwrap();
+    contract.event(name).unwrap().clone()
+}
+
 fn get_erc20_balanceof() -> BaseContract {
     BaseContract::from(
         parse_abi(&["function balanceOf(address) external returns (uint)"]).unwrap()
@@ -131,6 +263,56 @@ fn get_token1() -> BaseContract {
 }
 
 
+fn get_owner() -> BaseContract {
+    BaseContract::from(
+        parse_abi(&["function owner() external view returns (address)"]).unwrap()
+    )
+}
+
+fn get_total_supply() -> BaseContract {
+    BaseContract::from(
+        parse_abi(&["function totalSupply() external view returns (uint256)"]).unwrap()
+    )
+}
+
+fn get_erc20_metadata() -> BaseContract {
+    BaseContract::from(
+        parse_abi(
+            &[
+                "function name() external view returns (string)",
+                "function symbol() external view returns (string)",
+                "function decimals() external view returns (uint8)",
+            ]
+        ).unwrap()
+    )
+}
+
+// Unicrypt V2 liquidity locker
+fn get_unicrypt_locker() -> BaseContract {
+    BaseContract::from(
+        parse_abi(
+            &[
+                "function getNumLocksForToken(address) external view returns (uint256)",
+                "function tokenLocks(address,uint256) external view returns (uint256,uint256,uint256,uint256,uint256,address)",
+            ]
+        ).unwrap()
+    )
+}
+
+fn get_v3_pool() -> BaseContract {
+    BaseContract::from(
+        parse_abi(&["function fee() external view returns (uint24)"]).unwrap()
+    )
+}
+
+fn get_v3_factory() -> BaseContract {
+    BaseContract::from(
+        parse_abi(
+            &["function getPool(address,address,uint24) external view returns (address)"]
+        ).unwrap()
+    )
+}
+
 
 pub fn load_abi_from_file(file_path: &str) -> Result<String, Box<dyn std::error::Error>> {
     let content = fs::read_to_string(file_path)?;
@@ -149,3 +331,19 @@ abigen!(
     "src/utils/abi/IUniswapV2Router.abi",
     event_derives(serde::Deserialize, serde::Serialize)
 );
+
+abigen!(
+    UniswapV3Pool,
+    r#"[
+        function slot0() external view returns (uint160 sqrtPriceX96, int24 tick, uint16 observationIndex, uint16 observationCardinality, uint16 observationCardinalityNext, uint8 feeProtocol, bool unlocked)
+        function liquidity() external view returns (uint128)
+        function token0() external view returns (address)
+    ]"#
+);
+
+abigen!(
+    Erc20Token,
+    r#"[
+        function balanceOf(address) external view returns (uint256)
+    ]"#
+);
\ No newline at end of file
@@ -1,12 +1,20 @@
 use lazy_static::lazy_static;
 use ethers::prelude::*;
 use std::str::FromStr;
+use crate::utils::types::structs::base_token::BaseToken;
+use crate::utils::types::structs::v2_fork::V2Fork;
+use crate::utils::types::structs::builder::{ Builder, BuilderMethod };
+use crate::utils::types::structs::tx_route::TxRoute;
+use crate::utils::types::structs::builder_payment::BuilderPayment;
 
 
 // ** Addresses **
 lazy_static!{
     pub static ref WETH: Address = Address::from_str("0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2").unwrap();
 
+    // the chain we sign our txs for, the bot does not start if the node is on another one
+    pub static ref CHAIN_ID: u64 = 1;
+
     // the address which you sign the transactions and call the contract
     pub static ref CALLER_ADDRESS: Address = Address::from_str("0xYOUR_ADDRESS").unwrap();
 
@@ -15,6 +23,10 @@ lazy_static!{
 
     pub static ref CALLER_WALLET: LocalWallet = get_my_wallet();
 
+    // every wallet we sign with, the first one must be CALLER_WALLET
+    // all of them must be authorized with set_swap_user on the contract
+    pub static ref SIGNER_WALLETS: Vec<LocalWallet> = get_signer_wallets();
+
     pub static ref FLASHBOT_IDENTITY: LocalWallet = get_flashbot_identity();
 
     pub static ref FLASHBOT_SEARCHER: LocalWallet = get_flashbot_searcher();
@@ -24,6 +36,123 @@ lazy_static!{
 
     // Locally inserted contract
     pub static ref SWAPPER_ADDRESS: Address = Address::from_str("00000000000000000000000000000000F3370000").unwrap();
+
+    // run the pre-buy checks against a Sniper inserted at SWAPPER_ADDRESS
+    // instead of the deployed contract, needs the contract to be built with forge
+    // falls back to the deployed contract if the artifact is missing
+    pub static ref USE_LOCAL_SNIPER: bool = true;
+
+    // foundry artifact of the Sniper contract
+    pub static ref SNIPER_ARTIFACT_FILE: String = "../../contract/out/sniper.sol/Sniper.json".to_string();
+
+    // weth balance the local Sniper is seeded with
+    // default 10 weth
+    pub static ref LOCAL_SNIPER_WETH_BALANCE: U256 = U256::from(10000000000000000000u128);
+
+    // storage slot of the balanceOf mapping in WETH9
+    pub static ref WETH_BALANCE_SLOT: u64 = 3;
+
+    // storage slot of the swap_users mapping in the Sniper
+    pub static ref SWAP_USERS_SLOT: u64 = 0;
+
+    // ** V2 Forks **
+    // Uniswap V2 style factories we snipe new pairs from, with their router,
+    // pair init code hash and swap fee (in hundredths of a bip, 3000 is 0.3%)
+    // pairs from any other factory are ignored
+    pub static ref V2_FORKS: Vec<V2Fork> = vec![
+        V2Fork::new(
+            "Uniswap V2",
+            Address::from_str("0x5C69bEe701ef814a2B6a3EDD4B1652CB9cc5aA6f").unwrap(),
+            Address::from_str("0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D").unwrap(),
+            H256::from_str("0x96e8ac4277198ff8b6f785478aa9a39f403cb768dd02cbee326c3e7da348845f").unwrap(),
+            3000
+        ),
+        V2Fork::new(
+            "SushiSwap",
+            Address::from_str("0xC0AEe478e3658e2610c5F7A4A2E1777cE9e4f2Ac").unwrap(),
+            Address::from_str("0xd9e1cE17f2641f24aE83637ab66a2cca9C378B9F").unwrap(),
+            H256::from_str("0xe18a34eb0e04b04f7a0ac29a6e80748dca96319b42c54d679cb821dca90c6303").unwrap(),
+            3000
+        ),
+        V2Fork::new(
+            "PancakeSwap V2",
+            Address::from_str("0x1097053Fd2ea711dad45caCcc45EfF7548fCB362").unwrap(),
+            Address::from_str("0xEfF92A263d31888d860bD50809A8D171709b7b1c").unwrap(),
+            H256::from_str("0x57224589c67f3f30a6b0d7a1b54cf3153ab84563bc609ef41dfb34f8b2974d2d").unwrap(),
+            2500
+        ),
+    ];
+
+    // Uniswap V3 factory, new V3 pools are created here
+    pub static ref UNISWAP_V3_FACTORY: Address = Address::from_str("0x1F98431c8aD98523631AE4a59f267346ea31F984").unwrap();
+
+    // ** Base Tokens **
+    pub static ref USDC: Address = Address::from_str("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48").unwrap();
+    pub static ref USDT: Address = Address::from_str("0xdAC17F958D2ee523a2206206994597C13D831ec7").unwrap();
+    pub static ref DAI: Address = Address::from_str("0x6B175474E89094C44Da98b954EedeAC495271d0F").unwrap();
+
+    // tokens new pairs can be quoted in, with the Uniswap V2 pair we use to route them to WETH
+    // and to value their reserves in ETH
+    // remove a line to stop sniping pairs quoted in that token
+    pub static ref BASE_TOKENS: Vec<BaseToken> = vec![
+        BaseToken::new(*WETH, Address::zero()),
+        BaseToken::new(*USDC, Address::from_str("0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc").unwrap()),
+        BaseToken::new(*USDT, Address::from_str("0x0d4a11d5EEaaC28EC3F61d100daF4d40471f1852").unwrap()),
+        BaseToken::new(*DAI, Address::from_str("0xA478c2975Ab1Ea89e8196811F51A7B7Ade33eB11").unwrap()),
+    ];
+
+    // fee of the base token pairs above, they are all Uniswap V2 pairs
+    pub static ref BASE_PAIR_FEE: u32 = 3000;
+
+    // burn address used by most tokens
+    pub static ref DEAD_ADDRESS: Address = Address::from_str("0x000000000000000000000000000000000000dEaD").unwrap();
+
+    // ** LP Lockers **
+    pub static ref UNICRYPT_V2_LOCKER: Address = Address::from_str("0x663A5C229c09b049E36dCc11a9B0d4a8Eb9db214").unwrap();
+    pub static ref TEAM_FINANCE_LOCKER: Address = Address::from_str("0xE2fE530C047f2d85298b07D9333C05737f1435fB").unwrap();
+    pub static ref PINKLOCK_V2_LOCKER: Address = Address::from_str("0x71B5759d73262FBb223956913ecF4ecC51057641").unwrap();
+
+    // all the lockers we recognize when checking who holds the LP tokens
+    pub static ref LP_LOCKERS: Vec<Address> = vec![
+        *UNICRYPT_V2_LOCKER,
+        *TEAM_FINANCE_LOCKER,
+        *PINKLOCK_V2_LOCKER,
+    ];
+
+    // ** Flagged Funders **
+    // a deployer funded by one of these is skipped, default are the Tornado Cash ETH pools and router
+    pub static ref FLAGGED_FUNDERS: Vec<Address> = vec![
+        Address::from_str("0x12D66f87A04A9E220743712cE6d9bB1B5616B8Fc").unwrap(),
+        Address::from_str("0x47CE0C6eD5B0Ce3d3A51fdb1C52DC66a7c3c2936").unwrap(),
+        Address::from_str("0x910Cbd523D972eb0a6f4cAe4618aD62622b39DbF").unwrap(),
+        Address::from_str("0xA160cdAB225685dA1d56aa342Ad8841c3b53f291").unwrap(),
+        Address::from_str("0xd90e2f925DA726b50C4Ed8D0Fb90Ad053324F31b").unwrap(),
+    ];
+
+    // ** Builders **
+    // MEV builders and relays we send the bundles to
+    // set enabled to false to stop sending to one, the coinbase is used to attribute our inclusions
+    pub static ref BUILDERS: Vec<Builder> = vec![
+        Builder::new("flashbots", "https://relay.flashbots.net/", BuilderMethod::FlashbotsBundle, true, Some(Address::from_str("0xDAFEA492D9c6733ae3d56b7Ed1ADB60692c98Bc5").unwrap())),
+        Builder::new("builder0x69", "http://builder0x69.io/", BuilderMethod::FlashbotsBundle, true, Some(Address::from_str("0x690B9A9E9aa1C9dB991C7721a92d351Db4FaC990").unwrap())),
+        Builder::new("titan", "http://rpc.titanbuilder.xyz", BuilderMethod::FlashbotsBundle, true, Some(Address::from_str("0x4838B106FCe9647Bdf1E7877BF73cE8B0BAD5f97").unwrap())),
+        Builder::new("eden", "https://api.edennetwork.io/v1/bundle", BuilderMethod::FlashbotsBundle, true, None),
+        Builder::new("beaverbuild", "https://rpc.beaverbuild.org/", BuilderMethod::FlashbotsBundle, true, Some(Address::from_str("0x95222290DD7278Aa3Ddd389Cc1E1d165CC4BAfe5").unwrap())),
+        Builder::new("lightspeed", "https://rpc.lightspeedbuilder.info/", BuilderMethod::FlashbotsBundle, true, None),
+        Builder::new("eth-builder", "https://eth-builder.com/", BuilderMethod::FlashbotsBundle, true, None),
+        Builder::new("ultrasound", "https://relay.ultrasound.money/", BuilderMethod::FlashbotsBundle, true, None),
+        Builder::new("agnostic", "https://agnostic-relay.net/", BuilderMethod::FlashbotsBundle, true, None),
+        Builder::new("relayooor", "https://relayooor.wtf/", BuilderMethod::FlashbotsBundle, true, None),
+        Builder::new("rsync", "https://rsync-builder.xyz/", BuilderMethod::FlashbotsBundle, true, Some(Address::from_str("0x1f9090aaE28b8a3dCeaDf281B0F12828e676c326").unwrap())),
+        Builder::new("buildai", "https://buildai.net/", BuilderMethod::FlashbotsBundle, true, None),
+        Builder::new("aestus", "http://mainnet.aestus.live/", BuilderMethod::FlashbotsBundle, true, None),
+        Builder::new("securerpc", "https://mainnet-relay.securerpc.com", BuilderMethod::FlashbotsBundle, true, None),
+        Builder::new("gmbit", "https://builder.gmbit.co/rpc", BuilderMethod::FlashbotsBundle, true, None),
+        Builder::new("bloxroute", "https://mev.api.blxrbdn.com/", BuilderMethod::FlashbotsBundle, true, None),
+        Builder::new("boba", "https://boba-builder.com/searcher/", BuilderMethod::FlashbotsBundle, true, None),
+        Builder::new("blockbeelder", "https://blockbeelder.com/rpc", BuilderMethod::FlashbotsBundle, true, None),
+        Builder::new("loki", "https://rpc.lokibuilder.xyz", BuilderMethod::FlashbotsBundle, true, None),
+    ];
 }
 
 
@@ -71,13 +200,34 @@ lazy_static! {
     // default 10 gwei
     pub static ref MINER_TIP_TO_SELL: U256 = U256::from(10000000000u128);
 
+    // safety margin added on top of the simulated gas when setting the gas limit (in percent)
+    // default 25%
+    pub static ref GAS_LIMIT_MARGIN: u64 = 25;
+
     // how many times we try to sell before we remove the token from the sell oracle
     pub static ref MAX_SELL_ATTEMPTS: u8 = 20;
 
     // how many times we retry to buy a token before we remove it from the retry oracle
     pub static ref MAX_SNIPE_RETRIES: u8 = 10;
 
+    // simulate the pending txs of the deployer or owner of the tokens in the retry oracle
+    // and backrun the tx that opens trading in the same block
+    pub static ref ENABLE_TRADING_ENABLE_BACKRUN: bool = true;
+
+    // sell a small part of the tokens right after the trading enable backrun in the same bundle
+    // if the opened token blocks sells the bundle reverts and never lands
+    // only for the bundle routes, the public mempool sends a single tx
+    pub static ref ENABLE_BUNDLE_SELL_CHECK: bool = true;
+
+    // share of the bought tokens the bundle sell check sells (in basis points)
+    // default 1%
+    pub static ref BUNDLE_SELL_CHECK_BPS: u64 = 100;
+
+    // gas limit of the bundle sell check, it is not simulated before the bundle
+    pub static ref BUNDLE_SELL_CHECK_GAS_LIMIT: u64 = 500000;
+
     // minimum weth reserve for a new pair
+    // pairs quoted in another base token are valued in ETH
     // default is 1 weth
     pub static ref MIN_WETH_RESERVE: U256 = U256::from(1000000000000000000u128);
 
@@ -85,6 +235,226 @@ lazy_static! {
     // default is 4 weth
     pub static ref MAX_WETH_RESERVE: U256 = U256::from(4000000000000000000u128);
 
+    // ** PAIR DISCOVERY **
+
+    // also scan the logs of every new block for new pairs
+    // catches launches sent privately to the builders which never show up in the mempool
+    pub static ref ENABLE_BLOCK_PAIR_DISCOVERY: bool = true;
+
+    // how many blocks we remember a pool we already processed
+    // so the mempool and the block logs dont snipe it twice
+    pub static ref SEEN_POOL_TTL_BLOCKS: u64 = 50;
+
+    // how many blocks we remember a V3 pool that was initialized without liquidity
+    // the first Mint on it within this window is a new pool
+    // default is about a day
+    pub static ref INITIALIZED_POOL_TTL_BLOCKS: u64 = 7200;
+
+    // ** UNISWAP V3 **
+
+    // snipe new Uniswap V3 pools as well as V2 pairs
+    pub static ref ENABLE_V3_SNIPING: bool = true;
+
+    // how many ticks on each side of the current tick we count
+    // when estimating the weth liquidity of a V3 pool
+    // default 2000 ticks which is about a 22% price move
+    pub static ref V3_LIQUIDITY_TICK_WINDOW: i32 = 2000;
+
+    // ** TOKEN CHECKS **
+
+    // skip tokens that have an owner which is not renounced
+    pub static ref REQUIRE_RENOUNCED_OWNERSHIP: bool = false;
+
+    // skip tokens whose LP tokens are not burned or sent to a known locker
+    pub static ref REQUIRE_LP_BURNED_OR_LOCKED: bool = false;
+
+    // minimum time the LP tokens must stay locked from now (in seconds)
+    // only checked for lockers we can read the unlock time from
+    // default 30 days
+    pub static ref MIN_LP_LOCK_DURATION: u64 = 2592000;
+
+    // skip tokens where the deployer holds too much of the supply
+    pub static ref ENABLE_DEPLOYER_SHARE_CHECK: bool = false;
+
+    // maximum share of the total supply the deployer can hold (in basis points)
+    // default 10%
+    pub static ref MAX_DEPLOYER_SUPPLY_SHARE: u64 = 1000;
+
+    // ** TOKEN INFO **
+
+    // skip tokens without a symbol or decimals
+    pub static ref REQUIRE_TOKEN_METADATA: bool = false;
+
+    // how many blocks of Transfer logs we scan to find the holders of a new token
+    pub static ref HOLDER_SCAN_BLOCKS: u64 = 1000;
+
+    // how many of the largest holders we count
+    pub static ref TOP_HOLDERS_COUNT: usize = 10;
+
+    // skip tokens where the top holders hold too much of the supply
+    pub static ref ENABLE_TOP_HOLDERS_CHECK: bool = false;
+
+    // maximum share of the total supply the top holders can hold (in basis points)
+    // the pool, the token contract and burned tokens are not counted
+    // default 50%
+    pub static ref MAX_TOP_HOLDERS_SHARE: u64 = 5000;
+
+    // look up where the deployer got its first ETH from
+    // needs an archive node and delays every snipe by a few dozen rpc calls
+    pub static ref ENABLE_FUNDING_SOURCE_LOOKUP: bool = false;
+
+    // how many blocks back we search for the deployer's funding
+    // default is about 30 days
+    pub static ref FUNDING_LOOKBACK_BLOCKS: u64 = 216000;
+
+    // ** SIMULATION EXECUTOR **
+
+    // threads used to run the revm simulations
+    // default is all cores but 2 which we leave for the tokio runtime
+    pub static ref SIM_WORKER_THREADS: usize = std::thread::available_parallelism()
+        .map(|n| n.get().saturating_sub(2).max(1))
+        .unwrap_or(4);
+
+    // maximum number of simulations waiting in the queue
+    pub static ref SIM_QUEUE_CAPACITY: usize = 1000;
+
+    // ** COMPETING SNIPERS **
+
+    // look for other pending swaps on the new pool and adapt our buy to them
+    pub static ref ENABLE_COMPETITOR_DETECTION: bool = true;
+
+    // minimum time competing swaps get to show up in the mempool before we snipe (in ms)
+    // counted from the pair discovery, the checks usually take longer so we rarely wait
+    pub static ref COMPETITOR_WAIT_MS: u64 = 300;
+
+    // how long we keep watching a new pool for competing swaps (in seconds)
+    pub static ref COMPETITOR_WATCH_SECS: u64 = 60;
+
+    // skip the token if we get less than this share of the tokens after the competitors (in basis points)
+    // default 50%
+    pub static ref MIN_COMPETITOR_FILL_BPS: u64 = 5000;
+
+    // how much we outbid the highest competitor tip (in percent)
+    pub static ref COMPETITOR_TIP_BUMP: u64 = 10;
+
+    // we never tip more than this to snipe
+    // default is 300 gwei
+    pub static ref MAX_MINER_TIP_TO_SNIPE: U256 = U256::from(300000000000u128);
+
+    // ** BUILDERS **
+
+    // how many of the last requests we keep per builder
+    pub static ref BUILDER_STATS_WINDOW: usize = 50;
+
+    // a builder is only judged once it has this many requests in the window
+    pub static ref BUILDER_MIN_REQUESTS: usize = 10;
+
+    // drop a builder if this share of its requests fail (in basis points)
+    // default 50%
+    pub static ref BUILDER_MAX_ERROR_RATE: u64 = 5000;
+
+    // drop a builder if its average request latency is above this (in ms)
+    pub static ref BUILDER_MAX_LATENCY_MS: u64 = 1000;
+
+    // how long a dropped builder stays dropped before we try it again (in seconds)
+    pub static ref BUILDER_RETRY_SECS: u64 = 600;
+
+    // how long we wait for the target block to check if our tx got included (in seconds)
+    pub static ref INCLUSION_TIMEOUT_SECS: u64 = 30;
+
+    // how many blocks we target with the same signed bundle, 1 only targets the next block
+    // the bundle is simulated again before every later target and cancelled if it fails
+    pub static ref BUNDLE_TARGET_BLOCKS: u64 = 1;
+
+    // how our bundles pay the builder, the public mempool always pays with the priority fee
+    pub static ref BUILDER_PAYMENT: BuilderPayment = BuilderPayment::PriorityFee;
+
+    // share of the simulated profit we transfer to the coinbase (in percent)
+    // txs without a known profit, eg. snipes and rug escapes, transfer what the miner tip would have cost
+    pub static ref COINBASE_PAYMENT_SHARE: u64 = 50;
+
+    // extra gas for unwrapping the weth and the coinbase transfer
+    pub static ref COINBASE_PAYMENT_GAS: u64 = 60000;
+
+    // ** SIGNERS AND NONCES **
+
+    // signers with less ETH are skipped until they are topped up
+    // default 0.05 ETH
+    pub static ref MIN_SIGNER_BALANCE: U256 = U256::from(50000000000000000u128);
+
+    // a nonce handed out to a tx that never reports back is reclaimed after this many blocks
+    // raised to cover the bundle targets and the speed ups of the public route plus the margin below
+    pub static ref NONCE_EXPIRY_BLOCKS: u64 = 10;
+
+    // blocks a nonce stays reserved after its last bundle target or speed up
+    pub static ref NONCE_EXPIRY_MARGIN_BLOCKS: u64 = 3;
+
+    // ** PUBLIC MEMPOOL **
+
+    // how we send our txs, bundles to the builders or the public mempool
+    pub static ref TX_ROUTE: TxRoute = TxRoute::Bundle;
+
+    // how often we poll for the receipt of a public tx (in ms)
+    pub static ref RECEIPT_POLL_MS: u64 = 500;
+
+    // how long we wait for a receipt before we speed up the tx (in seconds)
+    pub static ref SPEED_UP_INTERVAL_SECS: u64 = 12;
+
+    // how much we bump the fees on every speed up (in percent)
+    // nodes only accept a replacement with at least 10% higher fees
+    pub static ref SPEED_UP_BUMP: u64 = 15;
+
+    // after this many speed ups the tx is cancelled
+    pub static ref MAX_SPEED_UPS: u64 = 3;
+
+    // ** ANTI-BOT TRAPS **
+
+    // re-run the buy and sell with varied environment values when the token reads them
+    pub static ref ENABLE_ENV_SENSITIVITY_CHECK: bool = true;
+
+    // skip tokens whose outcome changes with the gas price or coinbase, otherwise only log them
+    // block and base fee changes are always only logged, launch taxes decay by block
+    pub static ref REJECT_ENV_SENSITIVE_TOKENS: bool = true;
+
+    // outcomes that differ by less than this are treated as equal (in basis points)
+    pub static ref ENV_CHECK_TOLERANCE_BPS: u64 = 100;
+
+    // how many blocks after launch we move the buy for the block number variation
+    pub static ref ENV_CHECK_BLOCK_OFFSET: u64 = 3;
+
+    // coinbase used for the coinbase variation, beaverbuild
+    pub static ref ENV_CHECK_COINBASE: Address = Address::from_str("0x95222290DD7278Aa3Ddd389Cc1E1d165CC4BAfe5").unwrap();
+
+    // ** SELL ORACLE FAST PATH **
+
+    // price held tokens with a fixed tax with the V2 math instead of a full EVM sell
+    pub static ref ENABLE_SELL_FAST_PATH: bool = true;
+
+    // run a full EVM sell every N blocks even if the fast path is used
+    pub static ref FAST_PATH_EVM_INTERVAL: u64 = 10;
+
+    // two EVM sells in a row must measure the same tax within this tolerance (in basis points)
+    // before we trust the fast path
+    pub static ref FAST_PATH_TAX_TOLERANCE: u64 = 50;
+
+    // fall back to the EVM if the fast path amount moved more than this since the last quote (in %)
+    pub static ref FAST_PATH_MAX_DEVIATION: u64 = 30;
+
+    // how many mapping slots we try when deriving the balances mapping from a traced SLOAD
+    pub static ref BALANCE_SLOT_SEARCH_DEPTH: u64 = 100;
+
+    // ** DEBUGGING **
+
+    // save the call trace of rejected tokens as json
+    // the pretty tree is always logged
+    pub static ref SAVE_REJECTED_TRACES: bool = true;
+
+    // directory where the call traces are saved
+    pub static ref TRACES_DIR: String = "traces".to_string();
+
+    // custom error signatures used to decode reverts
+    // add any signature you come across to this file
+    pub static ref ERROR_SIGNATURES_FILE: String = "../../src/utils/abi/error_signatures.txt".to_string();
     
 }
 
@@ -99,6 +469,17 @@ pub fn get_my_wallet() -> LocalWallet {
     private_key.parse::<LocalWallet>().expect("Failed to parse private key")
 }
 
+// extra wallets to sign with, fill in as many private keys as you want
+pub fn get_signer_wallets() -> Vec<LocalWallet> {
+    let private_keys: Vec<&str> = vec![];
+
+    let mut wallets = vec![get_my_wallet()];
+    for private_key in private_keys {
+        wallets.push(private_key.parse::<LocalWallet>().expect("Failed to parse signer private key"));
+    }
+    wallets
+}
+
 // flashbot identity , could also be a random private key
 pub fn get_flashbot_identity() -> LocalWallet {
     let private_key: String = "0xYOUR_PRIVATE_KEY".to_string();
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+use ethers::prelude::*;
+use ethers::abi::Token;
+use ethers::utils::keccak256;
+use revm::primitives::{ TransactTo, U256 as rU256 };
+use revm::EVM;
+use lazy_static::lazy_static;
+
+use crate::forked_db::{ fork_db::ForkDB, match_output };
+use crate::utils::abi::ERC20_BALANCE_OF;
+use crate::utils::constants::*;
+use super::insp::sload_tracer::SloadTracer;
+use super::simulate::get_mapping_slot;
+
+// value we write into a candidate slot, balanceOf must return it back
+const PROBE_VALUE: u64 = 0x1337_1337_1337;
+
+// How the balances mapping key is hashed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MappingLayout {
+    // keccak256(key . slot)
+    Solidity,
+    // keccak256(slot . key)
+    Vyper,
+}
+
+// The storage location of an ERC20 balances mapping
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BalanceSlot {
+    pub mapping_slot: U256,
+    pub layout: MappingLayout,
+}
+
+impl BalanceSlot {
+    // storage slot that holds the balance of a holder
+    pub fn slot_for(&self, holder: Address) -> U256 {
+        match self.layout {
+            MappingLayout::Solidity => get_mapping_slot(holder, self.mapping_slot),
+            MappingLayout::Vyper => {
+                let key = ethers::abi::encode(
+                    &[Token::Uint(self.mapping_slot), Token::Address(holder)]
+                );
+                U256::from_big_endian(&keccak256(key))
+            }
+        }
+    }
+}
+
+// ** Discovered balance slots, keyed by token **
+// None means the token doesnt keep balances in a plain mapping, eg. reflection tokens
+lazy_static! {
+    static ref BALANCE_SLOTS: RwLock<HashMap<Address, Option<BalanceSlot>>> = RwLock::new(
+        HashMap::new()
+    );
+}
+
+// Returns the balance slot of a token, discovers it on the first call
+pub fn get_balance_slot(token: Address, fork_db: &ForkDB) -> Option<BalanceSlot> {
+    if let Ok(slots) = BALANCE_SLOTS.read() {
+        if let Some(balance_slot) = slots.get(&token) {
+            return *balance_slot;
+        }
+    }
+
+    let balance_slot = match discover_balance_slot(token, fork_db) {
+        Ok(balance_slot) => balance_slot,
+        Err(e) => {
+            // dont cache errors, the backend may just be slow
+            log::error!("Failed to discover balance slot of {:?}: {:?}", token, e);
+            return None;
+        }
+    };
+
+    if balance_slot.is_none() {
+        log::warn!("No balances mapping found for {:?}", token);
+    }
+
+    if let Ok(mut slots) = BALANCE_SLOTS.write() {
+        slots.insert(token, balance_slot);
+    }
+
+    balance_slot
+}
+
+// Finds the balances mapping of a token
+//
+// ** 1. trace the SLOADs of balanceOf(holder)
+// ** 2. override each loaded slot and check balanceOf returns the override
+// ** 3. derive the mapping slot from the verified storage slot
+pub fn discover_balance_slot(
+    token: Address,
+    fork_db: &ForkDB
+) -> Result<Option<BalanceSlot>, anyhow::Error> {
+    // any holder works, we only need the hash of the key
+    let holder = *SWAPPER_ADDRESS;
+
+    let mut tracer = SloadTracer::new();
+    call_balance_of(token, holder, fork_db.clone(), Some(&mut tracer))?;
+
+    let probe = U256::from(PROBE_VALUE);
+
+    for slot in tracer.slots_of(token) {
+        let mut probe_db = fork_db.clone();
+        probe_db.insert_account_storage(token.0.into(), slot.into(), probe.into())?;
+
+        let balance = match call_balance_of(token, holder, probe_db, None) {
+            Ok(balance) => balance,
+            Err(_) => continue,
+        };
+
+        if balance != probe {
+            continue;
+        }
+
+        return Ok(find_mapping_slot(holder, slot));
+    }
+
+    Ok(None)
+}
+
+// finds the mapping slot whose key for the holder is the given storage slot
+fn find_mapping_slot(holder: Address, slot: U256) -> Option<BalanceSlot> {
+    for mapping_slot in 0..*BALANCE_SLOT_SEARCH_DEPTH {
+        for layout in [MappingLayout::Solidity, MappingLayout::Vyper] {
+            let balance_slot = BalanceSlot { mapping_slot: U256::from(mapping_slot), layout };
+            if balance_slot.slot_for(holder) == slot {
+                return Some(balance_slot);
+            }
+        }
+    }
+
+    None
+}
+
+// calls balanceOf, optionally tracing the SLOADs
+fn call_balance_of(
+    token: Address,
+    holder: Address,
+    fork_db: ForkDB,
+    tracer: Option<&mut SloadTracer>
+) -> Result<U256, anyhow::Error> {
+    let mut evm = EVM::new();
+    evm.database(fork_db);
+
+    evm.env.cfg.disable_base_fee = true;
+    evm.env.cfg.disable_block_gas_limit = true;
+    evm.env.cfg.disable_balance_check = true;
+    evm.env.tx.gas_limit = 1000000;
+
+    evm.env.tx.caller = CALLER_ADDRESS.0.into();
+    evm.env.tx.transact_to = TransactTo::Call(token.0.into());
+    evm.env.tx.data = ERC20_BALANCE_OF.encode("balanceOf", holder).unwrap().0;
+    evm.env.tx.value = rU256::ZERO;
+
+    let result = match tracer {
+        Some(tracer) => evm.inspect_ref(tracer)?.result,
+        None => evm.transact_ref()?.result,
+    };
+
+    let output = match_output(result)?;
+
+    let balance = ERC20_BALANCE_OF.decode_output("balanceOf", &output)?;
+
+    Ok(balance)
+}
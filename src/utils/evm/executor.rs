@@ -0,0 +1,232 @@
+use std::collections::VecDeque;
+use std::panic::{ catch_unwind, AssertUnwindSafe };
+use std::sync::{ Arc, Condvar, Mutex };
+use std::time::{ Duration, Instant };
+use tokio::sync::oneshot;
+use lazy_static::lazy_static;
+use anyhow::anyhow;
+
+use crate::utils::constants::{ SIM_WORKER_THREADS, SIM_QUEUE_CAPACITY };
+
+// ** Global simulation executor, all revm simulations should run through it **
+lazy_static! {
+    pub static ref SIM_EXECUTOR: SimExecutor = SimExecutor::new(
+        *SIM_WORKER_THREADS,
+        *SIM_QUEUE_CAPACITY
+    );
+}
+
+// Priority of a simulation job, higher runs first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SimPriority {
+    PairDiscovery = 0,
+    SellOracle = 1,
+    AntiRug = 2,
+}
+
+impl SimPriority {
+    pub const ALL: [SimPriority; 3] = [
+        SimPriority::PairDiscovery,
+        SimPriority::SellOracle,
+        SimPriority::AntiRug,
+    ];
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+// Metrics for a single priority
+#[derive(Debug, Clone, Default)]
+pub struct SimMetrics {
+    pub submitted: u64,
+    pub rejected: u64,
+    pub evicted: u64,
+    pub completed: u64,
+    pub total_wait: Duration,
+    pub total_exec: Duration,
+    pub max_wait: Duration,
+}
+
+impl SimMetrics {
+    pub fn avg_wait(&self) -> Duration {
+        if self.completed == 0 {
+            return Duration::ZERO;
+        }
+        self.total_wait / (self.completed as u32)
+    }
+
+    pub fn avg_exec(&self) -> Duration {
+        if self.completed == 0 {
+            return Duration::ZERO;
+        }
+        self.total_exec / (self.completed as u32)
+    }
+}
+
+struct Job {
+    queued_at: Instant,
+    task: Box<dyn FnOnce() + Send>,
+}
+
+// one queue per priority
+struct Queues {
+    jobs: [VecDeque<Job>; 3],
+    metrics: [SimMetrics; 3],
+}
+
+impl Queues {
+    fn len(&self) -> usize {
+        self.jobs
+            .iter()
+            .map(|q| q.len())
+            .sum()
+    }
+
+    // pops the oldest job with the highest priority
+    fn pop(&mut self) -> Option<(SimPriority, Job)> {
+        for priority in SimPriority::ALL.iter().rev() {
+            if let Some(job) = self.jobs[priority.index()].pop_front() {
+                return Some((*priority, job));
+            }
+        }
+        None
+    }
+
+    // drops the newest job with a lower priority to make room
+    fn evict_lower(&mut self, priority: SimPriority) -> bool {
+        for lower in SimPriority::ALL.iter() {
+            if *lower >= priority {
+                break;
+            }
+            if self.jobs[lower.index()].pop_back().is_some() {
+                self.metrics[lower.index()].evicted += 1;
+                return true;
+            }
+        }
+        false
+    }
+}
+
+struct Shared {
+    queues: Mutex<Queues>,
+    available: Condvar,
+    capacity: usize,
+}
+
+// A thread pool that runs the cpu bound revm simulations outside the tokio runtime
+//
+// Jobs are kept in a bounded queue, when the queue is full a job can only get in
+// by evicting a job with a lower priority
+#[derive(Clone)]
+pub struct SimExecutor {
+    shared: Arc<Shared>,
+}
+
+impl SimExecutor {
+    pub fn new(threads: usize, capacity: usize) -> Self {
+        let shared = Arc::new(Shared {
+            queues: Mutex::new(Queues {
+                jobs: Default::default(),
+                metrics: Default::default(),
+            }),
+            available: Condvar::new(),
+            capacity,
+        });
+
+        for i in 0..threads.max(1) {
+            let shared = shared.clone();
+            std::thread::Builder::new()
+                .name(format!("sim-worker-{}", i))
+                .spawn(move || worker_loop(shared))
+                .expect("failed to spawn simulation worker thread");
+        }
+
+        Self { shared }
+    }
+
+    // Queues a job and returns a receiver for its result
+    pub fn submit<F, T>(
+        &self,
+        priority: SimPriority,
+        f: F
+    ) -> Result<oneshot::Receiver<T>, anyhow::Error>
+        where F: FnOnce() -> T + Send + 'static, T: Send + 'static
+    {
+        let (sender, receiver) = oneshot::channel();
+
+        let job = Job {
+            queued_at: Instant::now(),
+            task: Box::new(move || {
+                let _ = sender.send(f());
+            }),
+        };
+
+        let mut queues = self.shared.queues.lock().unwrap();
+        queues.metrics[priority.index()].submitted += 1;
+
+        if queues.len() >= self.shared.capacity && !queues.evict_lower(priority) {
+            queues.metrics[priority.index()].rejected += 1;
+            return Err(anyhow!("Simulation queue is full, rejected {:?} job", priority));
+        }
+
+        queues.jobs[priority.index()].push_back(job);
+        drop(queues);
+
+        self.shared.available.notify_one();
+
+        Ok(receiver)
+    }
+
+    // Runs a job on the pool and waits for its result
+    pub async fn run<F, T>(&self, priority: SimPriority, f: F) -> Result<T, anyhow::Error>
+        where F: FnOnce() -> T + Send + 'static, T: Send + 'static
+    {
+        let receiver = self.submit(priority, f)?;
+
+        receiver.await.map_err(|_| anyhow!("{:?} simulation job was dropped or panicked", priority))
+    }
+
+    // number of jobs waiting for a priority
+    pub fn queue_depth_for(&self, priority: SimPriority) -> usize {
+        self.shared.queues.lock().unwrap().jobs[priority.index()].len()
+    }
+
+    // metrics for a priority
+    pub fn metrics(&self, priority: SimPriority) -> SimMetrics {
+        self.shared.queues.lock().unwrap().metrics[priority.index()].clone()
+    }
+}
+
+fn worker_loop(shared: Arc<Shared>) {
+    loop {
+        let (priority, job) = {
+            let mut queues = shared.queues.lock().unwrap();
+            loop {
+                if let Some(job) = queues.pop() {
+                    break job;
+                }
+                queues = shared.available.wait(queues).unwrap();
+            }
+        };
+
+        let started_at = Instant::now();
+        let wait = started_at - job.queued_at;
+
+        // a panicking simulation drops its sender, the caller gets an error
+        if catch_unwind(AssertUnwindSafe(job.task)).is_err() {
+            log::error!("{:?} simulation job panicked", priority);
+        }
+
+        let exec = started_at.elapsed();
+
+        let mut queues = shared.queues.lock().unwrap();
+        let metrics = &mut queues.metrics[priority.index()];
+        metrics.completed += 1;
+        metrics.total_wait += wait;
+        metrics.total_exec += exec;
+        if wait > metrics.max_wait {
+            metrics.max_wait = wait;
+        }
+    }
+}
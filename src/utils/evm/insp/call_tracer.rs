@@ -0,0 +1,389 @@
+use std::fs;
+use ethers::types::{ Address, Bytes, H256, U256 };
+use ethers::utils::hex;
+use serde_json::{ json, Value };
+use crate::forked_db::revert_decoder::decode_revert_reason;
+use revm::{
+    interpreter::{
+        opcode,
+        CallInputs,
+        CallScheme,
+        CreateInputs,
+        Gas,
+        InstructionResult,
+        Interpreter,
+    },
+    primitives::{ B160 as rAddress, B256, Bytes as rBytes, CreateScheme },
+    Database,
+    EVMData,
+    Inspector,
+};
+
+// The kind of a call frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CallKind {
+    Call,
+    StaticCall,
+    DelegateCall,
+    CallCode,
+    Create,
+    Create2,
+}
+
+impl CallKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CallKind::Call => "CALL",
+            CallKind::StaticCall => "STATICCALL",
+            CallKind::DelegateCall => "DELEGATECALL",
+            CallKind::CallCode => "CALLCODE",
+            CallKind::Create => "CREATE",
+            CallKind::Create2 => "CREATE2",
+        }
+    }
+}
+
+// A log emitted inside a call frame
+#[derive(Debug, Clone)]
+pub struct TraceLog {
+    pub address: Address,
+    pub topics: Vec<H256>,
+    pub data: Bytes,
+}
+
+// A single call frame of the call tree
+#[derive(Debug, Clone)]
+pub struct CallTrace {
+    pub depth: usize,
+    pub kind: CallKind,
+    pub from: Address,
+    pub to: Address,
+    pub value: U256,
+    pub input: Bytes,
+    pub output: Bytes,
+    pub gas_limit: u64,
+    pub gas_used: u64,
+    pub status: InstructionResult,
+    pub is_reverted: bool,
+    // program counter of the REVERT/INVALID opcode if this frame reverted by itself
+    pub revert_pc: Option<usize>,
+    pub logs: Vec<TraceLog>,
+    pub children: Vec<usize>,
+}
+
+impl CallTrace {
+    // decoded revert reason if the frame reverted
+    pub fn revert_reason(&self) -> Option<String> {
+        if self.status == InstructionResult::Revert {
+            Some(decode_revert_reason(&self.output))
+        } else {
+            None
+        }
+    }
+}
+
+// An [Inspector] that records the full call tree of a transaction
+// with inputs, outputs, gas, the revert point and emitted logs
+//
+// Frames are stored flat, the first frame is the root of the tree
+#[derive(Debug, Default)]
+pub struct CallTracer {
+    traces: Vec<CallTrace>,
+    // indexes of the frames that are currently executing
+    stack: Vec<usize>,
+}
+
+impl CallTracer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // returns the innermost frame that reverted by itself
+    pub fn revert_point(&self) -> Option<&CallTrace> {
+        self.traces
+            .iter()
+            .rev()
+            .find(|trace| trace.is_reverted && trace.revert_pc.is_some())
+    }
+
+    // exports the call tree as json
+    pub fn to_json(&self) -> Value {
+        if self.traces.is_empty() {
+            return Value::Null;
+        }
+        self.node_to_json(0)
+    }
+
+    // saves the call tree as json to the given path
+    pub fn save_json(&self, path: &str) -> Result<(), anyhow::Error> {
+        let json = serde_json::to_string_pretty(&self.to_json())?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+
+    // exports the call tree as a human readable tree
+    pub fn to_pretty_tree(&self) -> String {
+        let mut out = String::new();
+        if !self.traces.is_empty() {
+            self.node_to_string(0, "", &mut out);
+        }
+        out
+    }
+
+    fn node_to_json(&self, index: usize) -> Value {
+        let trace = &self.traces[index];
+
+        let logs: Vec<Value> = trace.logs
+            .iter()
+            .map(|log| {
+                json!({
+                    "address": log.address,
+                    "topics": log.topics,
+                    "data": log.data,
+                })
+            })
+            .collect();
+
+        let calls: Vec<Value> = trace.children
+            .iter()
+            .map(|child| self.node_to_json(*child))
+            .collect();
+
+        json!({
+            "type": trace.kind.as_str(),
+            "depth": trace.depth,
+            "from": trace.from,
+            "to": trace.to,
+            "value": trace.value,
+            "input": trace.input,
+            "output": trace.output,
+            "gasLimit": trace.gas_limit,
+            "gasUsed": trace.gas_used,
+            "status": format!("{:?}", trace.status),
+            "reverted": trace.is_reverted,
+            "revertPc": trace.revert_pc,
+            "revertReason": trace.revert_reason(),
+            "logs": logs,
+            "calls": calls,
+        })
+    }
+
+    fn node_to_string(&self, index: usize, prefix: &str, out: &mut String) {
+        let trace = &self.traces[index];
+
+        // only show the selector of the input
+        let selector = if trace.input.len() >= 4 {
+            format!("0x{}", hex::encode(&trace.input[0..4]))
+        } else {
+            format!("0x{}", hex::encode(&trace.input))
+        };
+
+        out.push_str(
+            &format!(
+                "{}[{}] {:?} -> {:?} {} value: {} gas used: {}/{}\n",
+                prefix,
+                trace.kind.as_str(),
+                trace.from,
+                trace.to,
+                selector,
+                trace.value,
+                trace.gas_used,
+                trace.gas_limit
+            )
+        );
+
+        let child_prefix = format!("{}  ", prefix);
+
+        for log in &trace.logs {
+            let topic0 = log.topics.first().cloned().unwrap_or_default();
+            out.push_str(&format!("{}emit {:?} from {:?}\n", child_prefix, topic0, log.address));
+        }
+
+        for child in &trace.children {
+            self.node_to_string(*child, &child_prefix, out);
+        }
+
+        if trace.is_reverted {
+            let revert_pc = match trace.revert_pc {
+                Some(pc) => format!(" at pc {}", pc),
+                None => String::new(),
+            };
+            let reason = match trace.revert_reason() {
+                Some(reason) => reason,
+                None => format!("0x{}", hex::encode(&trace.output)),
+            };
+            out.push_str(
+                &format!("{}<- {:?}{} {}\n", child_prefix, trace.status, revert_pc, reason)
+            );
+        }
+    }
+
+    // pushes a new frame and links it to its parent
+    fn start_trace(
+        &mut self,
+        depth: usize,
+        kind: CallKind,
+        from: rAddress,
+        to: rAddress,
+        value: U256,
+        input: rBytes,
+        gas_limit: u64
+    ) {
+        let index = self.traces.len();
+
+        if let Some(parent) = self.stack.last() {
+            self.traces[*parent].children.push(index);
+        }
+
+        self.traces.push(CallTrace {
+            depth,
+            kind,
+            from: from.0.into(),
+            to: to.0.into(),
+            value,
+            input: input.into(),
+            output: Bytes::default(),
+            gas_limit,
+            gas_used: 0,
+            status: InstructionResult::Continue,
+            is_reverted: false,
+            revert_pc: None,
+            logs: Vec::new(),
+            children: Vec::new(),
+        });
+
+        self.stack.push(index);
+    }
+
+    // pops the current frame and fills in the result
+    fn end_trace(&mut self, gas_used: u64, status: InstructionResult, output: rBytes) {
+        if let Some(index) = self.stack.pop() {
+            let trace = &mut self.traces[index];
+            trace.gas_used = gas_used;
+            trace.status = status;
+            trace.is_reverted = !matches!(
+                status,
+                InstructionResult::Continue |
+                    InstructionResult::Stop |
+                    InstructionResult::Return |
+                    InstructionResult::SelfDestruct
+            );
+            trace.output = output.into();
+        }
+    }
+}
+
+impl<DB> Inspector<DB> for CallTracer where DB: Database {
+    fn step(&mut self, interpreter: &mut Interpreter, _data: &mut EVMData<'_, DB>) -> InstructionResult {
+        let pc = interpreter.program_counter();
+        let op = interpreter.contract.bytecode.bytecode()[pc];
+
+        // record where the revert happened
+        if op == opcode::REVERT || op == opcode::INVALID {
+            if let Some(index) = self.stack.last() {
+                self.traces[*index].revert_pc = Some(pc);
+            }
+        }
+
+        InstructionResult::Continue
+    }
+
+    fn log(
+        &mut self,
+        _evm_data: &mut EVMData<'_, DB>,
+        address: &rAddress,
+        topics: &[B256],
+        data: &rBytes
+    ) {
+        if let Some(index) = self.stack.last() {
+            self.traces[*index].logs.push(TraceLog {
+                address: address.0.into(),
+                topics: topics
+                    .iter()
+                    .map(|topic| H256::from_slice(topic.as_bytes()))
+                    .collect(),
+                data: data.clone().into(),
+            });
+        }
+    }
+
+    fn call(
+        &mut self,
+        data: &mut EVMData<'_, DB>,
+        inputs: &mut CallInputs
+    ) -> (InstructionResult, Gas, rBytes) {
+        let kind = match inputs.context.scheme {
+            CallScheme::Call => CallKind::Call,
+            CallScheme::StaticCall => CallKind::StaticCall,
+            CallScheme::DelegateCall => CallKind::DelegateCall,
+            CallScheme::CallCode => CallKind::CallCode,
+        };
+
+        self.start_trace(
+            data.journaled_state.depth() as usize,
+            kind,
+            inputs.context.caller,
+            inputs.contract,
+            inputs.transfer.value.into(),
+            inputs.input.clone(),
+            inputs.gas_limit
+        );
+
+        (InstructionResult::Continue, Gas::new(0), rBytes::new())
+    }
+
+    fn call_end(
+        &mut self,
+        _data: &mut EVMData<'_, DB>,
+        _inputs: &CallInputs,
+        remaining_gas: Gas,
+        ret: InstructionResult,
+        out: rBytes
+    ) -> (InstructionResult, Gas, rBytes) {
+        self.end_trace(remaining_gas.spend(), ret, out.clone());
+
+        (ret, remaining_gas, out)
+    }
+
+    fn create(
+        &mut self,
+        data: &mut EVMData<'_, DB>,
+        inputs: &mut CreateInputs
+    ) -> (InstructionResult, Option<rAddress>, Gas, rBytes) {
+        let kind = match inputs.scheme {
+            CreateScheme::Create => CallKind::Create,
+            CreateScheme::Create2 { .. } => CallKind::Create2,
+        };
+
+        // the address is only known when the create ends
+        self.start_trace(
+            data.journaled_state.depth() as usize,
+            kind,
+            inputs.caller,
+            rAddress::zero(),
+            inputs.value.into(),
+            inputs.init_code.clone(),
+            inputs.gas_limit
+        );
+
+        (InstructionResult::Continue, None, Gas::new(0), rBytes::new())
+    }
+
+    fn create_end(
+        &mut self,
+        _data: &mut EVMData<'_, DB>,
+        _inputs: &CreateInputs,
+        ret: InstructionResult,
+        address: Option<rAddress>,
+        remaining_gas: Gas,
+        out: rBytes
+    ) -> (InstructionResult, Option<rAddress>, Gas, rBytes) {
+        if let (Some(index), Some(address)) = (self.stack.last(), address) {
+            self.traces[*index].to = address.0.into();
+        }
+
+        self.end_trace(remaining_gas.spend(), ret, out.clone());
+
+        (ret, address, remaining_gas, out)
+    }
+}
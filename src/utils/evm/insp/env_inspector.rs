@@ -0,0 +1,75 @@
+use std::collections::HashSet;
+use ethers::types::Address;
+use revm::{
+    interpreter::{ opcode, InstructionResult, Interpreter },
+    primitives::B160 as rAddress,
+    Database,
+    EVMData,
+    Inspector,
+};
+
+// Environment opcodes anti-bot code uses to single out snipers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EnvOpcode {
+    Origin,
+    GasPrice,
+    Number,
+    Timestamp,
+    Coinbase,
+    BaseFee,
+}
+
+impl EnvOpcode {
+    fn from_opcode(op: u8) -> Option<Self> {
+        match op {
+            opcode::ORIGIN => Some(EnvOpcode::Origin),
+            opcode::GASPRICE => Some(EnvOpcode::GasPrice),
+            opcode::NUMBER => Some(EnvOpcode::Number),
+            opcode::TIMESTAMP => Some(EnvOpcode::Timestamp),
+            opcode::COINBASE => Some(EnvOpcode::Coinbase),
+            opcode::BASEFEE => Some(EnvOpcode::BaseFee),
+            _ => None,
+        }
+    }
+}
+
+// An [Inspector] that records the environment opcodes a contract executes
+//
+// Only opcodes running in the storage context of the target are recorded,
+// the pair reads TIMESTAMP on every swap and would flag every token
+#[derive(Debug)]
+pub struct EnvInspector {
+    target: rAddress,
+    opcodes: HashSet<EnvOpcode>,
+}
+
+impl EnvInspector {
+    pub fn new(target: Address) -> Self {
+        Self {
+            target: target.0.into(),
+            opcodes: HashSet::new(),
+        }
+    }
+
+    // returns the recorded opcodes
+    pub fn opcodes(&self) -> &HashSet<EnvOpcode> {
+        &self.opcodes
+    }
+}
+
+impl<DB> Inspector<DB> for EnvInspector where DB: Database {
+    fn step(&mut self, interpreter: &mut Interpreter, _data: &mut EVMData<'_, DB>) -> InstructionResult {
+        if interpreter.contract.address != self.target {
+            return InstructionResult::Continue;
+        }
+
+        let pc = interpreter.program_counter();
+        let op = interpreter.contract.bytecode.bytecode()[pc];
+
+        if let Some(env_opcode) = EnvOpcode::from_opcode(op) {
+            self.opcodes.insert(env_opcode);
+        }
+
+        InstructionResult::Continue
+    }
+}
@@ -1 +1,5 @@
-pub mod access_list;
\ No newline at end of file
+pub mod access_list;
+pub mod call_tracer;
+pub mod sload_tracer;
+pub mod env_inspector;
+pub mod storage_diff;
\ No newline at end of file
@@ -0,0 +1,43 @@
+use ethers::types::{ Address, U256 };
+use revm::{ interpreter::{ opcode, InstructionResult, Interpreter }, Database, EVMData, Inspector };
+
+// An [Inspector] that records every SLOAD in execution order
+//
+// The address is the storage context, so reads done by a proxy implementation
+// through DELEGATECALL are recorded under the proxy
+#[derive(Debug, Default)]
+pub struct SloadTracer {
+    sloads: Vec<(Address, U256)>,
+}
+
+impl SloadTracer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // returns the slots read from an address, last read first, without duplicates
+    pub fn slots_of(&self, address: Address) -> Vec<U256> {
+        let mut slots: Vec<U256> = Vec::new();
+        for (sload_address, slot) in self.sloads.iter().rev() {
+            if *sload_address == address && !slots.contains(slot) {
+                slots.push(*slot);
+            }
+        }
+        slots
+    }
+}
+
+impl<DB> Inspector<DB> for SloadTracer where DB: Database {
+    fn step(&mut self, interpreter: &mut Interpreter, _data: &mut EVMData<'_, DB>) -> InstructionResult {
+        let pc = interpreter.program_counter();
+        let op = interpreter.contract.bytecode.bytecode()[pc];
+
+        if op == opcode::SLOAD {
+            if let Ok(slot) = interpreter.stack().peek(0) {
+                self.sloads.push((interpreter.contract.address.0.into(), slot.into()));
+            }
+        }
+
+        InstructionResult::Continue
+    }
+}
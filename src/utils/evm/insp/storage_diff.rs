@@ -0,0 +1,152 @@
+use std::collections::HashSet;
+use ethers::types::{ Address, U256 };
+use revm::{
+    interpreter::{ opcode, CallInputs, CreateInputs, Gas, InstructionResult, Interpreter },
+    primitives::{ B160 as rAddress, Bytes as rBytes },
+    Database,
+    EVMData,
+    Inspector,
+};
+
+// A storage write that survived the end of the transaction
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageWrite {
+    pub address: Address,
+    pub slot: U256,
+    pub value: U256,
+}
+
+// An [Inspector] that records the SSTOREs to a set of watched addresses
+//
+// Writes are kept per call frame and dropped when the frame reverts,
+// so only the writes that persist are reported
+#[derive(Debug)]
+pub struct StorageDiffInspector {
+    watched: HashSet<rAddress>,
+    // writes of the frames that are currently executing, the first frame is the root
+    frames: Vec<Vec<StorageWrite>>,
+}
+
+impl StorageDiffInspector {
+    pub fn new(watched: Vec<Address>) -> Self {
+        Self {
+            watched: watched
+                .into_iter()
+                .map(|address| address.0.into())
+                .collect(),
+            frames: vec![Vec::new()],
+        }
+    }
+
+    // returns the persisted writes, the last write to a slot wins
+    pub fn into_writes(mut self) -> Vec<StorageWrite> {
+        let writes = self.frames.swap_remove(0);
+
+        let mut persisted: Vec<StorageWrite> = Vec::new();
+        for write in writes.into_iter().rev() {
+            let exists = persisted
+                .iter()
+                .any(|x| x.address == write.address && x.slot == write.slot);
+            if !exists {
+                persisted.push(write);
+            }
+        }
+        persisted.reverse();
+        persisted
+    }
+
+    fn enter_frame(&mut self) {
+        self.frames.push(Vec::new());
+    }
+
+    // merges the frame into its parent, or drops it if it reverted
+    fn exit_frame(&mut self, ret: InstructionResult) {
+        if self.frames.len() <= 1 {
+            return;
+        }
+
+        let writes = self.frames.pop().unwrap_or_default();
+
+        let is_success = matches!(
+            ret,
+            InstructionResult::Continue |
+                InstructionResult::Stop |
+                InstructionResult::Return |
+                InstructionResult::SelfDestruct
+        );
+
+        if is_success {
+            if let Some(parent) = self.frames.last_mut() {
+                parent.extend(writes);
+            }
+        }
+    }
+}
+
+impl<DB> Inspector<DB> for StorageDiffInspector where DB: Database {
+    fn step(&mut self, interpreter: &mut Interpreter, _data: &mut EVMData<'_, DB>) -> InstructionResult {
+        let pc = interpreter.program_counter();
+        let op = interpreter.contract.bytecode.bytecode()[pc];
+
+        if op == opcode::SSTORE && self.watched.contains(&interpreter.contract.address) {
+            if let (Ok(slot), Ok(value)) = (interpreter.stack().peek(0), interpreter.stack().peek(1)) {
+                if let Some(frame) = self.frames.last_mut() {
+                    frame.push(StorageWrite {
+                        address: interpreter.contract.address.0.into(),
+                        slot: slot.into(),
+                        value: value.into(),
+                    });
+                }
+            }
+        }
+
+        InstructionResult::Continue
+    }
+
+    fn call(
+        &mut self,
+        _data: &mut EVMData<'_, DB>,
+        _inputs: &mut CallInputs
+    ) -> (InstructionResult, Gas, rBytes) {
+        self.enter_frame();
+
+        (InstructionResult::Continue, Gas::new(0), rBytes::new())
+    }
+
+    fn call_end(
+        &mut self,
+        _data: &mut EVMData<'_, DB>,
+        _inputs: &CallInputs,
+        remaining_gas: Gas,
+        ret: InstructionResult,
+        out: rBytes
+    ) -> (InstructionResult, Gas, rBytes) {
+        self.exit_frame(ret);
+
+        (ret, remaining_gas, out)
+    }
+
+    fn create(
+        &mut self,
+        _data: &mut EVMData<'_, DB>,
+        _inputs: &mut CreateInputs
+    ) -> (InstructionResult, Option<rAddress>, Gas, rBytes) {
+        self.enter_frame();
+
+        (InstructionResult::Continue, None, Gas::new(0), rBytes::new())
+    }
+
+    fn create_end(
+        &mut self,
+        _data: &mut EVMData<'_, DB>,
+        _inputs: &CreateInputs,
+        ret: InstructionResult,
+        address: Option<rAddress>,
+        remaining_gas: Gas,
+        out: rBytes
+    ) -> (InstructionResult, Option<rAddress>, Gas, rBytes) {
+        self.exit_frame(ret);
+
+        (ret, address, remaining_gas, out)
+    }
+}
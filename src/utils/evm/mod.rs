@@ -1,2 +1,5 @@
 pub mod simulate;
-pub mod insp;
\ No newline at end of file
+pub mod insp;
+pub mod executor;
+pub mod state_override;
+pub mod balance_slot;
\ No newline at end of file
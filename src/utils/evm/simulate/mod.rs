@@ -1,16 +1,35 @@
 use ethers::prelude::*;
 use std::str::FromStr;
-use ethers::abi::Tokenizable;
+use ethers::abi::{ Detokenize, Token, Tokenizable };
 use ethabi::RawLog;
 use revm::EVM;
+use revm::db::DatabaseRef;
 use crate::forked_db::{ fork_db::ForkDB, match_output, match_output_reverted };
 use crate::oracles::block_oracle::BlockInfo;
 use crate::utils::{ helpers::*, types::structs::pool::Pool };
-use revm::primitives::{TransactTo, Log, Address as rAddress, U256 as rU256, Bytes as rBytes};
+use revm::primitives::{TransactTo, CreateScheme, Log, ExecutionResult, Address as rAddress, U256 as rU256, Bytes as rBytes};
 
 use crate::utils::evm::insp::access_list::AccessListInspector;
-use crate::utils::abi::{ ERC20_BALANCE_OF, TOKEN0, TOKEN1, V2_SWAP_EVENT, TRANSFER_EVENT, encode_swap };
+use crate::utils::evm::insp::call_tracer::CallTracer;
+use crate::utils::abi::{
+    ERC20_BALANCE_OF,
+    TOKEN0,
+    TOKEN1,
+    OWNER,
+    TOTAL_SUPPLY,
+    ERC20_METADATA,
+    UNICRYPT_LOCKER,
+    V3_POOL,
+    V3_FACTORY,
+    V2_SWAP_EVENT,
+    V3_SWAP_EVENT,
+    TRANSFER_EVENT,
+    encode_buy,
+    encode_sell,
+};
 use crate::utils::constants::*;
+use crate::utils::types::structs::base_token::get_base_token;
+use crate::utils::uniswap_v2::sort_reserves;
 
 use anyhow::anyhow;
 
@@ -18,10 +37,31 @@ use super::insp::access_list::convert_access_list;
 
 pub mod sim;
 
+// The amount of weth we get for selling a held token
+// if from_evm is false the amount was calculated with the V2 math
+#[derive(Debug, Clone)]
+pub struct SellQuote {
+    pub amount_out: U256,
+    pub sell_tax_bps: Option<u64>,
+    pub balance_slot: Option<U256>,
+    pub from_evm: bool,
+}
+
+// The outcome of a bundle simulated in bundle order on the target block
+#[derive(Debug, Clone)]
+pub struct BundleSimulation {
+    pub gas_used: u64,
+    // the amount our tx receives
+    pub amount_out: U256,
+    // what the block builder earns from the bundle, priority fees plus direct transfers
+    pub coinbase_payment: U256,
+}
+
 pub struct SimulationResult {
     pub is_reverted: bool,
     pub logs: Vec<Log>,
     pub gas_used: u64,
+    pub gas_refunded: u64,
     pub output: rBytes,
 }
 
@@ -39,13 +79,7 @@ pub fn generate_call_data(
 
     // if we buy
     if do_we_buy {
-        call_data = encode_swap(
-            pool.token_0, // weth
-            pool.token_1, // shitcoin
-            pool.address,
-            amount_in,
-            minimum_received // expected amount
-        );
+        call_data = encode_buy(pool, amount_in, minimum_received);
     } else {
         // we sell
 
@@ -56,13 +90,7 @@ pub fn generate_call_data(
             evm
         )?;
 
-        call_data = encode_swap(
-            pool.token_1, // shitcoin
-            pool.token_0, // weth
-            pool.address,
-            token_balance,
-            minimum_received // expected amount
-        );
+        call_data = encode_sell(pool, token_balance, minimum_received);
     }
 
     Ok(call_data)
@@ -105,6 +133,126 @@ pub fn get_erc20_balance(
     Ok(bal)
 }
 
+// get the owner of a token
+// returns None if the token has no owner() function
+pub fn get_token_owner(
+    token: Address,
+    evm: &mut EVM<ForkDB>
+) -> Result<Option<Address>, anyhow::Error> {
+    evm.env.tx.caller = CALLER_ADDRESS.0.into();
+    evm.env.tx.transact_to = TransactTo::Call(token.0.into());
+    evm.env.tx.data = OWNER.encode("owner", ()).unwrap().0;
+    evm.env.tx.value = rU256::ZERO;
+
+    let result = evm.transact_ref()?.result;
+
+    let output = match match_output(result) {
+        Ok(output) => output,
+        Err(_) => {
+            return Ok(None);
+        }
+    };
+
+    let owner = match OWNER.decode_output("owner", &output) {
+        Ok(owner) => owner,
+        Err(_) => {
+            return Ok(None);
+        }
+    };
+
+    Ok(Some(owner))
+}
+
+// get the total supply of a token
+// works for the LP token of a pool too
+pub fn get_total_supply(
+    token: Address,
+    evm: &mut EVM<ForkDB>
+) -> Result<U256, anyhow::Error> {
+    evm.env.tx.caller = CALLER_ADDRESS.0.into();
+    evm.env.tx.transact_to = TransactTo::Call(token.0.into());
+    evm.env.tx.data = TOTAL_SUPPLY.encode("totalSupply", ()).unwrap().0;
+    evm.env.tx.value = rU256::ZERO;
+
+    let result = evm.transact_ref()?.result;
+
+    let output = match_output(result)?;
+
+    let total_supply = TOTAL_SUPPLY.decode_output("totalSupply", &output)?;
+
+    Ok(total_supply)
+}
+
+// get the name, symbol and decimals of a token
+// each is None if the token doesnt implement it or returns something else, eg. a bytes32 name
+pub fn get_token_metadata(
+    token: Address,
+    evm: &mut EVM<ForkDB>
+) -> (Option<String>, Option<String>, Option<u8>) {
+    let name = call_erc20_metadata(token, "name", evm);
+    let symbol = call_erc20_metadata(token, "symbol", evm);
+    let decimals = call_erc20_metadata(token, "decimals", evm);
+
+    (name, symbol, decimals)
+}
+
+fn call_erc20_metadata<D: Detokenize>(
+    token: Address,
+    method: &str,
+    evm: &mut EVM<ForkDB>
+) -> Option<D> {
+    evm.env.tx.caller = CALLER_ADDRESS.0.into();
+    evm.env.tx.transact_to = TransactTo::Call(token.0.into());
+    evm.env.tx.data = ERC20_METADATA.encode(method, ()).ok()?.0;
+    evm.env.tx.value = rU256::ZERO;
+
+    let result = evm.transact_ref().ok()?.result;
+    let output = match_output(result).ok()?;
+
+    ERC20_METADATA.decode_output(method, &output).ok()
+}
+
+// get the earliest unlock time of the LP tokens locked in the Unicrypt V2 locker
+// returns None if there are no locks for the LP token
+pub fn get_unicrypt_unlock_time(
+    lp_token: Address,
+    evm: &mut EVM<ForkDB>
+) -> Result<Option<U256>, anyhow::Error> {
+    evm.env.tx.caller = CALLER_ADDRESS.0.into();
+    evm.env.tx.transact_to = TransactTo::Call(UNICRYPT_V2_LOCKER.0.into());
+    evm.env.tx.data = UNICRYPT_LOCKER.encode("getNumLocksForToken", lp_token).unwrap().0;
+    evm.env.tx.value = rU256::ZERO;
+
+    let result = evm.transact_ref()?.result;
+
+    let output = match_output(result)?;
+
+    let num_locks: U256 = UNICRYPT_LOCKER.decode_output("getNumLocksForToken", &output)?;
+
+    let mut unlock_time: Option<U256> = None;
+
+    for index in 0..num_locks.as_u64() {
+        evm.env.tx.data = UNICRYPT_LOCKER.encode(
+            "tokenLocks",
+            (lp_token, U256::from(index))
+        ).unwrap().0;
+
+        let result = evm.transact_ref()?.result;
+
+        let output = match_output(result)?;
+
+        let (_, _, _, unlock_date, _, _): (U256, U256, U256, U256, U256, Address) =
+            UNICRYPT_LOCKER.decode_output("tokenLocks", &output)?;
+
+        unlock_time = match unlock_time {
+            Some(time) if time <= unlock_date => Some(time),
+            _ => Some(unlock_date),
+        };
+    }
+
+    Ok(unlock_time)
+}
+
 // simulate a token call to the pool address
 // returns token0 and token1
 fn get_tokens_from_pool(
@@ -134,38 +282,230 @@ fn get_tokens_from_pool(
 }
 
 
-/// Simulates a call without any inspectors
+// the eth balance of an address in the current state of the evm
+pub fn get_eth_balance(address: rAddress, evm: &EVM<ForkDB>) -> Result<U256, anyhow::Error> {
+    let fork_db = evm.db.as_ref().ok_or(anyhow!("Evm has no database"))?;
+    let balance = match DatabaseRef::basic(fork_db, address)? {
+        Some(account) => account.balance.into(),
+        None => U256::zero(),
+    };
+    Ok(balance)
+}
+
+// sets the evm tx env from a signed transaction
+// the gas price is left to revm so the coinbase gets the real priority fee
+pub fn set_tx_env(tx: &Transaction, evm: &mut EVM<ForkDB>) {
+    evm.env.tx.caller = tx.from.0.into();
+    evm.env.tx.transact_to = match tx.to {
+        Some(to) => TransactTo::Call(to.0.into()),
+        None => TransactTo::Create(CreateScheme::Create),
+    };
+    evm.env.tx.data = tx.input.0.clone();
+    evm.env.tx.value = tx.value.into();
+    evm.env.tx.gas_limit = tx.gas.as_u64();
+    evm.env.tx.gas_price = tx.max_fee_per_gas.or(tx.gas_price).unwrap_or_default().into();
+    evm.env.tx.gas_priority_fee = tx.max_priority_fee_per_gas.map(|x| x.into());
+    evm.env.tx.access_list = tx.access_list
+        .clone()
+        .unwrap_or_default()
+        .0.iter()
+        .map(|item| {
+            (
+                item.address.0.into(),
+                item.storage_keys
+                    .iter()
+                    .map(|key| rU256::from_be_bytes(key.0))
+                    .collect(),
+            )
+        })
+        .collect();
+}
+
+// reads a storage slot directly from the fork db, without running the evm
+pub fn read_storage(
+    address: Address,
+    slot: U256,
+    fork_db: &ForkDB
+) -> Result<U256, anyhow::Error> {
+    let value = DatabaseRef::storage(fork_db, address.0.into(), slot.into())?;
+    Ok(value.into())
+}
+
+// reads the reserves of a V2 pair from storage
+// reserve0, reserve1 and blockTimestampLast are packed in slot 8
+pub fn get_v2_reserves_from_storage(
+    pool_address: Address,
+    fork_db: &ForkDB
+) -> Result<(U256, U256), anyhow::Error> {
+    let packed = read_storage(pool_address, U256::from(8u64), fork_db)?;
+
+    let mask = (U256::one() << 112) - 1;
+    let reserve_0 = packed & mask;
+    let reserve_1 = (packed >> 112) & mask;
+
+    Ok((reserve_0, reserve_1))
+}
+
+// values an amount of a base token in ETH with the spot price of its V2 pair with WETH
+pub fn base_to_eth(
+    base_token: Address,
+    amount: U256,
+    fork_db: &ForkDB
+) -> Result<U256, anyhow::Error> {
+    let base = get_base_token(base_token).ok_or(anyhow!("{:?} is not a base token", base_token))?;
+
+    if base.is_weth() {
+        return Ok(amount);
+    }
+
+    let (reserve_0, reserve_1) = get_v2_reserves_from_storage(base.weth_pair, fork_db)?;
+    let (base_reserve, weth_reserve) = sort_reserves(base.address, *WETH, reserve_0, reserve_1);
+
+    if base_reserve.is_zero() {
+        return Err(anyhow!("Base pair {:?} has no reserves", base.weth_pair));
+    }
+
+    Ok((amount * weth_reserve) / base_reserve)
+}
+
+// reads the price and the active liquidity of a V3 pool from storage
+// sqrtPriceX96 is in the lower 160 bits of slot0, liquidity is in slot 4
+pub fn get_v3_state_from_storage(
+    pool_address: Address,
+    fork_db: &ForkDB
+) -> Result<(U256, U256), anyhow::Error> {
+    let slot0 = read_storage(pool_address, U256::zero(), fork_db)?;
+    let liquidity = read_storage(pool_address, U256::from(4u64), fork_db)?;
+
+    let sqrt_price_x96 = slot0 & ((U256::one() << 160) - 1);
+    let liquidity = liquidity & ((U256::one() << 128) - 1);
+
+    Ok((sqrt_price_x96, liquidity))
+}
+
+// simulate a fee call to a V3 pool
+pub fn get_v3_pool_fee(pool_address: Address, evm: &mut EVM<ForkDB>) -> Result<u32, anyhow::Error> {
+    evm.env.tx.caller = CALLER_ADDRESS.0.into();
+    evm.env.tx.transact_to = TransactTo::Call(pool_address.0.into());
+    evm.env.tx.data = V3_POOL.encode("fee", ())?.0;
+    evm.env.tx.value = rU256::ZERO;
+
+    let result = evm.transact_ref()?.result;
+    let output = match_output(result)?;
+
+    let fee: u32 = V3_POOL.decode_output("fee", &output)?;
+
+    Ok(fee)
+}
+
+// checks that a V3 pool was deployed by the Uniswap V3 factory
+pub fn is_v3_factory_pool(
+    pool_address: Address,
+    token_0: Address,
+    token_1: Address,
+    fee: u32,
+    evm: &mut EVM<ForkDB>
+) -> Result<bool, anyhow::Error> {
+    evm.env.tx.caller = CALLER_ADDRESS.0.into();
+    evm.env.tx.transact_to = TransactTo::Call(UNISWAP_V3_FACTORY.0.into());
+    evm.env.tx.data = V3_FACTORY.encode("getPool", (token_0, token_1, fee))?.0;
+    evm.env.tx.value = rU256::ZERO;
+
+    let result = evm.transact_ref()?.result;
+    let output = match_output(result)?;
+
+    let factory_pool: Address = V3_FACTORY.decode_output("getPool", &output)?;
+
+    Ok(factory_pool == pool_address)
+}
+
+// storage slot of balances[owner] for a solidity mapping declared at mapping_slot
+pub fn get_mapping_slot(owner: Address, mapping_slot: U256) -> U256 {
+    let key = ethers::abi::encode(&[Token::Address(owner), Token::Uint(mapping_slot)]);
+    U256::from_big_endian(&ethers::utils::keccak256(key))
+}
+
+/// Simulates a call, optionally recording the call tree with a [CallTracer]
 /// Returns [SimulationResult]
 pub fn sim_call(
     caller: Address,
     transact_to: Address,
     call_data: Bytes,
     apply_changes: bool,
+    tracer: Option<&mut CallTracer>,
     evm: &mut EVM<ForkDB>
 ) -> Result<SimulationResult, anyhow::Error> {
     evm.env.tx.caller = caller.0.into();
     evm.env.tx.transact_to = TransactTo::Call(transact_to.0.into());
     evm.env.tx.data = call_data.0;
 
-    let result;
+    let result = match (tracer, apply_changes) {
+        (Some(tracer), true) => evm.inspect_commit(tracer)?,
+        (Some(tracer), false) => evm.inspect_ref(tracer)?.result,
+        (None, true) => evm.transact_commit()?,
+        (None, false) => evm.transact_ref()?.result,
+    };
 
-    if apply_changes {
-        result = evm.transact_commit()?;
-    } else {
-        result = evm.transact_ref()?.result;
-    }
+    Ok(into_simulation_result(result))
+}
+
+// converts the revm result into a [SimulationResult]
+pub fn into_simulation_result(result: ExecutionResult) -> SimulationResult {
     let is_reverted = match_output_reverted(result.clone());
     let logs = result.logs();
     let gas_used = result.clone().gas_used();
+    // refunds are only paid back after execution so the gas limit must cover them
+    let gas_refunded = match result {
+        ExecutionResult::Success { gas_refunded, .. } => gas_refunded,
+        _ => 0,
+    };
     let output = result.into_output().unwrap_or_default();
-    let sim_result = SimulationResult {
+
+    SimulationResult {
         is_reverted,
         logs,
         gas_used,
-        output
-    };
+        gas_refunded,
+        output,
+    }
+}
 
-    Ok(sim_result)
+// Re-runs a call with the [CallTracer] attached and logs the call tree
+// used to debug tokens we reject, the state of the evm is not changed
+pub fn trace_rejected_call(
+    token: Address,
+    caller: Address,
+    transact_to: Address,
+    call_data: Bytes,
+    evm: &mut EVM<ForkDB>
+) {
+    let mut tracer = CallTracer::new();
+
+    if let Err(e) = sim_call(caller, transact_to, call_data, false, Some(&mut tracer), evm) {
+        log::error!("Failed to trace call for {:?}: {:?}", token, e);
+        return;
+    }
+
+    if let Some(revert_point) = tracer.revert_point() {
+        log::warn!(
+            "Revert point for {:?}: {:?} at pc {:?} reason: {}",
+            token,
+            revert_point.to,
+            revert_point.revert_pc,
+            revert_point.revert_reason().unwrap_or_default()
+        );
+    }
+    log::warn!("Call trace for {:?}:\n{}", token, tracer.to_pretty_tree());
+
+    if *SAVE_REJECTED_TRACES {
+        let path = format!("{}/{:?}.json", *TRACES_DIR, token);
+        let saved = std::fs::create_dir_all(&*TRACES_DIR)
+            .map_err(anyhow::Error::from)
+            .and_then(|_| tracer.save_json(&path));
+        if let Err(e) = saved {
+            log::error!("Failed to save call trace to {}: {:?}", path, e);
+        }
+    }
 }
 
 
@@ -177,6 +517,7 @@ pub fn sim_call(
 pub fn get_real_amount_from_logs(
     logs: Vec<Log>,
     pool_address: H160,
+    recipient: Address
 ) -> Result<(U256, U256), anyhow::Error> {
     // hold decoded events
     let mut swap_opt = None;
@@ -196,7 +537,17 @@ pub fn get_real_amount_from_logs(
                 data: log.data.clone().to_vec(),
             })
         {
-            swap_opt = Some(decoded_log);
+            swap_opt = Some(get_amount_out_from_v2_swap(&decoded_log));
+        }
+
+        // V3 pools emit a different swap event
+        if
+            let Ok(decoded_log) = V3_SWAP_EVENT.parse_log(RawLog {
+                topics: converted_topics.clone(),
+                data: log.data.clone().to_vec(),
+            })
+        {
+            swap_opt = Some(get_amount_out_from_v3_swap(&decoded_log));
         }
 
         // get all transfer logs
@@ -211,8 +562,8 @@ pub fn get_real_amount_from_logs(
     }
 
     // if for some reason we dont get the swap log (unlikely) return err
-    let swap_log = match swap_opt {
-        Some(swap) => swap,
+    let token_amount_from_swap = match swap_opt {
+        Some(amount) => amount,
         None => {
             return Err(anyhow!("Swap event not found"));
         }
@@ -223,17 +574,6 @@ pub fn get_real_amount_from_logs(
         return Err(anyhow!("Transfer events not found"));
     }
 
-    // get the amount of tokens we are going to receive from the swap_log
-    let amount_0_out = swap_log.params[3].value.clone().into_token().into_uint().unwrap();
-    let amount_1_out = swap_log.params[4].value.clone().into_token().into_uint().unwrap();
-
-    // the amount of tokens should be either amount 0 out or amount 1 out
-    // which ever is not zero is the tokens we receive
-    let token_amount_from_swap = if amount_0_out == U256::zero() {
-        amount_1_out
-    } else {
-        amount_0_out
-    };
     let mut got_amount = false;
     let mut real_amount = U256::zero();
 
@@ -244,7 +584,7 @@ pub fn get_real_amount_from_logs(
         // to address must be our contract address
         let to = log.params[1].value.clone().into_token().into_address().unwrap();
 
-        if from == pool_address && to == *CONTRACT_ADDRESS {
+        if from == pool_address && to == recipient {
             // get the amount of tokens
             real_amount = log.params[2].value.clone().into_token().into_uint().unwrap();
             got_amount = true;
@@ -257,3 +597,29 @@ pub fn get_real_amount_from_logs(
 
     Ok((real_amount, token_amount_from_swap))
 }
+
+// the amount of tokens should be either amount 0 out or amount 1 out
+// which ever is not zero is the tokens we receive
+fn get_amount_out_from_v2_swap(swap_log: &ethabi::Log) -> U256 {
+    let amount_0_out = swap_log.params[3].value.clone().into_token().into_uint().unwrap();
+    let amount_1_out = swap_log.params[4].value.clone().into_token().into_uint().unwrap();
+
+    if amount_0_out == U256::zero() {
+        amount_1_out
+    } else {
+        amount_0_out
+    }
+}
+
+// V3 swap amounts are deltas of the pool balances
+// the negative one is the amount that left the pool
+fn get_amount_out_from_v3_swap(swap_log: &ethabi::Log) -> U256 {
+    let amount_0 = I256::from_raw(swap_log.params[2].value.clone().into_token().into_int().unwrap());
+    let amount_1 = I256::from_raw(swap_log.params[3].value.clone().into_token().into_int().unwrap());
+
+    if amount_0.is_negative() {
+        amount_0.unsigned_abs()
+    } else {
+        amount_1.unsigned_abs()
+    }
+}
@@ -10,10 +10,25 @@ use crate::{ forked_db::fork_db::ForkDB, utils::types::structs::tx_data::TxData
 use crate::oracles::block_oracle::BlockInfo;
 use ethers::abi::Tokenizable;
 use ethabi::RawLog;
+use std::collections::HashSet;
 
 use crate::utils::abi::*;
 use crate::utils::types::structs::snipe_tx::SnipeTx;
-use crate::utils::types::structs::pool::Pool;
+use crate::utils::types::structs::bundle_plan::BundlePlan;
+use crate::utils::types::structs::pool::{ Pool, PoolKind };
+use crate::utils::types::structs::token_checks::{ TokenChecks, LpStatus };
+use crate::utils::types::structs::token_info::TokenInfo;
+use crate::utils::types::structs::competition::Competition;
+use crate::utils::evm::state_override::{ local_sniper_fork, seed_token_balance };
+use crate::utils::evm::balance_slot::get_balance_slot;
+use crate::utils::evm::insp::env_inspector::{ EnvInspector, EnvOpcode };
+use crate::utils::evm::insp::storage_diff::{ StorageDiffInspector, StorageWrite };
+use crate::utils::types::structs::storage_change::{ SlotChange, SlotKind };
+use crate::utils::types::structs::env_sensitivity::{ EnvSensitivity, EnvVariation, SwapOutcome };
+use crate::utils::uniswap_v2::{ get_amount_in, get_amount_out, sort_reserves };
+use crate::utils::uniswap_v3::estimate_base_liquidity;
+use crate::utils::types::structs::base_token::get_base_token;
+use crate::utils::types::structs::v2_fork::{ get_v2_fork, find_v2_fork_of_pair };
 
 // finds the amount in weth to buy the token
 // ** A lot of tokens have min and max buy size
@@ -34,13 +49,9 @@ pub fn find_amount_in(
     let mut is_reverted = true;
 
     // call data
-    let mut call_data = encode_swap(
-        pool.token_0, // weth
-        pool.token_1, // shitcoin
-        pool.address,
-        amount_in,
-        U256::from(0u128)
-    );
+    let mut call_data = encode_buy(pool, amount_in, U256::from(0u128));
+
+    let (fork_db, sniper) = local_sniper_fork(fork_db)?;
 
     // ** a simple while loop to find the amount in
 
@@ -55,14 +66,15 @@ pub fn find_amount_in(
         if let Some(ref tx) = pending_tx {
             // first simulate and commit the pending tx so we can buy the token
             evm.env.tx.value = tx.value.into();
-            let _ = sim_call(tx.from, tx.to.unwrap_or_default(), tx.input.clone(), true, &mut evm)?;
+            let _ = sim_call(tx.from, tx.to.unwrap_or_default(), tx.input.clone(), true, None, &mut evm)?;
         }
 
         let result = sim_call(
             *CALLER_ADDRESS,
-            *CONTRACT_ADDRESS,
+            sniper,
             call_data.clone().into(),
             false,
+            None,
             &mut evm
         )?;
 
@@ -72,13 +84,7 @@ pub fn find_amount_in(
             if amount_in < *MIN_BUY_SIZE || attempts >= max_attempts {
                 break;
             }
-            call_data = encode_swap(
-                pool.token_0,
-                pool.token_1,
-                pool.address,
-                amount_in,
-                U256::from(0u128)
-            );
+            call_data = encode_buy(pool, amount_in, U256::from(0u128));
             attempts += 1;
         } else {
             is_reverted = false;
@@ -93,6 +99,96 @@ pub fn find_amount_in(
     return Ok(amount_in);
 }
 
+// Simulates our buy when the builder orders it after the other pending swaps on the pool
+// ** competitors are ordered by their priority fee, highest first
+pub fn simulate_competition(
+    pool: &Pool,
+    amount_in: U256,
+    next_block: &BlockInfo,
+    pending_tx: Option<Transaction>,
+    mut competing_txs: Vec<Transaction>,
+    fork_db: ForkDB
+) -> Result<Competition, anyhow::Error> {
+    let (fork_db, sniper) = local_sniper_fork(fork_db)?;
+
+    let mut evm = revm::EVM::new();
+    evm.database(fork_db);
+
+    // setup the next block state
+    setup_evm(&mut evm, next_block);
+
+    if let Some(ref tx) = pending_tx {
+        evm.env.tx.value = tx.value.into();
+        let _ = sim_call(tx.from, tx.to.unwrap_or_default(), tx.input.clone(), true, None, &mut evm)?;
+    }
+
+    let call_data = encode_buy(pool, amount_in, U256::zero());
+
+    // ** our fill when we are the only buyer
+    evm.env.tx.value = rU256::ZERO;
+    let amount_out_alone = simulate_buy_amount_out(pool, sniper, call_data.clone().into(), &mut evm)?;
+
+    // ** commit the competing swaps
+    competing_txs.sort_by(|a, b| {
+        get_priority_fee(b, next_block.base_fee).cmp(&get_priority_fee(a, next_block.base_fee))
+    });
+
+    let mut competitors = 0;
+    let mut max_competitor_tip = U256::zero();
+
+    for tx in competing_txs {
+        evm.env.tx.value = tx.value.into();
+        let result = match
+            sim_call(tx.from, tx.to.unwrap_or_default(), tx.input.clone(), true, None, &mut evm)
+        {
+            Ok(result) => result,
+            Err(e) => {
+                log::warn!("Failed to simulate competing tx {:?}: {:?}", tx.hash, e);
+                continue;
+            }
+        };
+
+        // only count the txs that actually traded on the pool
+        let traded = !result.is_reverted &&
+            result.logs.iter().any(|log| H160::from(log.address) == pool.address);
+
+        if traded {
+            competitors += 1;
+            max_competitor_tip = max_competitor_tip.max(get_priority_fee(&tx, next_block.base_fee));
+        }
+    }
+
+    // ** our fill after the competitors
+    evm.env.tx.value = rU256::ZERO;
+    let amount_out_after = simulate_buy_amount_out(pool, sniper, call_data.into(), &mut evm)?;
+
+    Ok(Competition {
+        competitors,
+        max_competitor_tip,
+        amount_out_alone,
+        amount_out_after,
+    })
+}
+
+// simulates a buy without committing it
+// returns the real amount of tokens received, 0 if reverted
+fn simulate_buy_amount_out(
+    pool: &Pool,
+    sniper: Address,
+    call_data: Bytes,
+    evm: &mut revm::EVM<ForkDB>
+) -> Result<U256, anyhow::Error> {
+    let result = sim_call(*CALLER_ADDRESS, sniper, call_data, false, None, evm)?;
+
+    if result.is_reverted {
+        return Ok(U256::zero());
+    }
+
+    let (amount_out, _) = get_real_amount_from_logs(result.logs, pool.address, sniper)?;
+
+    Ok(amount_out)
+}
+
 // Checks if the token has taxes
 // we use a resonable amount of weth cause of the price impact
 // ** We also do HoneyPot checks **
@@ -103,8 +199,11 @@ pub fn tax_check(
     pending_tx: Option<Transaction>,
     fork_db: ForkDB
 ) -> Result<bool, anyhow::Error> {
+    // works before the contract is deployed or funded
+    let (fork_db, sniper) = local_sniper_fork(fork_db)?;
+
     let mut evm = revm::EVM::new();
-    evm.database(fork_db.clone());
+    evm.database(fork_db);
 
     // setup the next block state
     setup_evm(&mut evm, next_block);
@@ -113,23 +212,18 @@ pub fn tax_check(
     if let Some(tx) = pending_tx.clone() {
         // commit the pending tx so we can buy the token
         evm.env.tx.value = tx.value.into();
-        let _ = sim_call(tx.from, tx.to.unwrap_or_default(), tx.input.clone(), true, &mut evm)?;
+        let _ = sim_call(tx.from, tx.to.unwrap_or_default(), tx.input.clone(), true, None, &mut evm)?;
     }
 
     // ** create the call_data for the swap
-    let call_data = encode_swap(
-        pool.token_0, // weth
-        pool.token_1, // shitcoin
-        pool.address,
-        amount_in_weth,
-        U256::from(0u128)
-    );
+    let call_data = encode_buy(pool, amount_in_weth, U256::from(0u128));
 
     let result = sim_call(
         *CALLER_ADDRESS,
-        *CONTRACT_ADDRESS,
+        sniper,
         call_data.clone().into(),
         false,
+        None,
         &mut evm
     )?;
 
@@ -139,30 +233,38 @@ pub fn tax_check(
     // we return false so we can push it to retry oracle
     if result.is_reverted {
         log::warn!("Buy reverted {:?}", pool.token_1);
+        trace_rejected_call(
+            pool.token_1,
+            *CALLER_ADDRESS,
+            sniper,
+            call_data.clone().into(),
+            &mut evm
+        );
         return Ok(false);
     }
 
     // ** we check the logs to see the actual amount of tokens the pool is gonna send us
-    let (real_amount, amount_from_swap) = get_real_amount_from_logs(result.logs, pool.address)?;
+    let (real_amount, amount_from_swap) = get_real_amount_from_logs(result.logs, pool.address, sniper)?;
 
     // if the actual amount of tokens is less than 70% of the amount we should receive
     // then we skip the token
     if real_amount < (amount_from_swap * 7) / 10 {
         log::error!("Amount From Swap {:?}", amount_from_swap);
         log::error!("Real Amount {:?}", real_amount);
+        trace_rejected_call(
+            pool.token_1,
+            *CALLER_ADDRESS,
+            sniper,
+            call_data.clone().into(),
+            &mut evm
+        );
         return Ok(false);
     }
 
     // ** Do the sell Transaction **
 
     // ** create the call_data for the swap
-    let call_data = encode_swap(
-        pool.token_1, // shitcoin
-        pool.token_0, // weth
-        pool.address,
-        real_amount,
-        U256::from(0u128)
-    );
+    let call_data = encode_sell(pool, real_amount, U256::from(0u128));
 
     // try to avoid the transfer delay error buy setting the block 1 number further
     evm.env.block.number = rU256::from(next_block.number.as_u64() + 1);
@@ -171,26 +273,41 @@ pub fn tax_check(
     // ** Simulate sell
     let result = sim_call(
         *CALLER_ADDRESS,
-        *CONTRACT_ADDRESS,
+        sniper,
         call_data.clone().into(),
         false,
+        None,
         &mut evm
     )?;
 
     // see if the tx is revrted
     if result.is_reverted {
         log::warn!("Sell reverted {:?}", pool.token_1);
+        trace_rejected_call(
+            pool.token_1,
+            *CALLER_ADDRESS,
+            sniper,
+            call_data.clone().into(),
+            &mut evm
+        );
         return Ok(false);
     }
 
     // ** check the amount of weth we are going to receive
-    let (real_weth_amount, _) = get_real_amount_from_logs(result.logs, pool.address)?;
+    let (real_weth_amount, _) = get_real_amount_from_logs(result.logs, pool.weth_out_pool(), sniper)?;
 
     // if the actual amount of weth is less than 70% of the amount in weth
     // then we skip the token
     if real_weth_amount < (amount_in_weth * 7) / 10 {
         log::error!("Amount In Weth {}", convert_wei_to_ether(amount_in_weth));
         log::error!("Real Weth Amount out {}", convert_wei_to_ether(real_weth_amount));
+        trace_rejected_call(
+            pool.token_1,
+            *CALLER_ADDRESS,
+            sniper,
+            call_data.clone().into(),
+            &mut evm
+        );
         return Ok(false);
     }
 
@@ -198,15 +315,340 @@ pub fn tax_check(
     Ok(true)
 }
 
+// ** Anti-bot trap detection
+// ** records which environment opcodes the token reads during our buy and sell,
+// ** then re-runs both with varied values and flags the variations that change the outcome
+pub fn env_sensitivity_check(
+    pool: &Pool,
+    amount_in_weth: U256,
+    next_block: &BlockInfo,
+    pending_tx: Option<Transaction>,
+    fork_db: ForkDB
+) -> Result<EnvSensitivity, anyhow::Error> {
+    let (fork_db, sniper) = local_sniper_fork(fork_db)?;
+
+    let mut inspector = EnvInspector::new(pool.token_1);
+    let baseline = simulate_buy_sell(
+        pool,
+        sniper,
+        amount_in_weth,
+        next_block,
+        &pending_tx,
+        None,
+        Some(&mut inspector),
+        fork_db.clone()
+    )?;
+
+    let opcodes_read: Vec<EnvOpcode> = inspector.opcodes().iter().copied().collect();
+
+    let mut variations: Vec<EnvVariation> = Vec::new();
+    for opcode in &opcodes_read {
+        if let Some(variation) = EnvVariation::for_opcode(*opcode) {
+            if !variations.contains(&variation) {
+                variations.push(variation);
+            }
+        }
+    }
+
+    let mut flagged = Vec::new();
+    for variation in variations {
+        let outcome = simulate_buy_sell(
+            pool,
+            sniper,
+            amount_in_weth,
+            next_block,
+            &pending_tx,
+            Some(variation),
+            None,
+            fork_db.clone()
+        )?;
+
+        if outcome.differs_from(&baseline) {
+            flagged.push((variation, outcome));
+        }
+    }
+
+    Ok(EnvSensitivity {
+        opcodes_read,
+        baseline,
+        flagged,
+    })
+}
+
+// buys and sells the token with an optional environment variation
+fn simulate_buy_sell(
+    pool: &Pool,
+    sniper: Address,
+    amount_in_weth: U256,
+    next_block: &BlockInfo,
+    pending_tx: &Option<Transaction>,
+    variation: Option<EnvVariation>,
+    mut inspector: Option<&mut EnvInspector>,
+    fork_db: ForkDB
+) -> Result<SwapOutcome, anyhow::Error> {
+    let mut evm = revm::EVM::new();
+    evm.database(fork_db);
+
+    // setup the next block state
+    setup_evm(&mut evm, next_block);
+
+    if let Some(tx) = pending_tx {
+        evm.env.tx.value = tx.value.into();
+        let _ = sim_call(tx.from, tx.to.unwrap_or_default(), tx.input.clone(), true, None, &mut evm)?;
+        evm.env.tx.value = rU256::ZERO;
+    }
+
+    // vary the environment after the launch tx, eg. launchBlock is recorded by it
+    if let Some(variation) = variation {
+        apply_env_variation(&mut evm, variation, next_block);
+    }
+
+    // ** buy and keep the tokens so we can sell them
+    let call_data = encode_buy(pool, amount_in_weth, U256::zero());
+    let result = inspect_call(sniper, call_data.into(), inspector.as_deref_mut(), &mut evm)?;
+
+    if result.is_reverted {
+        return Ok(SwapOutcome {
+            buy_reverted: true,
+            sell_reverted: true,
+            tokens_out: U256::zero(),
+            weth_out: U256::zero(),
+        });
+    }
+
+    let (tokens_out, _) = get_real_amount_from_logs(result.logs, pool.address, sniper)?;
+
+    // ** sell in the next block
+    evm.env.block.number = evm.env.block.number + rU256::from(1u64);
+    evm.env.block.timestamp = evm.env.block.timestamp + rU256::from(12u64);
+
+    let call_data = encode_sell(pool, tokens_out, U256::zero());
+    let result = inspect_call(sniper, call_data.into(), inspector, &mut evm)?;
+
+    let weth_out = if result.is_reverted {
+        U256::zero()
+    } else {
+        get_real_amount_from_logs(result.logs, pool.weth_out_pool(), sniper)?.0
+    };
+
+    Ok(SwapOutcome {
+        buy_reverted: false,
+        sell_reverted: result.is_reverted,
+        tokens_out,
+        weth_out,
+    })
+}
+
+// commits a call to our contract, with the env inspector if given
+fn inspect_call(
+    sniper: Address,
+    call_data: Bytes,
+    inspector: Option<&mut EnvInspector>,
+    evm: &mut revm::EVM<ForkDB>
+) -> Result<SimulationResult, anyhow::Error> {
+    let inspector = match inspector {
+        Some(inspector) => inspector,
+        None => {
+            return sim_call(*CALLER_ADDRESS, sniper, call_data, true, None, evm);
+        }
+    };
+
+    evm.env.tx.caller = CALLER_ADDRESS.0.into();
+    evm.env.tx.transact_to = TransactTo::Call(sniper.0.into());
+    evm.env.tx.data = call_data.0;
+
+    let result = evm.inspect_commit(inspector)?;
+
+    Ok(into_simulation_result(result))
+}
+
+fn apply_env_variation(evm: &mut revm::EVM<ForkDB>, variation: EnvVariation, next_block: &BlockInfo) {
+    match variation {
+        EnvVariation::RealisticGasPrice => {
+            evm.env.tx.gas_price = (next_block.base_fee + *MINER_TIP_TO_SNIPE).into();
+        }
+        EnvVariation::BuilderCoinbase => {
+            evm.env.block.coinbase = ENV_CHECK_COINBASE.0.into();
+        }
+        EnvVariation::LaterBlock => {
+            let offset = *ENV_CHECK_BLOCK_OFFSET;
+            evm.env.block.number = rU256::from(next_block.number.as_u64() + offset);
+            evm.env.block.timestamp = (next_block.timestamp + U256::from(offset * 12)).into();
+        }
+        EnvVariation::HigherBaseFee => {
+            let base_fee = next_block.base_fee * 2;
+            evm.env.block.basefee = base_fee.into();
+            evm.env.tx.gas_price = base_fee.into();
+        }
+    }
+}
+
+// ** Ownership, LP and supply checks for a new pair
+// ** deployer is the address that created the pair
+pub fn get_token_checks(
+    pool: &Pool,
+    deployer: Address,
+    next_block: &BlockInfo,
+    pending_tx: Option<Transaction>,
+    fork_db: ForkDB
+) -> Result<TokenChecks, anyhow::Error> {
+    let mut evm = revm::EVM::new();
+    evm.database(fork_db);
+
+    // setup the next block state
+    setup_evm(&mut evm, next_block);
+
+    // if we have a pending tx simulate it
+    if let Some(tx) = pending_tx {
+        evm.env.tx.value = tx.value.into();
+        let _ = sim_call(tx.from, tx.to.unwrap_or_default(), tx.input.clone(), true, None, &mut evm)?;
+    }
+
+    // ** check the owner of the token
+    let owner = get_token_owner(pool.token_1, &mut evm)?;
+
+    // ** check how much of the supply the deployer still holds
+    let total_supply = get_total_supply(pool.token_1, &mut evm)?;
+    let deployer_balance = get_erc20_balance(pool.token_1, deployer, &mut evm)?;
+    let deployer_share_bps = calculate_share_bps(deployer_balance, total_supply);
+
+    // V3 liquidity is an NFT position, there is no LP token to check
+    if pool.is_v3() {
+        return Ok(TokenChecks::new(owner, LpStatus::Unknown, deployer_share_bps));
+    }
+
+    // ** check who holds the LP tokens
+    // ** the pool address is also the LP token address
+    let lp_supply = get_total_supply(pool.address, &mut evm)?;
+
+    let burned_balance =
+        get_erc20_balance(pool.address, Address::zero(), &mut evm)? +
+        get_erc20_balance(pool.address, *DEAD_ADDRESS, &mut evm)?;
+    let burned_share = calculate_share_bps(burned_balance, lp_supply);
+
+    let deployer_lp_balance = get_erc20_balance(pool.address, deployer, &mut evm)?;
+    let deployer_lp_share = calculate_share_bps(deployer_lp_balance, lp_supply);
+
+    let mut lp_status = LpStatus::Unknown;
+    let mut max_share = 0;
+
+    if burned_share > max_share {
+        lp_status = LpStatus::Burned { share_bps: burned_share };
+        max_share = burned_share;
+    }
+
+    for locker in LP_LOCKERS.iter() {
+        let locker_balance = get_erc20_balance(pool.address, *locker, &mut evm)?;
+        let locker_share = calculate_share_bps(locker_balance, lp_supply);
+
+        if locker_share > max_share {
+            // we can only read the unlock time from the unicrypt locker
+            let unlock_time = if *locker == *UNICRYPT_V2_LOCKER {
+                get_unicrypt_unlock_time(pool.address, &mut evm).unwrap_or(None)
+            } else {
+                None
+            };
+
+            lp_status = LpStatus::Locked {
+                locker: *locker,
+                share_bps: locker_share,
+                unlock_time,
+            };
+            max_share = locker_share;
+        }
+    }
+
+    if deployer_lp_share > max_share {
+        lp_status = LpStatus::HeldByDeployer { share_bps: deployer_lp_share };
+    }
+
+    Ok(TokenChecks::new(owner, lp_status, deployer_share_bps))
+}
+
+// Gets the metadata and the top holders of a new token
+// holders are the addresses we know received the token, the ones in the pending tx are added too
+// the funding source of the deployer is looked up separately over rpc
+pub fn get_token_info(
+    pool: &Pool,
+    holders: Vec<Address>,
+    next_block: &BlockInfo,
+    pending_tx: Option<Transaction>,
+    fork_db: ForkDB
+) -> Result<TokenInfo, anyhow::Error> {
+    let mut evm = revm::EVM::new();
+    evm.database(fork_db);
+
+    // setup the next block state
+    setup_evm(&mut evm, next_block);
+
+    let mut holders = holders;
+
+    // if we have a pending tx simulate it
+    if let Some(tx) = pending_tx {
+        evm.env.tx.value = tx.value.into();
+        let result = sim_call(tx.from, tx.to.unwrap_or_default(), tx.input.clone(), true, None, &mut evm)?;
+
+        for log in result.logs {
+            let topics: Vec<H256> = log.topics
+                .iter()
+                .map(|b256| H256::from_slice(b256.as_bytes()))
+                .collect();
+
+            // only the Transfer logs of the token
+            if H160::from(log.address) != pool.token_1 || topics.len() < 3 || topics[0] != TRANSFER_EVENT.signature() {
+                continue;
+            }
+            let recipient = Address::from(topics[2]);
+            if !holders.contains(&recipient) {
+                holders.push(recipient);
+            }
+        }
+    }
+
+    let (name, symbol, decimals) = get_token_metadata(pool.token_1, &mut evm);
+    let total_supply = get_total_supply(pool.token_1, &mut evm)?;
+
+    // ** the tokens in the pool, in the token contract or burned are not held by anyone
+    let excluded = [pool.address, pool.token_1, Address::zero(), *DEAD_ADDRESS];
+
+    let mut balances = Vec::new();
+    for holder in holders {
+        if excluded.contains(&holder) {
+            continue;
+        }
+        let balance = get_erc20_balance(pool.token_1, holder, &mut evm)?;
+        if !balance.is_zero() {
+            balances.push((holder, balance));
+        }
+    }
+
+    balances.sort_by(|a, b| b.1.cmp(&a.1));
+    balances.truncate(*TOP_HOLDERS_COUNT);
+
+    let top_holders_balance = balances.iter().fold(U256::zero(), |sum, (_, balance)| sum + *balance);
+    let top_holders_share_bps = calculate_share_bps(top_holders_balance, total_supply);
+
+    Ok(TokenInfo {
+        address: pool.token_1,
+        name,
+        symbol,
+        decimals,
+        total_supply,
+        top_holders: balances,
+        top_holders_share_bps,
+        funding_source: None,
+    })
+}
+
 // ** Generate Call Data **
 pub fn generate_tx_data(
     pool: &Pool,
     amount_in_weth: U256,
     next_block: &BlockInfo,
-    pending_tx: Option<Transaction>,
+    plan: BundlePlan,
     miner_tip: U256,
-    frontrun_or_backrun: u8,
     do_we_buy: bool,
+    minimum_received: Option<U256>,
     fork_db: ForkDB
 ) -> Result<(SnipeTx, TxData), anyhow::Error> {
     let mut evm = revm::EVM::new();
@@ -215,11 +657,12 @@ pub fn generate_tx_data(
     // setup the next block state
     setup_evm(&mut evm, next_block);
 
-    // if we have a pending tx simulate it
-    if let Some(ref tx) = pending_tx {
+    // if we backrun simulate the foreign txs in bundle order
+    for tx in plan.txs_before_swap() {
         evm.env.tx.value = tx.value.into();
-        let _ = sim_call(tx.from, tx.to.unwrap_or_default(), tx.input.clone(), true, &mut evm)?;
+        let _ = sim_call(tx.from, tx.to.unwrap_or_default(), tx.input.clone(), true, None, &mut evm)?;
     }
+    evm.env.tx.value = rU256::ZERO;
 
     // generate call data based on whether we buy or sell
     let call_data = generate_call_data(
@@ -253,17 +696,26 @@ pub fn generate_tx_data(
         *CONTRACT_ADDRESS,
         call_data.into(),
         false,
+        None,
         &mut evm
     )?;
 
-    // calculate total gas cost for the transaction
-    let gas_cost = (next_block.base_fee + miner_tip) * result.gas_used;
+    // gas limit derived from the simulated gas
+    let gas_limit = calculate_gas_limit(result.gas_used + result.gas_refunded);
+
+    // calculate the max gas cost for the transaction
+    let gas_cost = (next_block.base_fee + miner_tip) * gas_limit;
+
+    // the pool that sends us the tokens, or the weth if we sell
+    let out_pool = if do_we_buy { pool.address } else { pool.weth_out_pool() };
 
     // get the real amount of tokens received
-    let (amount_received, _) = get_real_amount_from_logs(result.logs, pool.address)?;
+    let (amount_received, _) = get_real_amount_from_logs(result.logs, out_pool, *CONTRACT_ADDRESS)?;
 
-    let minimum_received =
-        (amount_received * U256::from(*BUY_NUMERATOR)) / U256::from(*BUY_DENOMINATOR);
+    // use the given minimum if we adapted the slippage, eg. to competing snipers
+    let minimum_received = minimum_received.unwrap_or(
+        (amount_received * U256::from(*BUY_NUMERATOR)) / U256::from(*BUY_DENOMINATOR)
+    );
 
     // encode the call data again with the minimum received
     let call_data = generate_call_data(
@@ -289,9 +741,10 @@ pub fn generate_tx_data(
     let tx_data = TxData::new(
         call_data.into(),
         result.gas_used,
+        gas_limit,
         minimum_received,
-        pending_tx.unwrap_or(Transaction::default()),
-        U256::from(frontrun_or_backrun),
+        out_pool,
+        plan,
         convert_access_list(access_list)
     );
 
@@ -316,7 +769,7 @@ pub fn simulate_sell(
     // if we have a pending tx simulate it
     if let Some(tx) = tx.clone() {
         evm.env.tx.value = tx.value.into();
-        let _ = sim_call(tx.from, tx.to.unwrap_or_default(), tx.input.clone(), true, &mut evm)?;
+        let _ = sim_call(tx.from, tx.to.unwrap_or_default(), tx.input.clone(), true, None, &mut evm)?;
     }
 
     // ** get the token balance for the amount_in to sell
@@ -327,13 +780,7 @@ pub fn simulate_sell(
     )?;
 
     // ** create the call_data for the swap
-    let call_data = encode_swap(
-        pool.token_1, // shitcoin
-        pool.token_0, // weth
-        pool.address,
-        amount_in,
-        U256::from(0u128)
-    );
+    let call_data = encode_sell(&pool, amount_in, U256::from(0u128));
 
     // ** Simulate the Sell Transaction
     let result = sim_call(
@@ -341,6 +788,7 @@ pub fn simulate_sell(
         *CONTRACT_ADDRESS,
         call_data.clone().into(),
         false,
+        None,
         &mut evm
     )?;
     
@@ -355,12 +803,186 @@ pub fn simulate_sell(
     // ** get the actual amount of weth we are going to receive from the logs
     let (weth_amount, _) = get_real_amount_from_logs(
         result.logs,
-        pool.address,
+        pool.weth_out_pool(),
+        *CONTRACT_ADDRESS
     )?;
 
     return Ok(weth_amount);
 }
 
+// ** Seeds the tokens we expect from our buy if the contract holds none yet
+// ** eg. while the buy is in flight, so the sell checks still see what a pending tx does to our sell
+pub fn seed_expected_balance(
+    pool: Pool,
+    expected_tokens: U256,
+    next_block: BlockInfo,
+    fork_db: ForkDB
+) -> Result<ForkDB, anyhow::Error> {
+    let mut evm = revm::EVM::new();
+    evm.database(fork_db.clone());
+    setup_evm(&mut evm, &next_block);
+
+    let balance = get_erc20_balance(pool.token_1, *CONTRACT_ADDRESS, &mut evm)?;
+    if !balance.is_zero() || expected_tokens.is_zero() {
+        return Ok(fork_db);
+    }
+
+    let mut fork_db = fork_db;
+    seed_token_balance(&mut fork_db, pool.token_1, *CONTRACT_ADDRESS, expected_tokens)?;
+
+    Ok(fork_db)
+}
+
+// ** Quotes the amount of weth we get for selling a held token
+// ** Tokens with a confirmed fixed tax are priced with the closed-form V2 math from storage
+// ** everything else, or a quote that looks off, goes through a full EVM sell
+pub fn quote_sell(
+    snipe_tx: &SnipeTx,
+    latest_block: U64,
+    next_block: BlockInfo,
+    fork_db: ForkDB
+) -> Result<SellQuote, anyhow::Error> {
+    let blocks_since_evm_check = latest_block.saturating_sub(snipe_tx.last_evm_check).as_u64();
+
+    // the closed-form math only covers V2 pairs
+    let use_fast_path =
+        *ENABLE_SELL_FAST_PATH &&
+        !snipe_tx.pool.is_v3() &&
+        snipe_tx.fixed_tax &&
+        blocks_since_evm_check < *FAST_PATH_EVM_INTERVAL;
+
+    if use_fast_path {
+        match fast_sell_quote(snipe_tx, &fork_db) {
+            Ok(amount_out) if !fast_quote_looks_off(amount_out, snipe_tx.last_amount_out) => {
+                return Ok(SellQuote {
+                    amount_out,
+                    sell_tax_bps: snipe_tx.sell_tax_bps,
+                    balance_slot: snipe_tx.balance_slot,
+                    from_evm: false,
+                });
+            }
+            Ok(amount_out) => {
+                log::warn!(
+                    "Fast path quote {:?} for {:?} looks off, last amount out {:?}",
+                    amount_out,
+                    snipe_tx.pool.token_1,
+                    snipe_tx.last_amount_out
+                );
+            }
+            Err(e) => {
+                log::warn!("Fast path quote failed for {:?}: {:?}", snipe_tx.pool.token_1, e);
+            }
+        }
+    }
+
+    evm_sell_quote(snipe_tx.pool, next_block, fork_db)
+}
+
+// runs a full EVM sell and measures the sell tax by inverting the V2 math
+// V3 positions are always priced here
+fn evm_sell_quote(
+    pool: Pool,
+    next_block: BlockInfo,
+    fork_db: ForkDB
+) -> Result<SellQuote, anyhow::Error> {
+    // simulate_sell doesnt commit anything so the state here is the same as the sell
+    let mut evm = revm::EVM::new();
+    evm.database(fork_db.clone());
+    setup_evm(&mut evm, &next_block);
+
+    let balance = get_erc20_balance(pool.token_1, *CONTRACT_ADDRESS, &mut evm)?;
+
+    let amount_out = simulate_sell(None, pool, next_block, fork_db.clone())?;
+
+    let sell_tax_bps = if amount_out.is_zero() || balance.is_zero() {
+        None
+    } else if pool.is_v3() {
+        // a V3 pool checks its balance after the swap callback
+        // so a sell that loses tokens to a transfer tax reverts
+        Some(0)
+    } else {
+        // the amount that actually reached the pair after the tax
+        let (reserve_0, reserve_1) = get_v2_reserves_from_storage(pool.address, &fork_db)?;
+        let (reserve_in, reserve_out) = sort_reserves(pool.token_1, pool.token_0, reserve_0, reserve_1);
+
+        // undo the hop from the base token to weth first
+        let base_amount_out = match get_base_pair_reserves(&pool, &fork_db)? {
+            Some((base_reserve, weth_reserve)) =>
+                get_amount_in(amount_out, base_reserve, weth_reserve, *BASE_PAIR_FEE),
+            None => amount_out,
+        };
+
+        let amount_in_after_tax = get_amount_in(
+            base_amount_out,
+            reserve_in,
+            reserve_out,
+            pool.fee()
+        ).min(balance);
+        Some(calculate_share_bps(balance - amount_in_after_tax, balance))
+    };
+
+    let balance_slot = get_balance_slot(pool.token_1, &fork_db).map(|slot| slot.slot_for(*CONTRACT_ADDRESS));
+
+    Ok(SellQuote {
+        amount_out,
+        sell_tax_bps,
+        balance_slot,
+        from_evm: true,
+    })
+}
+
+// prices the sell with the same math as Swapper._getAmountOut and the fee of the pair's fork
+// the reserves and our balance are read from storage
+fn fast_sell_quote(snipe_tx: &SnipeTx, fork_db: &ForkDB) -> Result<U256, anyhow::Error> {
+    let pool = snipe_tx.pool;
+    let sell_tax_bps = snipe_tx.sell_tax_bps.ok_or(anyhow!("Sell tax is not measured yet"))?;
+    let balance_slot = snipe_tx.balance_slot.ok_or(anyhow!("Balance slot is unknown"))?;
+
+    let balance = read_storage(pool.token_1, balance_slot, fork_db)?;
+    let (reserve_0, reserve_1) = get_v2_reserves_from_storage(pool.address, fork_db)?;
+    let (reserve_in, reserve_out) = sort_reserves(pool.token_1, pool.token_0, reserve_0, reserve_1);
+
+    let amount_in_after_tax = (balance * (10000 - sell_tax_bps.min(10000))) / 10000;
+    let base_amount_out = get_amount_out(amount_in_after_tax, reserve_in, reserve_out, pool.fee());
+
+    // pools quoted in another base token take one more hop to weth
+    match get_base_pair_reserves(&pool, fork_db)? {
+        Some((base_reserve, weth_reserve)) =>
+            Ok(get_amount_out(base_amount_out, base_reserve, weth_reserve, *BASE_PAIR_FEE)),
+        None => Ok(base_amount_out),
+    }
+}
+
+// reserves of the V2 pair between the pool's base token and weth as (base_reserve, weth_reserve)
+// None if the pool is quoted in weth
+fn get_base_pair_reserves(
+    pool: &Pool,
+    fork_db: &ForkDB
+) -> Result<Option<(U256, U256)>, anyhow::Error> {
+    match pool.base_pair() {
+        Some(base_pair) => {
+            let (reserve_0, reserve_1) = get_v2_reserves_from_storage(base_pair, fork_db)?;
+            Ok(Some(sort_reserves(pool.token_0, *WETH, reserve_0, reserve_1)))
+        }
+        None => Ok(None),
+    }
+}
+
+// a fast quote looks off if its zero or moved too far since the last quote
+fn fast_quote_looks_off(amount_out: U256, last_amount_out: U256) -> bool {
+    if amount_out.is_zero() || last_amount_out.is_zero() {
+        return true;
+    }
+
+    let diff = if amount_out > last_amount_out {
+        amount_out - last_amount_out
+    } else {
+        last_amount_out - amount_out
+    };
+
+    diff * 100 > last_amount_out * *FAST_PATH_MAX_DEVIATION
+}
+
 // Profit Taker
 
 pub fn profit_taker(
@@ -381,13 +1003,7 @@ pub fn profit_taker(
     // ** to see how much tokens we get and we will use that amount to sell
 
     // ** create the call_data for the swap
-    let call_data = encode_swap(
-        *WETH, // input
-        pool.token_1, // output
-        pool.address,
-        amount_in,
-        U256::from(0u128)
-    );
+    let call_data = encode_buy(&pool, amount_in, U256::from(0u128));
 
     // ** Simulate the Buy Transaction
     let result = sim_call(
@@ -395,23 +1011,19 @@ pub fn profit_taker(
         *CONTRACT_ADDRESS,
         call_data.clone().into(),
         false,
+        None,
         &mut evm
     )?;
 
     // get the real amount of tokens we are going to receive
     let (mut amount_of_tokens_to_sell, _) = get_real_amount_from_logs(
         result.logs,
-        pool.address
+        pool.address,
+        *CONTRACT_ADDRESS
     )?;
 
     // encode the sell call data
-    let call_data = encode_swap(
-        pool.token_1, // input
-        *WETH, // output
-        pool.address,
-        amount_of_tokens_to_sell,
-        U256::from(0u128)
-    );
+    let call_data = encode_sell(&pool, amount_of_tokens_to_sell, U256::from(0u128));
 
     // ** Generate Access List
     let mut access_list_inspector = AccessListInspector::new(*CALLER_ADDRESS, *CONTRACT_ADDRESS);
@@ -437,13 +1049,15 @@ pub fn profit_taker(
         *CONTRACT_ADDRESS,
         call_data.clone().into(),
         false,
+        None,
         &mut evm
     )?;
 
     // ** get the amount of weth we are going to receive
     let (real_amount_weth, _) = get_real_amount_from_logs(
         result.logs,
-        pool.address
+        pool.weth_out_pool(),
+        *CONTRACT_ADDRESS
     )?;
 
     // make sure the real_amount_weth is not less than the initial amount
@@ -457,21 +1071,16 @@ pub fn profit_taker(
         (real_amount_weth * U256::from(*BUY_NUMERATOR)) / U256::from(*BUY_DENOMINATOR);
 
     // encode the final call data
-    let call_data = encode_swap(
-        pool.token_1, // input
-        *WETH, // output
-        pool.address,
-        amount_of_tokens_to_sell,
-        minimum_received
-    );
+    let call_data = encode_sell(&pool, amount_of_tokens_to_sell, minimum_received);
 
     // ** Generate TxData
     let tx_data = TxData::new(
         call_data.into(),
         result.gas_used,
+        calculate_gas_limit(result.gas_used + result.gas_refunded),
         minimum_received,
-        Transaction::default(),
-        U256::from(2u128), // 2 because we dont frontrun or back run
+        pool.weth_out_pool(),
+        BundlePlan::standalone(),
         convert_access_list(access_list)
     );
 
@@ -485,6 +1094,113 @@ pub fn get_touched_pools(
     pools: Vec<Pool>,
     fork_db: ForkDB
 ) -> Result<Option<Vec<Pool>>, anyhow::Error> {
+    // only the pools whose storage the tx actually changes
+    // a read, eg. getReserves from a router, doesnt count
+    let watched = pools.iter().map(|pool| pool.address).collect();
+    let writes = get_storage_writes(tx, next_block, watched, fork_db)?;
+
+    let touched_pools: Vec<Pool> = pools
+        .into_iter()
+        .filter(|pool| writes.iter().any(|write| write.address == pool.address))
+        .collect();
+
+    // if the touched_pools vector is empty return None
+    if touched_pools.is_empty() {
+        return Ok(None);
+    }
+
+    // else return the touched_pools
+    Ok(Some(touched_pools))
+}
+
+// ** Reports which storage slots of our held tokens and their pools a pending tx changes
+// ** and classifies them, whatever contract the tx was sent to
+pub fn get_storage_changes(
+    tx: &Transaction,
+    next_block: &BlockInfo,
+    pools: Vec<Pool>,
+    fork_db: ForkDB
+) -> Result<Vec<SlotChange>, anyhow::Error> {
+    let watched = pools
+        .iter()
+        .flat_map(|pool| [pool.token_1, pool.address])
+        .collect();
+    let writes = get_storage_writes(tx, next_block, watched, fork_db.clone())?;
+
+    if writes.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // mapping entries keyed by our address, eg. a blacklist
+    let our_mapping_slots: Vec<(U256, U256)> = (0..*BALANCE_SLOT_SEARCH_DEPTH)
+        .map(|i| (get_mapping_slot(*CONTRACT_ADDRESS, U256::from(i)), U256::from(i)))
+        .collect();
+
+    let mut owners: Vec<(Address, Option<Address>)> = Vec::new();
+    let mut changes = Vec::new();
+
+    for write in writes {
+        let old_value = read_storage(write.address, write.slot, &fork_db)?;
+        if old_value == write.value {
+            continue;
+        }
+
+        let pool = match
+            pools.iter().find(|pool| pool.token_1 == write.address || pool.address == write.address)
+        {
+            Some(pool) => *pool,
+            None => {
+                continue;
+            }
+        };
+
+        // the owner is only needed once per token
+        let owner = match owners.iter().find(|(token, _)| *token == pool.token_1) {
+            Some((_, owner)) => *owner,
+            None => {
+                let mut evm = revm::EVM::new();
+                evm.database(fork_db.clone());
+                setup_evm(&mut evm, next_block);
+                // a renounced owner would match every slot written from or to zero
+                let owner = get_token_owner(pool.token_1, &mut evm)
+                    .unwrap_or(None)
+                    .filter(|owner| !owner.is_zero() && *owner != *DEAD_ADDRESS);
+                owners.push((pool.token_1, owner));
+                owner
+            }
+        };
+
+        let kind = classify_slot(
+            &pool,
+            write.address,
+            write.slot,
+            old_value,
+            write.value,
+            owner,
+            &our_mapping_slots,
+            &fork_db
+        );
+
+        changes.push(SlotChange {
+            pool,
+            address: write.address,
+            slot: write.slot,
+            old_value,
+            new_value: write.value,
+            kind,
+        });
+    }
+
+    Ok(changes)
+}
+
+// simulates the tx and returns the persisted writes to the watched addresses
+fn get_storage_writes(
+    tx: &Transaction,
+    next_block: &BlockInfo,
+    watched: Vec<Address>,
+    fork_db: ForkDB
+) -> Result<Vec<StorageWrite>, anyhow::Error> {
     // setup an evm instance
     let mut evm = revm::EVM::new();
     evm.database(fork_db);
@@ -498,36 +1214,79 @@ pub fn get_touched_pools(
     evm.env.tx.data = tx.input.0.clone();
     evm.env.tx.value = tx.value.into();
 
-    let res = evm.transact_ref()?;
+    let mut inspector = StorageDiffInspector::new(watched);
+    let res = evm.inspect_ref(&mut inspector)?;
 
-    // get the touched accs
-    let touched_accs = res.state.keys();
+    // nothing persists if the tx reverts
+    if !res.result.is_success() {
+        return Ok(Vec::new());
+    }
 
-    // get the touched_pools from the touched_accs
-    let touched_pools: Vec<Pool> = touched_accs
-        .filter_map(|acc| {
-            pools
-                .iter()
-                .find(|pool| pool.address == H160::from(*acc))
-                .cloned()
-        })
-        .collect();
+    Ok(inspector.into_writes())
+}
 
-    // if the touched_pools vector is empty return None
-    if touched_pools.is_empty() {
-        return Ok(None);
+fn classify_slot(
+    pool: &Pool,
+    address: Address,
+    slot: U256,
+    old_value: U256,
+    new_value: U256,
+    owner: Option<Address>,
+    our_mapping_slots: &[(U256, U256)],
+    fork_db: &ForkDB
+) -> SlotKind {
+    if address == pool.address {
+        let is_reserves_slot = match pool.kind {
+            // reserve0, reserve1 and blockTimestampLast
+            PoolKind::UniswapV2 { .. } => slot == U256::from(8u64),
+            // slot0 with the price and the active liquidity
+            PoolKind::UniswapV3 { .. } => slot.is_zero() || slot == U256::from(4u64),
+        };
+        if is_reserves_slot {
+            return SlotKind::PoolReserves;
+        }
+        return SlotKind::Other;
     }
 
-    // else return the touched_pools
-    Ok(Some(touched_pools))
+    if let Some(balance_slot) = get_balance_slot(pool.token_1, fork_db) {
+        if slot == balance_slot.slot_for(*CONTRACT_ADDRESS) {
+            return SlotKind::OurBalance;
+        }
+        if slot == balance_slot.slot_for(pool.address) {
+            return SlotKind::PoolBalance;
+        }
+    }
+
+    if let Some((_, mapping_slot)) = our_mapping_slots.iter().find(|(x, _)| *x == slot) {
+        return SlotKind::OurMappingEntry { mapping_slot: *mapping_slot };
+    }
+
+    // the owner may be packed with other variables in the same slot
+    // renounced owners are passed as None
+    if let Some(owner) = owner {
+        let owner = U256::from(owner.as_bytes());
+        let mask = (U256::one() << 160) - 1;
+        if (old_value & mask) == owner || (new_value & mask) == owner {
+            return SlotKind::Owner;
+        }
+    }
+
+    if old_value <= U256::from(10000u64) && new_value <= U256::from(10000u64) {
+        return SlotKind::FeeLike;
+    }
+
+    SlotKind::Other
 }
 
-// Gets a new pair from a pending transaction
+// Gets a new V2 pair or V3 pool from a pending transaction
+// returns None if the tx doesnt create one on a DEX we know
+// initialized_pools are the V3 pools initialized in an earlier tx, a Mint on them is a new pool
 pub fn get_pair(
     next_block: BlockInfo,
     tx: &Transaction,
+    initialized_pools: HashSet<Address>,
     fork_db: ForkDB
-) -> Result<(Address, Address, Address, U256), anyhow::Error> {
+) -> Result<Option<Pool>, anyhow::Error> {
     // setup an evm instance
     let mut evm = revm::EVM::new();
     evm.database(fork_db);
@@ -542,64 +1301,122 @@ pub fn get_pair(
         tx.to.unwrap_or_default(),
         tx.input.clone(),
         true,
+        None,
         &mut evm,
     )?;
 
-    // ** define empty addresses
-    let mut token_0 = Address::zero();
-    let mut token_1 = Address::zero();
-    let mut pool_address = Address::zero();
+    let logs: Vec<(Address, RawLog)> = result.logs
+        .iter()
+        .map(|log| {
+            let topics = log.topics
+                .iter()
+                .map(|b256| H256::from_slice(b256.as_bytes()))
+                .collect();
+            (H160::from(log.address), RawLog { topics, data: log.data.clone().to_vec() })
+        })
+        .collect();
+
+    get_pair_from_logs(&logs, &initialized_pools, &mut evm)
+}
+
+// Gets a new V2 pair or V3 pool from the logs of a tx that is already in a block
+// the fork_db must be forked at or after that block
+pub fn get_pair_in_block(
+    next_block: BlockInfo,
+    logs: &[(Address, RawLog)],
+    initialized_pools: HashSet<Address>,
+    fork_db: ForkDB
+) -> Result<Option<Pool>, anyhow::Error> {
+    // setup an evm instance
+    let mut evm = revm::EVM::new();
+    evm.database(fork_db);
+
+    // setup block state
+    setup_evm(&mut evm, &next_block);
+
+    get_pair_from_logs(logs, &initialized_pools, &mut evm)
+}
+
+// Finds a new pool in the logs of a single tx
+// the tx must already be committed to the evm
+fn get_pair_from_logs(
+    logs: &[(Address, RawLog)],
+    initialized_pools: &HashSet<Address>,
+    evm: &mut revm::EVM<ForkDB>
+) -> Result<Option<Pool>, anyhow::Error> {
+    // ** define the pair fields, they are set by the events we find
+    let token_0;
+    let token_1;
+    let pool_address;
     let mut mint_pool_address = Address::zero();
-    let mut reserve_0 = U256::zero();
-    let mut reserve_1 = U256::zero();
+    let reserve_0;
+    let reserve_1;
     let sync_reserve_0;
 
     // Structures to hold decoded events
     let mut pair_created_opt = None;
     let mut mint_opt = None;
     let mut sync_opt = None;
+    let mut pool_created_opt = None;
+    let mut initialize_opt = None;
+    let mut v3_mint_opt = None;
 
     // Collect events
-    for log in &result.logs {
-        let converted_topics: Vec<_> = log.topics
-            .iter()
-            .map(|b256| H256::from_slice(b256.as_bytes()))
-            .collect();
-
-        // Check for PairCreated event
-        if
-            let Ok(decoded_log) = PAIR_CREATED_EVENT.parse_log(RawLog {
-                topics: converted_topics.clone(),
-                data: log.data.clone().to_vec(),
-            })
-        {
-            pair_created_opt = Some(decoded_log);
+    for (address, raw_log) in logs {
+        let address = *address;
+
+        // Check for PairCreated event, only from the registered V2 factories
+        if let Some(fork) = get_v2_fork(address) {
+            if let Ok(decoded_log) = PAIR_CREATED_EVENT.parse_log(raw_log.clone()) {
+                pair_created_opt = Some((fork, decoded_log));
+            }
         }
 
         // Check for Mint event
-        if
-            let Ok(decoded_log) = MINT_EVENT.parse_log(RawLog {
-                topics: converted_topics.clone(),
-                data: log.data.clone().to_vec(),
-            })
-        {
+        if let Ok(decoded_log) = MINT_EVENT.parse_log(raw_log.clone()) {
             mint_opt = Some(decoded_log);
-            mint_pool_address = H160::from(log.address);
+            mint_pool_address = address;
         }
 
         // Check for Sync event
-        if
-            let Ok(decoded_log) = SYNC_EVENT.parse_log(RawLog {
-                topics: converted_topics.clone(),
-                data: log.data.clone().to_vec(),
-            })
-        {
+        if let Ok(decoded_log) = SYNC_EVENT.parse_log(raw_log.clone()) {
             sync_opt = Some(decoded_log);
         }
+
+        // Check for the V3 PoolCreated event, only from the V3 factory
+        if address == *UNISWAP_V3_FACTORY {
+            if let Ok(decoded_log) = POOL_CREATED_EVENT.parse_log(raw_log.clone()) {
+                pool_created_opt = Some(decoded_log);
+            }
+        }
+
+        // Check for the V3 Initialize event
+        if V3_INITIALIZE_EVENT.parse_log(raw_log.clone()).is_ok() {
+            initialize_opt = Some(address);
+        }
+
+        // Check for the V3 Mint event
+        if V3_MINT_EVENT.parse_log(raw_log.clone()).is_ok() {
+            v3_mint_opt = Some(address);
+        }
     }
 
+    // ** a V3 pool is new if it gets its first Mint, either in the tx that initializes it
+    // ** or in a later tx on a pool we saw getting initialized
+    if *ENABLE_V3_SNIPING {
+        if let Some(minted_pool) = v3_mint_opt {
+            if initialize_opt == Some(minted_pool) || initialized_pools.contains(&minted_pool) {
+                return get_new_v3_pool(minted_pool, pool_created_opt, evm).map(Some);
+            }
+        }
+    }
+
+    let v2_fork;
+
     // Process PairCreated (if found)
-    if let Some(pair_created) = pair_created_opt {
+    if let Some((fork, pair_created)) = pair_created_opt {
+        v2_fork = fork;
+
         // decode the log
         token_0 = pair_created.params[0].value.clone().into_token().into_address().unwrap();
 
@@ -639,25 +1456,181 @@ pub fn get_pair(
         // check if the mint and sync reserves match
         // if they match we found a new pool, if they dont then we found a pool that was already existed
 
-        // if reserves dont match return a zero pool address
+        // if reserves dont match there is no new pool
         if reserve_0 != sync_reserve_0 {
-            pool_address = Address::zero();
-        } else {
-            // return the pool address
-            pool_address = mint_pool_address;
+            return Ok(None);
         }
 
+        // return the pool address
+        pool_address = mint_pool_address;
+
         // if we got the pool address we can get the tokens by simulating a call to the pool contract
         (token_0, token_1) = get_tokens_from_pool(pool_address, &mut evm.clone())?;
-    }
 
-    // ** determine which token is weth and its corrospending reserve
-    // ** we want to return the weth token address as token_0
-    let (weth, token_1, weth_reserve) = if token_0 == *WETH {
-        (token_0, token_1, reserve_0)
+        // the pair address must match the CREATE2 address of one of the registered forks
+        v2_fork = match find_v2_fork_of_pair(pool_address, token_0, token_1) {
+            Some(fork) => fork,
+            None => {
+                return Ok(None);
+            }
+        };
     } else {
-        (token_1, token_0, reserve_1)
+        return Ok(None);
+    }
+
+    // ** determine which token is the base token and its corrospending reserve
+    // ** we want to return the base token address as token_0
+    let (base, token_1, base_reserve) = match sort_base_token(token_0, token_1, reserve_0, reserve_1) {
+        Some(sorted) => sorted,
+        None => {
+            return Ok(None);
+        }
+    };
+
+    // ** value the base reserve in ETH
+    let fork_db = evm.db.as_ref().ok_or(anyhow!("Evm has no database"))?;
+    let weth_reserve = base_to_eth(base, base_reserve, fork_db)?;
+
+    let kind = PoolKind::UniswapV2 {
+        factory: v2_fork.factory,
+        fee: v2_fork.fee,
+    };
+
+    Ok(Some(Pool::new(pool_address, base, token_1, weth_reserve, kind)))
+}
+
+// returns (base token, other token, base amount) if exactly one of the tokens is a base token
+fn sort_base_token(
+    token_0: Address,
+    token_1: Address,
+    amount_0: U256,
+    amount_1: U256
+) -> Option<(Address, Address, U256)> {
+    match (get_base_token(token_0).is_some(), get_base_token(token_1).is_some()) {
+        (true, false) => Some((token_0, token_1, amount_0)),
+        (false, true) => Some((token_1, token_0, amount_1)),
+        _ => None,
+    }
+}
+
+// builds a new V3 pool, the tx that created it must already be committed to the evm
+fn get_new_v3_pool(
+    pool_address: Address,
+    pool_created: Option<ethabi::Log>,
+    evm: &mut revm::EVM<ForkDB>
+) -> Result<Pool, anyhow::Error> {
+    let (token_0, token_1, fee) = match pool_created {
+        Some(pool_created) => {
+            let token_0 = pool_created.params[0].value.clone().into_token().into_address().unwrap();
+            let token_1 = pool_created.params[1].value.clone().into_token().into_address().unwrap();
+            let fee = pool_created.params[2].value.clone().into_token().into_uint().unwrap();
+            let created_pool = pool_created.params[4].value.clone().into_token().into_address().unwrap();
+
+            if created_pool != pool_address {
+                return Err(anyhow!("Minted V3 pool {:?} is not the created pool", pool_address));
+            }
+
+            (token_0, token_1, fee.as_u32())
+        }
+        None => {
+            // the pool was created in an earlier tx, make sure its a Uniswap pool
+            let (token_0, token_1) = get_tokens_from_pool(pool_address, evm)?;
+            let fee = get_v3_pool_fee(pool_address, evm)?;
+
+            if !is_v3_factory_pool(pool_address, token_0, token_1, fee, evm)? {
+                return Err(anyhow!("V3 pool {:?} is not from the factory", pool_address));
+            }
+
+            (token_0, token_1, fee)
+        }
     };
 
-    Ok((pool_address, weth, token_1, weth_reserve))
+    // ** we want to return the base token address as token_0
+    let (base, token, _) = sort_base_token(token_0, token_1, U256::zero(), U256::zero()).ok_or(
+        anyhow!("V3 pool {:?} has no base token side", pool_address)
+    )?;
+
+    // ** estimate the base token around the current tick and value it in ETH
+    let base_balance = get_erc20_balance(base, pool_address, evm)?;
+    let fork_db = evm.db.as_ref().ok_or(anyhow!("Evm has no database"))?;
+    let (sqrt_price_x96, liquidity) = get_v3_state_from_storage(pool_address, fork_db)?;
+
+    let base_liquidity = estimate_base_liquidity(
+        sqrt_price_x96,
+        liquidity,
+        base == token_0,
+        base_balance
+    );
+    let weth_liquidity = base_to_eth(base, base_liquidity, fork_db)?;
+
+    Ok(Pool::new(pool_address, base, token, weth_liquidity, PoolKind::UniswapV3 { fee }))
+}
+
+// ** Simulates a bundle before we send it
+// ** the signed txs run in bundle order on the target block and every one of them is committed
+// ** returns an error with the reason if the bundle should not be sent
+// ** our swap is the first of our tx hashes, the amount out is checked on it
+pub fn simulate_bundle(
+    signed_txs: &[Bytes],
+    our_tx_hashes: &[TxHash],
+    out_pool: Address,
+    minimum_received: U256,
+    next_block: &BlockInfo,
+    fork_db: ForkDB
+) -> Result<BundleSimulation, anyhow::Error> {
+    let mut evm = revm::EVM::new();
+    evm.database(fork_db);
+
+    // setup the next block state
+    setup_evm(&mut evm, next_block);
+
+    let coinbase = evm.env.block.coinbase;
+    let coinbase_balance_before = get_eth_balance(coinbase, &evm)?;
+
+    let mut gas_used = 0;
+    let mut amount_out = None;
+    let mut our_txs_seen = 0;
+
+    for signed_tx in signed_txs {
+        let mut tx: Transaction = ethers::utils::rlp::decode(signed_tx)?;
+        tx.from = tx.recover_from()?;
+
+        set_tx_env(&tx, &mut evm);
+        let result = into_simulation_result(evm.transact_commit()?);
+        gas_used += result.gas_used;
+
+        if result.is_reverted {
+            return Err(anyhow!("Tx {:?} reverted", tx.hash));
+        }
+
+        if !our_tx_hashes.contains(&tx.hash) {
+            continue;
+        }
+        our_txs_seen += 1;
+
+        if our_tx_hashes.first() == Some(&tx.hash) {
+            let (real_amount, _) = get_real_amount_from_logs(result.logs, out_pool, *CONTRACT_ADDRESS)?;
+            amount_out = Some(real_amount);
+        }
+    }
+
+    let amount_out = amount_out.ok_or(anyhow!("Our swap is not in the bundle"))?;
+
+    if our_txs_seen != our_tx_hashes.len() {
+        return Err(anyhow!("Only {} of our {} txs are in the bundle", our_txs_seen, our_tx_hashes.len()));
+    }
+
+    if amount_out < minimum_received {
+        return Err(
+            anyhow!("Amount out {} is less than the minimum received {}", amount_out, minimum_received)
+        );
+    }
+
+    let coinbase_payment = get_eth_balance(coinbase, &evm)?.saturating_sub(coinbase_balance_before);
+
+    Ok(BundleSimulation {
+        gas_used,
+        amount_out,
+        coinbase_payment,
+    })
 }
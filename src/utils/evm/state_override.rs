@@ -0,0 +1,128 @@
+use std::fs;
+use ethers::prelude::*;
+use ethers::utils::{ hex, keccak256 };
+use revm::primitives::{ AccountInfo, Bytecode as rBytecode, B256, U256 as rU256 };
+use lazy_static::lazy_static;
+use anyhow::anyhow;
+
+use crate::forked_db::fork_db::ForkDB;
+use crate::utils::constants::*;
+use super::simulate::get_mapping_slot;
+use super::balance_slot::get_balance_slot;
+
+// ** Runtime bytecode of the Sniper contract from the foundry artifact **
+lazy_static! {
+    static ref SNIPER_BYTECODE: Option<Bytes> = match load_sniper_bytecode() {
+        Ok(code) => Some(code),
+        Err(e) => {
+            log::warn!(
+                "Failed to load Sniper bytecode from {}, simulating against the deployed contract: {:?}",
+                *SNIPER_ARTIFACT_FILE,
+                e
+            );
+            None
+        }
+    };
+}
+
+// reads deployedBytecode.object from the artifact `forge build` writes
+fn load_sniper_bytecode() -> Result<Bytes, anyhow::Error> {
+    let content = fs::read_to_string(&*SNIPER_ARTIFACT_FILE)?;
+    let artifact: serde_json::Value = serde_json::from_str(&content)?;
+
+    let object = artifact["deployedBytecode"]["object"]
+        .as_str()
+        .ok_or(anyhow!("deployedBytecode.object not found"))?;
+
+    let code = hex::decode(object.trim_start_matches("0x"))?;
+    if code.is_empty() {
+        return Err(anyhow!("Sniper bytecode is empty"));
+    }
+
+    Ok(code.into())
+}
+
+// Inserts contract bytecode at an address
+pub fn insert_code(fork_db: &mut ForkDB, address: Address, code: Bytes) {
+    let code_hash = B256::from_slice(&keccak256(&code));
+
+    let info = AccountInfo {
+        balance: rU256::ZERO,
+        nonce: 1,
+        code_hash,
+        code: Some(rBytecode::new_raw(code.0)),
+    };
+
+    fork_db.insert_account_info(address.0.into(), info);
+}
+
+// Overrides a storage slot of an address
+pub fn set_storage(
+    fork_db: &mut ForkDB,
+    address: Address,
+    slot: U256,
+    value: U256
+) -> Result<(), anyhow::Error> {
+    fork_db.insert_account_storage(address.0.into(), slot.into(), value.into())?;
+    Ok(())
+}
+
+// Seeds the WETH balance of an address through the balanceOf mapping
+pub fn seed_weth_balance(
+    fork_db: &mut ForkDB,
+    owner: Address,
+    amount: U256
+) -> Result<(), anyhow::Error> {
+    let slot = get_mapping_slot(owner, U256::from(*WETH_BALANCE_SLOT));
+    set_storage(fork_db, *WETH, slot, amount)
+}
+
+// Seeds the balance of any ERC20 through its discovered balances mapping
+pub fn seed_token_balance(
+    fork_db: &mut ForkDB,
+    token: Address,
+    holder: Address,
+    amount: U256
+) -> Result<(), anyhow::Error> {
+    let balance_slot = get_balance_slot(token, fork_db).ok_or(
+        anyhow!("No balances mapping found for {:?}", token)
+    )?;
+    set_storage(fork_db, token, balance_slot.slot_for(holder), amount)
+}
+
+// Inserts the Sniper contract at an address, funds it with WETH and authorizes the signers
+pub fn insert_sniper(
+    fork_db: &mut ForkDB,
+    address: Address,
+    weth_balance: U256
+) -> Result<(), anyhow::Error> {
+    let code = SNIPER_BYTECODE.clone().ok_or(
+        anyhow!("Sniper bytecode is not loaded, run forge build in the contract folder")
+    )?;
+
+    insert_code(fork_db, address, code);
+    seed_weth_balance(fork_db, address, weth_balance)?;
+
+    // the constructor does not run so we authorize the signers ourselves
+    for wallet in SIGNER_WALLETS.iter() {
+        let slot = get_mapping_slot(wallet.address(), U256::from(*SWAP_USERS_SLOT));
+        set_storage(fork_db, address, slot, U256::one())?;
+    }
+
+    Ok(())
+}
+
+// Returns the fork db and the address we simulate our swaps against
+// with USE_LOCAL_SNIPER the Sniper is inserted at SWAPPER_ADDRESS
+// so the checks dont depend on the deployed and funded contract
+// without a forge build we fall back to the deployed contract
+pub fn local_sniper_fork(fork_db: ForkDB) -> Result<(ForkDB, Address), anyhow::Error> {
+    if !*USE_LOCAL_SNIPER || SNIPER_BYTECODE.is_none() {
+        return Ok((fork_db, *CONTRACT_ADDRESS));
+    }
+
+    let mut fork_db = fork_db;
+    insert_sniper(&mut fork_db, *SWAPPER_ADDRESS, *LOCAL_SNIPER_WETH_BALANCE)?;
+
+    Ok((fork_db, *SWAPPER_ADDRESS))
+}
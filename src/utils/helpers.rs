@@ -3,10 +3,12 @@ use std::str::FromStr;
 use ethers::prelude::*;
 use ethers::types::transaction::eip2718::TypedTransaction;
 use bigdecimal::BigDecimal;
-use crate::utils::abi::UniswapV2Pair;
+use crate::utils::abi::{ UniswapV2Pair, UniswapV3Pool, Erc20Token, TRANSFER_EVENT };
+use crate::utils::types::structs::{ pool::Pool, token_info::FundingSource };
+use crate::utils::uniswap_v3::estimate_base_liquidity;
 use anyhow::anyhow;
 
-use super::constants::WETH;
+use super::constants::{ WETH, GAS_LIMIT_MARGIN, SIGNER_WALLETS };
 
 
 /// Create Websocket Client
@@ -30,6 +32,55 @@ pub fn convert_wei_to_gwei(wei: U256) -> BigDecimal {
 }
 
 
+// Calculates the gas limit of a transaction from the simulated gas
+// ** EIP-150: a call only forwards 63/64 of the remaining gas
+// ** so we scale the gas up by 64/63 before adding the safety margin
+pub fn calculate_gas_limit(simulated_gas: u64) -> u64 {
+    let gas = (simulated_gas * 64) / 63;
+    gas + (gas * *GAS_LIMIT_MARGIN) / 100
+}
+
+// the highest base fee possible after a number of full blocks
+// the base fee can rise at most 12.5% per block
+pub fn max_base_fee_after(base_fee: U256, blocks: u64) -> U256 {
+    (0..blocks).fold(base_fee, |fee, _| (fee * 1125) / 1000)
+}
+
+// share of a total in basis points, capped at 100%
+// token balances are arbitrary so the multiplication saturates instead of overflowing
+pub fn calculate_share_bps(amount: U256, total: U256) -> u64 {
+    if total.is_zero() {
+        return 0;
+    }
+    if amount >= total {
+        return 10000;
+    }
+    let share = amount.saturating_mul(U256::from(10000u64)) / total;
+    share.min(U256::from(10000u64)).as_u64()
+}
+
+
+
+// priority fee a pending tx pays to the miner
+pub fn get_priority_fee(tx: &Transaction, base_fee: U256) -> U256 {
+    match tx.max_priority_fee_per_gas {
+        Some(max_priority_fee) => {
+            let max_fee = tx.max_fee_per_gas.unwrap_or_default();
+            max_priority_fee.min(max_fee.saturating_sub(base_fee))
+        }
+        None => tx.gas_price.unwrap_or_default().saturating_sub(base_fee),
+    }
+}
+
+
+
+// the wallet of one of our signers
+pub fn get_signer_wallet(signer: Address) -> Result<LocalWallet, anyhow::Error> {
+    SIGNER_WALLETS.iter()
+        .find(|wallet| wallet.address() == signer)
+        .cloned()
+        .ok_or(anyhow!("No wallet for signer {:?}", signer))
+}
 
 /// Sign eip1559 transactions
 pub async fn sign_eip1559(
@@ -48,9 +99,38 @@ pub async fn sign_eip1559(
     Ok(tx_typed.rlp_signed(&signed_frontrun_tx_sig))
 }
 
-// get the reserves from a V2 pool
+// get the weth reserve of a pool
+// the base token reserve is valued in ETH, same as the pool's weth_liquidity
+// for V3 pools this is the base token around the current tick
 pub async fn get_reserves(
+    pool: &Pool,
+    client: Arc<Provider<Ws>>
+) -> Result<U256, anyhow::Error> {
+    let base_reserve = if pool.is_v3() {
+        get_v3_base_liquidity(pool, client.clone()).await?
+    } else {
+        get_v2_base_reserve(pool.address, pool.token_0, client.clone()).await?
+    };
+
+    // value the base token in ETH through its pair with weth
+    match pool.base_pair() {
+        Some(base_pair) => {
+            let base_pair_reserve = get_v2_base_reserve(base_pair, pool.token_0, client.clone()).await?;
+            let weth_reserve = get_v2_base_reserve(base_pair, *WETH, client.clone()).await?;
+
+            if base_pair_reserve.is_zero() {
+                return Err(anyhow!("Base pair {:?} has no reserves", base_pair));
+            }
+            Ok((base_reserve * weth_reserve) / base_pair_reserve)
+        }
+        None => Ok(base_reserve),
+    }
+}
+
+// get the reserve of a token from a V2 pool
+async fn get_v2_base_reserve(
     target_pool: Address,
+    base_token: Address,
     client: Arc<Provider<Ws>>
 ) -> Result<U256, anyhow::Error> {
     let pair = UniswapV2Pair::new(target_pool, client.clone());
@@ -65,11 +145,123 @@ pub async fn get_reserves(
     };
 
     // match the tokens with the corrospinding reserves
-    let reserve_base = if token_a == *WETH {
+    let reserve_base = if token_a == base_token {
         reserve_a
     } else {
         reserve_b
     };
 
     Ok(reserve_base.into())
-}
\ No newline at end of file
+}
+
+async fn get_v3_base_liquidity(
+    pool: &Pool,
+    client: Arc<Provider<Ws>>
+) -> Result<U256, anyhow::Error> {
+    let v3_pool = UniswapV3Pool::new(pool.address, client.clone());
+    let base = Erc20Token::new(pool.token_0, client.clone());
+
+    let (sqrt_price_x96, _, _, _, _, _, _) = match v3_pool.slot_0().call().await {
+        Ok(r) => r,
+        Err(e) => {
+            return Err(anyhow!("Error getting slot0 {:?}", e));
+        }
+    };
+    let liquidity = v3_pool.liquidity().call().await?;
+    let base_balance = base.balance_of(pool.address).call().await?;
+
+    let base_is_token0 = pool.token_0 < pool.token_1;
+
+    Ok(
+        estimate_base_liquidity(
+            sqrt_price_x96,
+            U256::from(liquidity),
+            base_is_token0,
+            base_balance
+        )
+    )
+}
+
+// get every address that received the token in the given blocks
+pub async fn get_transfer_recipients(
+    token: Address,
+    from_block: U64,
+    to_block: U64,
+    client: Arc<Provider<Ws>>
+) -> Result<Vec<Address>, anyhow::Error> {
+    let filter = Filter::new()
+        .address(token)
+        .topic0(TRANSFER_EVENT.signature())
+        .from_block(from_block)
+        .to_block(to_block);
+    let logs = client.get_logs(&filter).await?;
+
+    let mut recipients = Vec::new();
+    for log in logs {
+        // the recipient is the second indexed topic
+        if let Some(topic) = log.topics.get(2) {
+            let recipient = Address::from(*topic);
+            if !recipients.contains(&recipient) {
+                recipients.push(recipient);
+            }
+        }
+    }
+
+    Ok(recipients)
+}
+
+// finds where an address got its first ETH from
+// binary searches the first block with a balance, returns None if it already had ETH lookback blocks ago
+pub async fn get_funding_source(
+    address: Address,
+    latest_block: U64,
+    lookback: u64,
+    client: Arc<Provider<Ws>>
+) -> Result<Option<FundingSource>, anyhow::Error> {
+    let mut low = latest_block.saturating_sub(U64::from(lookback));
+    let mut high = latest_block;
+
+    let balance_at = |block: U64| {
+        let client = client.clone();
+        async move { client.get_balance(address, Some(BlockId::Number(BlockNumber::Number(block)))).await }
+    };
+
+    if !balance_at(low).await?.is_zero() || balance_at(high).await?.is_zero() {
+        return Ok(None);
+    }
+
+    // the balance is zero at low and non zero at high
+    while high - low > U64::one() {
+        let middle = low + (high - low) / 2;
+        if balance_at(middle).await?.is_zero() {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+
+    let block = match client.get_block_with_txs(high).await? {
+        Some(block) => block,
+        None => {
+            return Ok(Some(FundingSource { block: high, tx: None, funder: None }));
+        }
+    };
+
+    // a direct transfer to the address
+    if let Some(tx) = block.transactions.iter().find(|tx| tx.to == Some(address) && !tx.value.is_zero()) {
+        return Ok(Some(FundingSource { block: high, tx: Some(tx.hash), funder: Some(tx.from) }));
+    }
+
+    // paid out by a contract, eg. a mixer withdrawal or a bridge, the address is in the call data
+    let funding_tx = block.transactions
+        .iter()
+        .find(|tx| tx.input.as_ref().windows(20).any(|window| window == address.as_bytes()));
+
+    Ok(
+        Some(FundingSource {
+            block: high,
+            tx: funding_tx.map(|tx| tx.hash),
+            funder: funding_tx.and_then(|tx| tx.to),
+        })
+    )
+}
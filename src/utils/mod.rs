@@ -2,4 +2,6 @@ pub mod types;
 pub mod evm;
 pub mod helpers;
 pub mod constants;
-pub mod abi;
\ No newline at end of file
+pub mod abi;
+pub mod uniswap_v2;
+pub mod uniswap_v3;
\ No newline at end of file
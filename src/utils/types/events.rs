@@ -1,14 +1,24 @@
-use super::structs::pool::Pool;
-use ethers::types::Transaction;
+use super::structs::{ pool::Pool, token_info::TokenInfo };
+use ethers::types::{ Address, Transaction };
 
 
 
 // New pair event from the pair oracle
 #[derive(Debug, Clone)]
 pub enum NewPairEvent {
+    // found in a pending tx, we backrun it
+    // launchers are the deployer and the owner of the token
     NewPairWithTx {
         pool: Pool,
         tx: Transaction,
+        launchers: Vec<Address>,
+        token_info: TokenInfo,
+    },
+    // found in the logs of a new block, we buy in the next block
+    NewPairInBlock {
+        pool: Pool,
+        launchers: Vec<Address>,
+        token_info: TokenInfo,
     },
 }
 
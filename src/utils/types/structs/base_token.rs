@@ -0,0 +1,32 @@
+use ethers::prelude::*;
+use crate::utils::constants::{ BASE_TOKENS, WETH };
+
+
+// A token new pairs can be quoted in, eg. WETH or a stablecoin
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BaseToken {
+    pub address: Address,
+    // the V2 pair with WETH we route through and read the ETH price from
+    // zero for WETH itself
+    pub weth_pair: Address,
+}
+
+impl BaseToken {
+    pub fn new(address: Address, weth_pair: Address) -> BaseToken {
+        BaseToken {
+            address,
+            weth_pair,
+        }
+    }
+
+    pub fn is_weth(&self) -> bool {
+        self.address == *WETH
+    }
+}
+
+// get the configured base token for an address
+pub fn get_base_token(address: Address) -> Option<BaseToken> {
+    BASE_TOKENS.iter()
+        .find(|x| x.address == address)
+        .copied()
+}
@@ -2,11 +2,21 @@ use ethers::prelude::*;
 
 use tokio::sync::RwLock;
 use std::sync::Arc;
+use std::time::Duration;
+use std::collections::HashSet;
 use crate::oracles::block_oracle::{ BlockOracle, BlockInfo };
 use super::oracles::*;
 use crate::forked_db::fork_db::ForkDB;
+use crate::utils::constants::{
+    NONCE_EXPIRY_BLOCKS,
+    NONCE_EXPIRY_MARGIN_BLOCKS,
+    BUNDLE_TARGET_BLOCKS,
+    MAX_SPEED_UPS,
+    SPEED_UP_INTERVAL_SECS,
+};
 
 use super::snipe_tx::SnipeTx;
+use super::pool::Pool;
 
 // Holds all oracles for the bot
 #[derive(Debug, Clone)]
@@ -16,6 +26,8 @@ pub struct Bot {
     pub sell_oracle: Arc<RwLock<SellOracle>>,
     pub retry_oracle: Arc<RwLock<RetryOracle>>,
     pub fork_db_oracle: Arc<RwLock<ForkOracle>>,
+    pub competitor_oracle: Arc<RwLock<CompetitorOracle>>,
+    pub pair_oracle: Arc<RwLock<PairOracle>>,
 }
 
 impl Bot {
@@ -25,7 +37,9 @@ impl Bot {
         nonce_oracle: Arc<RwLock<NonceOracle>>,
         sell_oracle: Arc<RwLock<SellOracle>>,
         retry_oracle: Arc<RwLock<RetryOracle>>,
-        fork_db_oracle: Arc<RwLock<ForkOracle>>
+        fork_db_oracle: Arc<RwLock<ForkOracle>>,
+        competitor_oracle: Arc<RwLock<CompetitorOracle>>,
+        pair_oracle: Arc<RwLock<PairOracle>>
     ) -> Self {
         Bot {
             block_oracle,
@@ -33,6 +47,8 @@ impl Bot {
             sell_oracle,
             retry_oracle,
             fork_db_oracle,
+            competitor_oracle,
+            pair_oracle,
         }
     }
     // gets the fork_db
@@ -54,14 +70,26 @@ impl Bot {
         (latest_block, next_block)
     }
 
-    // returns the nonce and updates it
-    pub async fn get_nonce(&mut self) -> U256 {
+    // picks a signer for our new txs and hands out `count` sequential nonces, returns the first
+    // urgent txs, eg. rug escapes, get a signer without a pending tx
+    // the nonces expire if the txs never report back, never while a bundle copy or speed up is live
+    pub async fn get_nonces(&mut self, urgent: bool, count: usize) -> (Address, U256) {
+        let (_, next_block) = self.get_block_info().await;
+        let expires_at = next_block.number + nonce_expiry_blocks();
+
         let mut nonce_oracle = self.nonce_oracle.write().await;
-        let nonce = nonce_oracle.get_nonce();
-        nonce_oracle.update_nonce(nonce + 1);
+        let signer = nonce_oracle.pick_signer(urgent);
+        let nonce = nonce_oracle.acquire_nonces(signer, count, expires_at);
         drop(nonce_oracle);
 
-        nonce
+        (signer, nonce)
+    }
+
+    // frees the nonces of txs that were never mined and are not pending so they are handed out again
+    pub async fn release_nonces(&mut self, signer: Address, nonce: U256, count: usize) {
+        let mut nonce_oracle = self.nonce_oracle.write().await;
+        nonce_oracle.release_nonces(signer, nonce, count);
+        drop(nonce_oracle);
     }
 
     // get tx len of sell oracle
@@ -128,6 +156,27 @@ impl Bot {
         drop(sell_oracle);
     }
 
+    // records a full EVM sell quote in the sell oracle
+    pub async fn record_evm_sell(
+        &mut self,
+        snipe_tx: SnipeTx,
+        sell_tax_bps: Option<u64>,
+        balance_slot: Option<U256>,
+        amount_out: U256,
+        block: U64
+    ) {
+        let mut sell_oracle = self.sell_oracle.write().await;
+        sell_oracle.record_evm_sell(snipe_tx, sell_tax_bps, balance_slot, amount_out, block);
+        drop(sell_oracle);
+    }
+
+    // records a fast path sell quote in the sell oracle
+    pub async fn record_fast_sell(&mut self, snipe_tx: SnipeTx, amount_out: U256) {
+        let mut sell_oracle = self.sell_oracle.write().await;
+        sell_oracle.record_fast_sell(snipe_tx, amount_out);
+        drop(sell_oracle);
+    }
+
     // adds tx data to retry oracle
     pub async fn add_tx_to_retry_oracle(&mut self, tx_data: SnipeTx) {
         let mut retry_oracle = self.retry_oracle.write().await;
@@ -164,4 +213,70 @@ impl Bot {
         retry_oracle.set_tx_is_pending(tx_data, pending);
         drop(retry_oracle);
     }
+
+    // marks a new pool as seen, returns false if another source already found it
+    pub async fn mark_pool_seen(&mut self, pool_address: Address, block: U64) -> bool {
+        let mut pair_oracle = self.pair_oracle.write().await;
+        let is_new = pair_oracle.mark_seen(pool_address, block);
+        drop(pair_oracle);
+
+        is_new
+    }
+
+    // remembers the V3 pools initialized in a block
+    pub async fn mark_pools_initialized(&mut self, pool_addresses: Vec<Address>, block: U64) {
+        let mut pair_oracle = self.pair_oracle.write().await;
+        pair_oracle.mark_initialized(pool_addresses, block);
+        drop(pair_oracle);
+    }
+
+    // gets the V3 pools that were initialized in an earlier block
+    pub async fn get_initialized_pools(&self) -> HashSet<Address> {
+        let pair_oracle = self.pair_oracle.read().await;
+        let initialized_pools = pair_oracle.get_initialized_pools();
+        drop(pair_oracle);
+
+        initialized_pools
+    }
+
+    // starts watching a new pool for competing swaps
+    pub async fn watch_pool(&mut self, pool: Pool, pair_tx: TxHash) {
+        let mut competitor_oracle = self.competitor_oracle.write().await;
+        competitor_oracle.watch_pool(pool, pair_tx);
+        drop(competitor_oracle);
+    }
+
+    // stops watching a pool for competing swaps
+    pub async fn unwatch_pool(&mut self, pool_address: Address) {
+        let mut competitor_oracle = self.competitor_oracle.write().await;
+        competitor_oracle.unwatch_pool(pool_address);
+        drop(competitor_oracle);
+    }
+
+    // how long we have been watching a pool for competing swaps
+    pub async fn watched_for(&self, pool_address: Address) -> Duration {
+        let competitor_oracle = self.competitor_oracle.read().await;
+        let watched_for = competitor_oracle.watched_for(pool_address);
+        drop(competitor_oracle);
+
+        watched_for
+    }
+
+    // gets the competing swaps seen for a pool
+    pub async fn get_competing_txs(&self, pool_address: Address) -> Vec<Transaction> {
+        let competitor_oracle = self.competitor_oracle.read().await;
+        let competing_txs = competitor_oracle.get_competing_txs(pool_address);
+        drop(competitor_oracle);
+
+        competing_txs
+    }
+}
+
+// how many blocks a nonce stays reserved
+// the public route waits for the first tx, every speed up and the cancel, at 12 seconds per block
+fn nonce_expiry_blocks() -> u64 {
+    let bundle_blocks = *BUNDLE_TARGET_BLOCKS;
+    let public_route_blocks = ((*MAX_SPEED_UPS + 2) * *SPEED_UP_INTERVAL_SECS).div_ceil(12);
+
+    (*NONCE_EXPIRY_BLOCKS).max(bundle_blocks.max(public_route_blocks) + *NONCE_EXPIRY_MARGIN_BLOCKS)
 }
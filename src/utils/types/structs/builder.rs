@@ -0,0 +1,41 @@
+use ethers::prelude::*;
+use url::Url;
+
+
+// How we send a bundle to a builder
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BuilderMethod {
+    // eth_sendBundle signed with the flashbots identity header
+    FlashbotsBundle,
+    // plain eth_sendBundle json rpc call without the signature header
+    RawBundle,
+}
+
+// A MEV builder or relay we send our bundles to
+#[derive(Clone, Debug, PartialEq)]
+pub struct Builder {
+    pub name: &'static str,
+    pub url: Url,
+    pub method: BuilderMethod,
+    pub enabled: bool,
+    // fee recipient of the blocks it builds, used to attribute inclusions
+    pub coinbase: Option<Address>,
+}
+
+impl Builder {
+    pub fn new(
+        name: &'static str,
+        url: &str,
+        method: BuilderMethod,
+        enabled: bool,
+        coinbase: Option<Address>
+    ) -> Builder {
+        Builder {
+            name,
+            url: Url::parse(url).unwrap(),
+            method,
+            enabled,
+            coinbase,
+        }
+    }
+}
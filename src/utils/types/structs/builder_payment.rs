@@ -0,0 +1,8 @@
+// How we pay the block builder for including our txs
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BuilderPayment {
+    // max_priority_fee_per_gas, paid even if our swap reverts
+    PriorityFee,
+    // a direct block.coinbase transfer from the contract, paid only if the swap succeeds
+    CoinbaseTransfer,
+}
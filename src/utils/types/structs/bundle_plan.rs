@@ -0,0 +1,179 @@
+use ethers::prelude::*;
+use ethers::types::transaction::eip2930::AccessList;
+
+
+// Where our txs go relative to the foreign txs of the bundle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BundleStrategy {
+    // our txs run first, eg. when we escape a rug pull
+    Frontrun,
+    // our txs run after the foreign txs, eg. when we snipe a launch
+    Backrun,
+    // there is nothing to frontrun or backrun, eg. a normal sell
+    Standalone,
+}
+
+// One of our own txs to the contract
+#[derive(Debug, Clone, PartialEq)]
+pub struct OurTx {
+    pub call_data: Bytes,
+    pub gas_limit: u64,
+    pub access_list: AccessList,
+}
+
+// One tx of the bundle
+#[derive(Debug, Clone, PartialEq)]
+pub enum BundleItem {
+    // a tx someone else signed, eg. the launcher's addLiquidity or openTrading
+    Foreign(Transaction),
+    // our swap from the TxData
+    OurSwap,
+    // another one of our txs
+    Ours(OurTx),
+}
+
+// The txs of a bundle in execution order
+// a launch can span several txs (deploy, addLiquidity, openTrading) and ours can go anywhere in between
+// our txs are signed with sequential nonces in the order they appear
+#[derive(Debug, Clone, PartialEq)]
+pub struct BundlePlan {
+    pub strategy: BundleStrategy,
+    pub items: Vec<BundleItem>,
+}
+
+impl BundlePlan {
+    pub fn frontrun(foreign_txs: Vec<Transaction>) -> Self {
+        let foreign_items = foreign_txs.into_iter().map(BundleItem::Foreign);
+
+        BundlePlan {
+            strategy: BundleStrategy::Frontrun,
+            items: std::iter::once(BundleItem::OurSwap).chain(foreign_items).collect(),
+        }
+    }
+
+    pub fn backrun(foreign_txs: Vec<Transaction>) -> Self {
+        let foreign_items = foreign_txs.into_iter().map(BundleItem::Foreign);
+
+        BundlePlan {
+            strategy: BundleStrategy::Backrun,
+            items: foreign_items.chain(std::iter::once(BundleItem::OurSwap)).collect(),
+        }
+    }
+
+    pub fn standalone() -> Self {
+        BundlePlan {
+            strategy: BundleStrategy::Standalone,
+            items: vec![BundleItem::OurSwap],
+        }
+    }
+
+    // backruns the pending tx if there is one
+    pub fn backrun_or_standalone(pending_tx: Option<Transaction>) -> Self {
+        match pending_tx {
+            Some(tx) => BundlePlan::backrun(vec![tx]),
+            None => BundlePlan::standalone(),
+        }
+    }
+
+    // adds one of our txs at a position of the bundle, the end if the position is past it
+    pub fn insert_our_tx(&mut self, position: usize, our_tx: OurTx) {
+        let position = position.min(self.items.len());
+        self.items.insert(position, BundleItem::Ours(our_tx));
+    }
+
+    // position of our swap in the bundle
+    pub fn swap_position(&self) -> usize {
+        self.items
+            .iter()
+            .position(|item| *item == BundleItem::OurSwap)
+            .unwrap_or(self.items.len())
+    }
+
+    // the foreign txs in bundle order
+    pub fn foreign_txs(&self) -> Vec<&Transaction> {
+        self.items
+            .iter()
+            .filter_map(|item| {
+                match item {
+                    BundleItem::Foreign(tx) => Some(tx),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    // the same plan without the given foreign txs, eg. once they are mined
+    pub fn without_txs(&self, tx_hashes: &[TxHash]) -> Self {
+        BundlePlan {
+            strategy: self.strategy,
+            items: self.items
+                .iter()
+                .filter(|item| {
+                    match item {
+                        BundleItem::Foreign(tx) => !tx_hashes.contains(&tx.hash),
+                        _ => true,
+                    }
+                })
+                .cloned()
+                .collect(),
+        }
+    }
+
+    // the foreign txs we must simulate before our swap
+    pub fn txs_before_swap(&self) -> Vec<&Transaction> {
+        self.items[..self.swap_position()]
+            .iter()
+            .filter_map(|item| {
+                match item {
+                    BundleItem::Foreign(tx) => Some(tx),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    // our swap and our other txs in bundle order, which is also their nonce order
+    pub fn our_txs(&self, swap: OurTx) -> Vec<OurTx> {
+        self.items
+            .iter()
+            .filter_map(|item| {
+                match item {
+                    BundleItem::Foreign(_) => None,
+                    BundleItem::OurSwap => Some(swap.clone()),
+                    BundleItem::Ours(our_tx) => Some(our_tx.clone()),
+                }
+            })
+            .collect()
+    }
+
+    // index of our swap among our txs
+    pub fn swap_index(&self) -> usize {
+        self.items[..self.swap_position()]
+            .iter()
+            .filter(|item| matches!(item, BundleItem::Ours(_)))
+            .count()
+    }
+
+    // how many of the txs are ours
+    pub fn our_tx_count(&self) -> usize {
+        self.items
+            .iter()
+            .filter(|item| !matches!(item, BundleItem::Foreign(_)))
+            .count()
+    }
+
+    // puts our signed txs, in nonce order, and the signed foreign txs into bundle order
+    pub fn order(&self, our_txs: Vec<Bytes>) -> Vec<Bytes> {
+        let mut our_txs = our_txs.into_iter();
+
+        self.items
+            .iter()
+            .filter_map(|item| {
+                match item {
+                    BundleItem::Foreign(tx) => Some(tx.rlp()),
+                    BundleItem::OurSwap | BundleItem::Ours(_) => our_txs.next(),
+                }
+            })
+            .collect()
+    }
+}
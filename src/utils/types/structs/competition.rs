@@ -0,0 +1,58 @@
+use ethers::prelude::*;
+use crate::utils::constants::*;
+
+
+// Result of simulating our buy after the other pending swaps on the same pool
+#[derive(Debug, Clone, PartialEq)]
+pub struct Competition {
+    // pending swaps that actually traded on the pool
+    pub competitors: usize,
+    // highest priority fee paid by a competitor
+    pub max_competitor_tip: U256,
+    // tokens we get when we are the only buyer
+    pub amount_out_alone: U256,
+    // tokens we get when we are ordered after the competitors
+    pub amount_out_after: U256,
+}
+
+impl Competition {
+    // how much of our fill is left after the competitors (in basis points)
+    pub fn fill_bps(&self) -> u64 {
+        if self.amount_out_alone.is_zero() {
+            return 0;
+        }
+        if self.amount_out_after >= self.amount_out_alone {
+            return 10000;
+        }
+        ((self.amount_out_after * 10000) / self.amount_out_alone).as_u64()
+    }
+
+    // true if the expected fill is too poor to buy
+    pub fn is_fill_too_poor(&self) -> bool {
+        self.competitors > 0 && self.fill_bps() < *MIN_COMPETITOR_FILL_BPS
+    }
+
+    // scales the amount in down by the fill, so we dont buy the top of a crowded launch
+    pub fn adapt_amount_in(&self, amount_in: U256) -> U256 {
+        if self.competitors == 0 {
+            return amount_in;
+        }
+        let adapted = (amount_in * self.fill_bps()) / 10000;
+        adapted.max(*MIN_BUY_SIZE).min(amount_in)
+    }
+
+    // outbids the highest competitor tip, capped at the max tip
+    pub fn adapt_miner_tip(&self, miner_tip: U256) -> U256 {
+        if self.competitors == 0 {
+            return miner_tip;
+        }
+        let outbid =
+            self.max_competitor_tip + (self.max_competitor_tip * *COMPETITOR_TIP_BUMP) / 100;
+        outbid.max(miner_tip).min(*MAX_MINER_TIP_TO_SNIPE)
+    }
+
+    // minimum tokens to receive when we are ordered after the competitors
+    pub fn minimum_received(&self) -> U256 {
+        (self.amount_out_after * U256::from(*BUY_NUMERATOR)) / U256::from(*BUY_DENOMINATOR)
+    }
+}
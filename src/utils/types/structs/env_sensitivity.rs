@@ -0,0 +1,86 @@
+use ethers::prelude::*;
+use crate::utils::constants::*;
+use crate::utils::evm::insp::env_inspector::EnvOpcode;
+
+
+// A change to the environment we re-run the buy and sell with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EnvVariation {
+    // base fee + our snipe tip instead of the bare base fee
+    RealisticGasPrice,
+    // a real builder as the coinbase
+    BuilderCoinbase,
+    // buy a few blocks after launch
+    LaterBlock,
+    // a higher base fee
+    HigherBaseFee,
+}
+
+impl EnvVariation {
+    // the variation that tests an opcode
+    // ORIGIN has none, only our caller can call the Sniper
+    pub fn for_opcode(opcode: EnvOpcode) -> Option<Self> {
+        match opcode {
+            EnvOpcode::GasPrice => Some(EnvVariation::RealisticGasPrice),
+            EnvOpcode::Coinbase => Some(EnvVariation::BuilderCoinbase),
+            EnvOpcode::Number | EnvOpcode::Timestamp => Some(EnvVariation::LaterBlock),
+            EnvOpcode::BaseFee => Some(EnvVariation::HigherBaseFee),
+            EnvOpcode::Origin => None,
+        }
+    }
+
+    // only the variations that single out snipers are traps
+    // the block and the base fee change for every buyer, eg. a launch tax that decays by block
+    pub fn is_trap(&self) -> bool {
+        match self {
+            EnvVariation::RealisticGasPrice | EnvVariation::BuilderCoinbase => true,
+            EnvVariation::LaterBlock | EnvVariation::HigherBaseFee => false,
+        }
+    }
+}
+
+// Outcome of a simulated buy followed by a sell
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwapOutcome {
+    pub buy_reverted: bool,
+    pub sell_reverted: bool,
+    pub tokens_out: U256,
+    pub weth_out: U256,
+}
+
+impl SwapOutcome {
+    // true if the outcomes differ by more than the tolerance
+    pub fn differs_from(&self, other: &SwapOutcome) -> bool {
+        self.buy_reverted != other.buy_reverted ||
+            self.sell_reverted != other.sell_reverted ||
+            differs_by_more_than_tolerance(self.tokens_out, other.tokens_out) ||
+            differs_by_more_than_tolerance(self.weth_out, other.weth_out)
+    }
+}
+
+fn differs_by_more_than_tolerance(a: U256, b: U256) -> bool {
+    let (high, low) = if a > b { (a, b) } else { (b, a) };
+    if high.is_zero() {
+        return false;
+    }
+    ((high - low) * 10000) / high > U256::from(*ENV_CHECK_TOLERANCE_BPS)
+}
+
+// The environment opcodes a token reads and the variations that changed its outcome
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvSensitivity {
+    pub opcodes_read: Vec<EnvOpcode>,
+    pub baseline: SwapOutcome,
+    pub flagged: Vec<(EnvVariation, SwapOutcome)>,
+}
+
+impl EnvSensitivity {
+    pub fn is_sensitive(&self) -> bool {
+        !self.flagged.is_empty()
+    }
+
+    // one of the flagged variations singles out snipers
+    pub fn is_trap(&self) -> bool {
+        self.flagged.iter().any(|(variation, _)| variation.is_trap())
+    }
+}
@@ -2,4 +2,16 @@ pub mod bot;
 pub mod oracles;
 pub mod pool;
 pub mod snipe_tx;
-pub mod tx_data;
\ No newline at end of file
+pub mod tx_data;
+pub mod token_checks;
+pub mod competition;
+pub mod env_sensitivity;
+pub mod storage_change;
+pub mod base_token;
+pub mod v2_fork;
+pub mod token_info;
+pub mod builder;
+pub mod bundle_plan;
+pub mod tx_route;
+pub mod builder_payment;
+pub mod tx_outcome;
\ No newline at end of file
@@ -2,6 +2,15 @@ use ethers::prelude::*;
 use super::snipe_tx::SnipeTx;
 use crate::forked_db::fork_db::ForkDB;
 use super::pool::Pool;
+use crate::utils::constants::{
+    FAST_PATH_TAX_TOLERANCE,
+    COMPETITOR_WATCH_SECS,
+    SEEN_POOL_TTL_BLOCKS,
+    INITIALIZED_POOL_TTL_BLOCKS,
+    MIN_SIGNER_BALANCE,
+};
+use std::collections::{ HashMap, HashSet, BTreeMap, BTreeSet };
+use std::time::{ Duration, Instant };
 
 
 // New Pair, Holds the pool and the transaction from the pair oracle
@@ -11,6 +20,53 @@ pub struct NewPair {
     pub tx: Transaction,
 }
 
+// Pair Oracle, Holds the pools we already processed
+// the mempool and the block logs can both find the same pool
+// also holds the V3 pools that were initialized in a block but got no liquidity yet
+#[derive(Debug, Clone)]
+pub struct PairOracle {
+    pub seen_pools: HashMap<Address, U64>,
+    pub initialized_pools: HashMap<Address, U64>,
+}
+
+impl PairOracle {
+    pub fn new() -> Self {
+        PairOracle { seen_pools: HashMap::new(), initialized_pools: HashMap::new() }
+    }
+
+    // remembers the V3 pools initialized in a block
+    // drops the pools initialized more than INITIALIZED_POOL_TTL_BLOCKS ago
+    pub fn mark_initialized(&mut self, pool_addresses: Vec<Address>, block: U64) {
+        let ttl = U64::from(*INITIALIZED_POOL_TTL_BLOCKS);
+        self.initialized_pools.retain(|_, initialized_at| *initialized_at + ttl > block);
+
+        for pool_address in pool_addresses {
+            self.initialized_pools.entry(pool_address).or_insert(block);
+        }
+    }
+
+    // the V3 pools that may still get their first liquidity
+    pub fn get_initialized_pools(&self) -> HashSet<Address> {
+        self.initialized_pools.keys().copied().collect()
+    }
+
+    // marks a pool as seen, returns false if it was already seen
+    // drops the pools we have seen more than SEEN_POOL_TTL_BLOCKS ago
+    // a seen V3 pool got its liquidity so its later Mints are not new pools
+    pub fn mark_seen(&mut self, pool_address: Address, block: U64) -> bool {
+        let ttl = U64::from(*SEEN_POOL_TTL_BLOCKS);
+        self.seen_pools.retain(|_, seen_at| *seen_at + ttl > block);
+        self.initialized_pools.remove(&pool_address);
+
+        if self.seen_pools.contains_key(&pool_address) {
+            return false;
+        }
+
+        self.seen_pools.insert(pool_address, block);
+        true
+    }
+}
+
 // ForkOracle
 // Creates a new backend connection in every new block
 #[derive(Debug, Clone)]
@@ -33,26 +89,171 @@ impl ForkOracle {
     }
 }
 
-// Nonce Oracle, Holds the nonce for the next transaction
-// Before we send any tx we notify the oracle to update the nonce
+// The nonces and balance of a single signer
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SignerNonces {
+    pub balance: U256,
+    // the pending tx count of the signer, every nonce below it is used
+    pub confirmed: U256,
+    // the mined tx count of the signer, the nonces from it to `confirmed` wait in the mempool
+    pub mined: U256,
+    // the next nonce we have never handed out
+    pub next: U256,
+    // nonces handed out to live txs and the block after which they expire
+    pub in_flight: BTreeMap<U256, U64>,
+    // nonces of txs that never landed, handed out again before `next`
+    pub reclaimed: BTreeSet<U256>,
+}
+
+impl SignerNonces {
+    // hands out `count` sequential nonces and returns the first
+    // the lowest reclaimed nonce goes first so we dont leave gaps, if the whole sequence fits from it
+    fn acquire(&mut self, count: usize, expires_at: U64) -> U256 {
+        let start = self.next.max(self.confirmed);
+
+        let first = match self.reclaimed.first() {
+            Some(&lowest) if
+                (0..count).all(|i| lowest + i >= start || self.reclaimed.contains(&(lowest + i)))
+            => lowest,
+            _ => start,
+        };
+
+        for i in 0..count {
+            self.reclaimed.remove(&(first + i));
+            self.in_flight.insert(first + i, expires_at);
+        }
+        self.next = self.next.max(first + count);
+
+        first
+    }
+
+    // has a tx we sent that did not report back yet, or a tx waiting in the mempool
+    fn has_pending_tx(&self) -> bool {
+        !self.in_flight.is_empty() || self.confirmed > self.mined
+    }
+
+    // frees the nonce of a tx that was never mined and is not pending anywhere
+    fn release(&mut self, nonce: U256) {
+        if self.in_flight.remove(&nonce).is_none() {
+            return;
+        }
+        if nonce >= self.confirmed {
+            self.reclaimed.insert(nonce);
+        }
+        self.compact();
+    }
+
+    // resyncs with the mined and pending tx counts and reclaims the nonces of expired txs
+    fn sync(&mut self, mined_count: U256, pending_count: U256, block_number: U64) {
+        self.confirmed = pending_count;
+        self.mined = mined_count;
+
+        // these are used by txs the node knows about
+        self.in_flight.retain(|nonce, _| *nonce >= pending_count);
+        self.reclaimed.retain(|nonce| *nonce >= pending_count);
+
+        let expired: Vec<U256> = self.in_flight
+            .iter()
+            .filter(|(_, expires_at)| **expires_at < block_number)
+            .map(|(nonce, _)| *nonce)
+            .collect();
+
+        for nonce in expired {
+            log::warn!("Reclaimed nonce {} of an expired tx", nonce);
+            self.release(nonce);
+        }
+
+        self.next = self.next.max(pending_count);
+        self.compact();
+    }
+
+    // drops the reclaimed nonces at the top so `next` goes back down
+    fn compact(&mut self) {
+        while self.next > self.confirmed && self.reclaimed.remove(&(self.next - 1)) {
+            self.next = self.next - 1;
+        }
+    }
+}
+
+// Nonce Oracle, Holds the nonces of every signer and picks the signer for a new tx
+// a nonce is never handed out to two live txs, it is only reused after its tx
+// is released because it was never mined nor pending, or its tx expired
 #[derive(Debug, Clone, PartialEq)]
 pub struct NonceOracle {
-    pub nonce: U256,
+    // in the order of SIGNER_WALLETS
+    pub order: Vec<Address>,
+    pub signers: HashMap<Address, SignerNonces>,
+    // index of the signer routine txs start looking from
+    pub rotation: usize,
 }
 
 impl NonceOracle {
-    pub fn new() -> Self {
-        NonceOracle { nonce: U256::zero() }
+    pub fn new(signers: Vec<Address>) -> Self {
+        NonceOracle {
+            order: signers.clone(),
+            signers: signers
+                .into_iter()
+                .map(|signer| (signer, SignerNonces::default()))
+                .collect(),
+            rotation: 0,
+        }
+    }
+
+    // the signers we track
+    pub fn get_signers(&self) -> Vec<Address> {
+        self.order.clone()
+    }
+
+    // picks the signer for a new tx
+    // urgent txs get a signer without a pending tx so they are never stuck behind one
+    // routine txs rotate across the signers
+    // signers below MIN_SIGNER_BALANCE are only used if every signer is
+    pub fn pick_signer(&mut self, urgent: bool) -> Address {
+        let funded: Vec<Address> = self.order
+            .iter()
+            .filter(|signer| self.signers[*signer].balance >= *MIN_SIGNER_BALANCE)
+            .copied()
+            .collect();
+        let candidates = if funded.is_empty() { self.order.clone() } else { funded };
+
+        let free = candidates
+            .iter()
+            .find(|signer| !self.signers[*signer].has_pending_tx())
+            .copied();
+
+        if urgent {
+            if let Some(signer) = free {
+                return signer;
+            }
+        }
+
+        self.rotation = (self.rotation + 1) % candidates.len();
+        candidates[self.rotation]
+    }
+
+    // updates the balance of the signer
+    pub fn update_balance(&mut self, signer: Address, balance: U256) {
+        self.signers.entry(signer).or_default().balance = balance;
+    }
+
+    // hands out `count` sequential nonces for new txs of the signer, returns the first
+    pub fn acquire_nonces(&mut self, signer: Address, count: usize, expires_at: U64) -> U256 {
+        self.signers.entry(signer).or_default().acquire(count, expires_at)
     }
 
-    // updates the nonce
-    pub fn update_nonce(&mut self, nonce: U256) {
-        self.nonce = nonce;
+    // frees the nonces of txs that were never mined and are not pending anywhere
+    // any other nonce is left for `sync`
+    pub fn release_nonces(&mut self, signer: Address, nonce: U256, count: usize) {
+        if let Some(nonces) = self.signers.get_mut(&signer) {
+            for i in 0..count {
+                nonces.release(nonce + i);
+            }
+        }
     }
 
-    // get the current nonce
-    pub fn get_nonce(&self) -> U256 {
-        self.nonce
+    // updates the signer with its mined and pending tx counts
+    pub fn sync(&mut self, signer: Address, mined_count: U256, pending_count: U256, block_number: U64) {
+        self.signers.entry(signer).or_default().sync(mined_count, pending_count, block_number);
     }
 }
 
@@ -123,6 +324,40 @@ impl SellOracle {
             }
         }
     }
+
+    // records a full EVM sell quote
+    // the tax counts as fixed if it matches the previous measurement
+    pub fn record_evm_sell(
+        &mut self,
+        snipe_tx: SnipeTx,
+        sell_tax_bps: Option<u64>,
+        balance_slot: Option<U256>,
+        amount_out: U256,
+        block: U64
+    ) {
+        for tx in &mut self.tx_data {
+            if tx.pool.token_1 == snipe_tx.pool.token_1 {
+                tx.fixed_tax = match (tx.sell_tax_bps, sell_tax_bps) {
+                    (Some(previous), Some(current)) =>
+                        previous.abs_diff(current) <= *FAST_PATH_TAX_TOLERANCE,
+                    _ => false,
+                };
+                tx.sell_tax_bps = sell_tax_bps;
+                tx.balance_slot = balance_slot;
+                tx.last_evm_check = block;
+                tx.last_amount_out = amount_out;
+            }
+        }
+    }
+
+    // records a fast path sell quote
+    pub fn record_fast_sell(&mut self, snipe_tx: SnipeTx, amount_out: U256) {
+        for tx in &mut self.tx_data {
+            if tx.pool.token_1 == snipe_tx.pool.token_1 {
+                tx.last_amount_out = amount_out;
+            }
+        }
+    }
 }
 
 
@@ -168,3 +403,244 @@ impl RetryOracle {
         }
     }
 }
+
+
+// A new pool we watch for competing swaps
+#[derive(Debug, Clone)]
+pub struct WatchedPool {
+    pub pool: Pool,
+    // the tx that creates the pair, never counts as a competitor
+    pub pair_tx: TxHash,
+    pub added_at: Instant,
+    pub competing_txs: Vec<Transaction>,
+}
+
+impl WatchedPool {
+    // a pending tx targets the pool if its call data holds the token or the pool address
+    // routers pass the token in the path and custom bots usually pass the pool
+    fn is_targeted_by(&self, tx: &Transaction) -> bool {
+        let input = tx.input.as_ref();
+        let token = self.pool.token_1.as_bytes();
+        let pool = self.pool.address.as_bytes();
+
+        input.windows(20).any(|window| window == token || window == pool)
+    }
+}
+
+// Competitor Oracle, Holds the pending swaps from other bots on the pools we snipe
+#[derive(Debug, Clone)]
+pub struct CompetitorOracle {
+    pub pools: HashMap<Address, WatchedPool>,
+}
+
+impl CompetitorOracle {
+    pub fn new() -> Self {
+        CompetitorOracle { pools: HashMap::new() }
+    }
+
+    // start watching a new pool, drops pools we watched for too long
+    pub fn watch_pool(&mut self, pool: Pool, pair_tx: TxHash) {
+        let max_age = Duration::from_secs(*COMPETITOR_WATCH_SECS);
+        self.pools.retain(|_, watched| watched.added_at.elapsed() < max_age);
+
+        self.pools.entry(pool.address).or_insert(WatchedPool {
+            pool,
+            pair_tx,
+            added_at: Instant::now(),
+            competing_txs: Vec::new(),
+        });
+    }
+
+    // stop watching a pool
+    pub fn unwatch_pool(&mut self, pool_address: Address) {
+        self.pools.remove(&pool_address);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pools.is_empty()
+    }
+
+    // adds a pending tx to every watched pool it targets
+    pub fn add_pending_tx(&mut self, tx: &Transaction) {
+        for watched in self.pools.values_mut() {
+            if tx.hash == watched.pair_tx || !watched.is_targeted_by(tx) {
+                continue;
+            }
+            if watched.competing_txs.iter().any(|x| x.hash == tx.hash) {
+                continue;
+            }
+            log::info!("Competing swap {:?} on {:?}", tx.hash, watched.pool.token_1);
+            watched.competing_txs.push(tx.clone());
+        }
+    }
+
+    // how long we have been watching a pool, zero if we dont watch it
+    pub fn watched_for(&self, pool_address: Address) -> Duration {
+        match self.pools.get(&pool_address) {
+            Some(watched) => watched.added_at.elapsed(),
+            None => Duration::ZERO,
+        }
+    }
+
+    // get the competing txs for a pool
+    pub fn get_competing_txs(&self, pool_address: Address) -> Vec<Transaction> {
+        match self.pools.get(&pool_address) {
+            Some(watched) => watched.competing_txs.clone(),
+            None => Vec::new(),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nonces(values: &[u64]) -> Vec<U256> {
+        values.iter().map(|x| U256::from(*x)).collect()
+    }
+
+    fn in_flight(signer: &SignerNonces) -> Vec<U256> {
+        signer.in_flight.keys().copied().collect()
+    }
+
+    fn reclaimed(signer: &SignerNonces) -> Vec<U256> {
+        signer.reclaimed.iter().copied().collect()
+    }
+
+    #[test]
+    fn acquire_hands_out_sequential_nonces_from_the_pending_count() {
+        let mut signer = SignerNonces::default();
+        signer.sync(U256::from(5), U256::from(5), U64::from(100));
+
+        assert_eq!(signer.acquire(1, U64::from(110)), U256::from(5));
+        assert_eq!(signer.acquire(2, U64::from(110)), U256::from(6));
+        assert_eq!(signer.next, U256::from(8));
+        assert_eq!(in_flight(&signer), nonces(&[5, 6, 7]));
+    }
+
+    #[test]
+    fn release_at_the_top_moves_next_back_down() {
+        let mut signer = SignerNonces::default();
+        signer.acquire(3, U64::from(10));
+
+        signer.release(U256::from(2));
+        assert_eq!(signer.next, U256::from(2));
+        assert!(reclaimed(&signer).is_empty());
+
+        // a gap below a live nonce stays reclaimed
+        signer.release(U256::from(0));
+        assert_eq!(signer.next, U256::from(2));
+        assert_eq!(reclaimed(&signer), nonces(&[0]));
+
+        // once the live nonce above it is released both go
+        signer.release(U256::from(1));
+        assert_eq!(signer.next, U256::zero());
+        assert!(reclaimed(&signer).is_empty());
+    }
+
+    #[test]
+    fn release_ignores_nonces_that_are_not_in_flight() {
+        let mut signer = SignerNonces::default();
+        signer.acquire(1, U64::from(10));
+
+        signer.release(U256::from(7));
+        signer.release(U256::zero());
+        signer.release(U256::zero());
+
+        assert!(in_flight(&signer).is_empty());
+        assert!(reclaimed(&signer).is_empty());
+        assert_eq!(signer.next, U256::zero());
+    }
+
+    #[test]
+    fn acquire_reuses_a_reclaimed_gap_only_if_the_whole_sequence_fits() {
+        let mut signer = SignerNonces::default();
+        signer.acquire(3, U64::from(10));
+        signer.release(U256::zero());
+
+        // 1 is still live so two nonces from 0 would collide with it
+        assert_eq!(signer.acquire(2, U64::from(10)), U256::from(3));
+        assert_eq!(reclaimed(&signer), nonces(&[0]));
+
+        // a single nonce fits in the gap
+        assert_eq!(signer.acquire(1, U64::from(10)), U256::zero());
+        assert!(reclaimed(&signer).is_empty());
+        assert_eq!(in_flight(&signer), nonces(&[0, 1, 2, 3, 4]));
+    }
+
+    #[test]
+    fn acquire_runs_a_sequence_from_a_reclaimed_gap_past_next() {
+        let mut signer = SignerNonces::default();
+        signer.acquire(3, U64::from(10));
+        signer.release(U256::from(1));
+
+        // 1 is reclaimed and 2 is live, 0 is live below it
+        assert_eq!(signer.acquire(2, U64::from(10)), U256::from(3));
+
+        signer.release(U256::from(2));
+        signer.release(U256::from(3));
+        signer.release(U256::from(4));
+
+        // 1 and 2 are reclaimed and the top ones compacted, so 1..3 fits up to next
+        assert_eq!(signer.next, U256::from(1));
+        assert_eq!(signer.acquire(2, U64::from(10)), U256::from(1));
+        assert_eq!(signer.next, U256::from(3));
+    }
+
+    #[test]
+    fn sync_drops_used_nonces_and_reclaims_expired_ones() {
+        let mut signer = SignerNonces::default();
+        signer.acquire(2, U64::from(10));
+        signer.acquire(1, U64::from(20));
+
+        // 0 is in the mempool, 1 expired and 2 is still live
+        signer.sync(U256::zero(), U256::from(1), U64::from(11));
+
+        assert_eq!(signer.confirmed, U256::from(1));
+        assert_eq!(in_flight(&signer), nonces(&[2]));
+        assert_eq!(reclaimed(&signer), nonces(&[1]));
+        assert_eq!(signer.next, U256::from(3));
+
+        // the next tx fills the gap
+        assert_eq!(signer.acquire(1, U64::from(30)), U256::from(1));
+    }
+
+    #[test]
+    fn sync_never_moves_next_below_the_pending_count() {
+        let mut signer = SignerNonces::default();
+        signer.acquire(1, U64::from(10));
+        signer.release(U256::zero());
+
+        // another client used the signer
+        signer.sync(U256::from(4), U256::from(4), U64::from(5));
+
+        assert!(reclaimed(&signer).is_empty());
+        assert_eq!(signer.next, U256::from(4));
+        assert_eq!(signer.acquire(1, U64::from(20)), U256::from(4));
+    }
+
+    #[test]
+    fn compact_stops_at_the_pending_count() {
+        let mut signer = SignerNonces::default();
+        signer.acquire(3, U64::from(10));
+        signer.sync(U256::from(2), U256::from(2), U64::from(5));
+
+        signer.release(U256::from(2));
+
+        assert_eq!(signer.next, U256::from(2));
+        assert!(reclaimed(&signer).is_empty());
+    }
+
+    #[test]
+    fn a_tx_stuck_in_the_mempool_keeps_the_signer_busy() {
+        let mut signer = SignerNonces::default();
+        signer.sync(U256::from(3), U256::from(4), U64::from(5));
+
+        assert!(signer.in_flight.is_empty());
+        assert!(signer.has_pending_tx());
+
+        signer.sync(U256::from(4), U256::from(4), U64::from(6));
+        assert!(!signer.has_pending_tx());
+    }
+}
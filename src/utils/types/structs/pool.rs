@@ -1,18 +1,44 @@
 use ethers::prelude::*;
+use crate::utils::constants::WETH;
+use super::base_token::get_base_token;
+use super::v2_fork::get_v2_fork;
 
 
 
+// The AMM a pool belongs to
+// fee is in hundredths of a bip, eg. 3000 is 0.3%
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PoolKind {
+    // a pair of one of the registered V2 forks
+    UniswapV2 {
+        factory: Address,
+        fee: u32,
+    },
+    UniswapV3 {
+        fee: u32,
+    },
+}
+
 // Holds Pool Information
+// token_0 is the base token (WETH or a stablecoin), token_1 is the shitcoin
+// weth_liquidity is the base token side of the pool valued in ETH
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Pool {
     pub address: Address,
     pub token_0: Address,
     pub token_1: Address,
     pub weth_liquidity: U256,
+    pub kind: PoolKind,
 }
 
 impl Pool {
-    pub fn new(address: Address, token_a: Address, token_b: Address, weth_liquidity: U256) -> Pool {
+    pub fn new(
+        address: Address,
+        token_a: Address,
+        token_b: Address,
+        weth_liquidity: U256,
+        kind: PoolKind
+    ) -> Pool {
         let token_0 = token_a;
         let token_1 = token_b;
 
@@ -21,6 +47,46 @@ impl Pool {
             token_0,
             token_1,
             weth_liquidity,
+            kind,
+        }
+    }
+
+    pub fn is_v3(&self) -> bool {
+        matches!(self.kind, PoolKind::UniswapV3 { .. })
+    }
+
+    pub fn fee(&self) -> u32 {
+        match self.kind {
+            PoolKind::UniswapV2 { fee, .. } | PoolKind::UniswapV3 { fee } => fee,
         }
     }
-}
\ No newline at end of file
+
+    // name of the DEX the pool belongs to
+    pub fn dex_name(&self) -> &'static str {
+        match self.kind {
+            PoolKind::UniswapV2 { factory, .. } =>
+                get_v2_fork(factory)
+                    .map(|x| x.name)
+                    .unwrap_or("Unknown V2"),
+            PoolKind::UniswapV3 { .. } => "Uniswap V3",
+        }
+    }
+
+    pub fn is_weth_quoted(&self) -> bool {
+        self.token_0 == *WETH
+    }
+
+    // the V2 pair we route through between WETH and the base token
+    // None if the pool is quoted in WETH
+    pub fn base_pair(&self) -> Option<Address> {
+        if self.is_weth_quoted() {
+            return None;
+        }
+        get_base_token(self.token_0).map(|x| x.weth_pair)
+    }
+
+    // the pool that sends us the weth when we sell
+    pub fn weth_out_pool(&self) -> Address {
+        self.base_pair().unwrap_or(self.address)
+    }
+}
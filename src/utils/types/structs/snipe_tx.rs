@@ -17,6 +17,18 @@ pub struct SnipeTx {
     pub is_pending: bool,
     pub retry_pending: bool,
     pub got_initial_out: bool,
+    // sell tax measured by the last EVM sell (in basis points)
+    pub sell_tax_bps: Option<u64>,
+    // true once two EVM sells in a row measured the same tax
+    pub fixed_tax: bool,
+    // storage slot of our token balance
+    pub balance_slot: Option<U256>,
+    // block of the last full EVM sell
+    pub last_evm_check: U64,
+    // last quoted amount of weth out
+    pub last_amount_out: U256,
+    // the deployer and the owner of the token, their txs can open trading
+    pub launchers: Vec<Address>,
 }
 
 impl SnipeTx {
@@ -43,6 +55,12 @@ impl SnipeTx {
             is_pending: false,
             retry_pending: false,
             got_initial_out: false,
+            sell_tax_bps: None,
+            fixed_tax: false,
+            balance_slot: None,
+            last_evm_check: U64::zero(),
+            last_amount_out: U256::zero(),
+            launchers: Vec::new(),
         }
     }
 
@@ -65,6 +83,12 @@ impl SnipeTx {
             is_pending: false,
             retry_pending: false,
             got_initial_out: false,
+            sell_tax_bps: None,
+            fixed_tax: false,
+            balance_slot: None,
+            last_evm_check: U64::zero(),
+            last_amount_out: U256::zero(),
+            launchers: Vec::new(),
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,67 @@
+use ethers::prelude::*;
+use super::pool::Pool;
+
+
+// What a changed storage slot most likely holds
+#[derive(Debug, Clone, PartialEq)]
+pub enum SlotKind {
+    // our token balance
+    OurBalance,
+    // a mapping entry keyed by our address other than the balance, eg. a blacklist
+    OurMappingEntry { mapping_slot: U256 },
+    // the token balance of the pool
+    PoolBalance,
+    // the slot holding the owner address
+    Owner,
+    // a small number, usually a fee or a percentage
+    FeeLike,
+    // the packed reserves of the pool
+    PoolReserves,
+    Other,
+}
+
+// A storage slot of a held token or its pool that a pending tx changes
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlotChange {
+    pub pool: Pool,
+    pub address: Address,
+    pub slot: U256,
+    pub old_value: U256,
+    pub new_value: U256,
+    pub kind: SlotKind,
+}
+
+impl SlotChange {
+    // true if the change can hurt our position
+    // swaps also write counters and balances of other holders, so in a swap
+    // we only care about the slots that are ours or the owner
+    pub fn is_suspicious(&self, is_swap: bool) -> bool {
+        match self.kind {
+            SlotKind::OurBalance => self.new_value < self.old_value,
+            SlotKind::OurMappingEntry { .. } | SlotKind::Owner => true,
+            SlotKind::FeeLike | SlotKind::Other => !is_swap,
+            SlotKind::PoolReserves | SlotKind::PoolBalance => false,
+        }
+    }
+}
+
+// Returns the pools whose changes are worth the before and after sell simulation
+pub fn get_suspicious_pools(changes: &[SlotChange]) -> Vec<Pool> {
+    let mut pools: Vec<Pool> = Vec::new();
+
+    for change in changes {
+        if pools.contains(&change.pool) {
+            continue;
+        }
+
+        let is_swap = changes
+            .iter()
+            .any(|x| x.pool == change.pool && x.kind == SlotKind::PoolReserves);
+
+        if change.is_suspicious(is_swap) {
+            pools.push(change.pool);
+        }
+    }
+
+    pools
+}
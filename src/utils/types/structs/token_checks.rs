@@ -0,0 +1,94 @@
+use ethers::prelude::*;
+use anyhow::anyhow;
+use crate::utils::constants::*;
+
+
+// Who holds the majority of the pair's LP tokens
+#[derive(Debug, Clone, PartialEq)]
+pub enum LpStatus {
+    // sent to the zero or dead address
+    Burned {
+        share_bps: u64,
+    },
+    // sent to a known locker contract
+    // unlock_time is None if we cant read it from the locker
+    Locked {
+        locker: Address,
+        share_bps: u64,
+        unlock_time: Option<U256>,
+    },
+    // still held by the deployer
+    HeldByDeployer {
+        share_bps: u64,
+    },
+    // held by someone else or there is no supply yet
+    Unknown,
+}
+
+
+// Holds the ownership, LP and supply checks for a new pair
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenChecks {
+    pub owner: Option<Address>,
+    pub is_renounced: bool,
+    pub lp_status: LpStatus,
+    pub deployer_share_bps: u64,
+}
+
+impl TokenChecks {
+    pub fn new(
+        owner: Option<Address>,
+        lp_status: LpStatus,
+        deployer_share_bps: u64
+    ) -> Self {
+        // a token without an owner() function counts as renounced
+        let is_renounced = match owner {
+            Some(owner) => owner == Address::zero() || owner == *DEAD_ADDRESS,
+            None => true,
+        };
+
+        Self {
+            owner,
+            is_renounced,
+            lp_status,
+            deployer_share_bps,
+        }
+    }
+
+    // runs the configured filters against the checks
+    // returns an error with the reason if one of them fails
+    pub fn check_filters(&self, timestamp: U256) -> Result<(), anyhow::Error> {
+        if *REQUIRE_RENOUNCED_OWNERSHIP && !self.is_renounced {
+            return Err(anyhow!("Ownership is not renounced, owner: {:?}", self.owner));
+        }
+
+        if *REQUIRE_LP_BURNED_OR_LOCKED {
+            match &self.lp_status {
+                LpStatus::Burned { .. } => {}
+                LpStatus::Locked { unlock_time: Some(unlock_time), .. } => {
+                    let min_unlock_time = timestamp + U256::from(*MIN_LP_LOCK_DURATION);
+                    if *unlock_time < min_unlock_time {
+                        return Err(anyhow!("LP unlocks too soon at {:?}", unlock_time));
+                    }
+                }
+                LpStatus::Locked { locker, unlock_time: None, .. } => {
+                    log::warn!("LP locked in {:?} but unlock time is unknown", locker);
+                }
+                lp_status => {
+                    return Err(anyhow!("LP is not burned or locked: {:?}", lp_status));
+                }
+            }
+        }
+
+        if *ENABLE_DEPLOYER_SHARE_CHECK && self.deployer_share_bps > *MAX_DEPLOYER_SUPPLY_SHARE {
+            return Err(
+                anyhow!(
+                    "Deployer holds {}% of the supply",
+                    (self.deployer_share_bps as f64) / 100.0
+                )
+            );
+        }
+
+        Ok(())
+    }
+}
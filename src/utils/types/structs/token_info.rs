@@ -0,0 +1,93 @@
+use ethers::prelude::*;
+use anyhow::anyhow;
+use crate::utils::constants::*;
+
+
+// Where the deployer got its first ETH from
+#[derive(Debug, Clone, PartialEq)]
+pub struct FundingSource {
+    pub block: U64,
+    pub tx: Option<TxHash>,
+    // the sender of the tx, or the contract that paid out if the ETH came from an internal call
+    pub funder: Option<Address>,
+}
+
+impl FundingSource {
+    // funded by one of the flagged contracts, eg. a mixer
+    pub fn is_flagged(&self) -> bool {
+        match self.funder {
+            Some(funder) => FLAGGED_FUNDERS.contains(&funder),
+            None => false,
+        }
+    }
+}
+
+
+// Holds the metadata and the holder distribution of a new token
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenInfo {
+    pub address: Address,
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    pub decimals: Option<u8>,
+    pub total_supply: U256,
+    // largest holders with their balance, the token, the pool and burn addresses are excluded
+    pub top_holders: Vec<(Address, U256)>,
+    pub top_holders_share_bps: u64,
+    pub funding_source: Option<FundingSource>,
+}
+
+impl TokenInfo {
+    // symbol and name of the token for the logs, falls back to the address
+    pub fn label(&self) -> String {
+        match (&self.symbol, &self.name) {
+            (Some(symbol), Some(name)) => format!("{} ({}) {:?}", symbol, name, self.address),
+            (Some(symbol), None) => format!("{} {:?}", symbol, self.address),
+            _ => format!("{:?}", self.address),
+        }
+    }
+
+    // one line with everything we know about the token
+    pub fn summary(&self) -> String {
+        format!(
+            "{} | decimals {:?} | supply {} | top {} holders {}% | funded by {:?}",
+            self.label(),
+            self.decimals,
+            self.total_supply,
+            self.top_holders.len(),
+            (self.top_holders_share_bps as f64) / 100.0,
+            self.funding_source.as_ref().and_then(|x| x.funder)
+        )
+    }
+
+    // writes what we decided for the token to the decision log
+    pub fn log_decision(&self, decision: &str, reason: &str) {
+        log::info!(target: "decisions", "{} | {} | {}", decision, self.summary(), reason);
+    }
+
+    // runs the configured filters against the token info
+    // returns an error with the reason if one of them fails
+    pub fn check_filters(&self) -> Result<(), anyhow::Error> {
+        if *REQUIRE_TOKEN_METADATA && (self.symbol.is_none() || self.decimals.is_none()) {
+            return Err(anyhow!("Token has no symbol or decimals"));
+        }
+
+        if *ENABLE_TOP_HOLDERS_CHECK && self.top_holders_share_bps > *MAX_TOP_HOLDERS_SHARE {
+            return Err(
+                anyhow!(
+                    "Top {} holders hold {}% of the supply",
+                    self.top_holders.len(),
+                    (self.top_holders_share_bps as f64) / 100.0
+                )
+            );
+        }
+
+        if let Some(funding_source) = &self.funding_source {
+            if funding_source.is_flagged() {
+                return Err(anyhow!("Deployer funded by flagged address {:?}", funding_source.funder));
+            }
+        }
+
+        Ok(())
+    }
+}
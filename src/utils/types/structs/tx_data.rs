@@ -1,5 +1,8 @@
 use ethers::prelude::*;
 use ethers::types::transaction::eip2930::AccessList;
+use crate::utils::abi::encode_swap_and_pay;
+use crate::utils::constants::COINBASE_PAYMENT_GAS;
+use super::bundle_plan::{ BundlePlan, OurTx };
 
 
 // Holds the data for a transaction
@@ -7,28 +10,61 @@ use ethers::types::transaction::eip2930::AccessList;
 pub struct TxData {
     pub tx_call_data: Bytes,
     pub gas_used: u64,
+    pub gas_limit: u64,
     pub expected_amount: U256,
-    pub pending_tx: Transaction,
-    pub frontrun_or_backrun: U256,
-    pub access_list: AccessList
+    // the pool that sends us the output, used to check the expected amount
+    pub out_pool: Address,
+    pub plan: BundlePlan,
+    pub access_list: AccessList,
+    // the simulated profit in weth, set by the callers that know what the tokens cost us
+    pub profit: Option<U256>,
 }
 
 impl TxData {
     pub fn new(
         tx_call_data: Bytes,
         gas_used: u64,
+        gas_limit: u64,
         expected_amount: U256,
-        pending_tx: Transaction,
-        frontrun_or_backrun: U256,
+        out_pool: Address,
+        plan: BundlePlan,
         access_list: AccessList
     ) -> Self {
         TxData {
             tx_call_data,
             gas_used,
+            gas_limit,
             expected_amount,
-            pending_tx,
-            frontrun_or_backrun,
-            access_list
+            out_pool,
+            plan,
+            access_list,
+            profit: None,
         }
     }
+
+    // our swap and our other txs of the plan, in nonce order
+    pub fn our_txs(&self) -> Vec<OurTx> {
+        let swap = OurTx {
+            call_data: self.tx_call_data.clone(),
+            gas_limit: self.gas_limit,
+            access_list: self.access_list.clone(),
+        };
+
+        self.plan.our_txs(swap)
+    }
+
+    // how many nonces our txs need
+    pub fn our_tx_count(&self) -> usize {
+        self.plan.our_tx_count()
+    }
+
+    // wraps the swap so the contract also pays the builder through block.coinbase
+    pub fn pay_coinbase(&mut self, coinbase_payment: U256) {
+        self.tx_call_data = encode_swap_and_pay(
+            self.tx_call_data.to_vec(),
+            coinbase_payment
+        ).into();
+        self.gas_used += *COINBASE_PAYMENT_GAS;
+        self.gas_limit += *COINBASE_PAYMENT_GAS;
+    }
 }
\ No newline at end of file
@@ -0,0 +1,18 @@
+// What happened to a tx we sent
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TxOutcome {
+    // our tx was mined and the swap went through
+    Landed,
+    // our swap will never land
+    // nonce_used is false only if nothing ever used the nonce, so it can be handed out again
+    Dropped { nonce_used: bool },
+    // our tx may still be pending, we cant tell yet if the swap happens
+    Unknown,
+}
+
+impl TxOutcome {
+    // only a nonce nothing used can go back to the pool, the rest is left to the nonce sync
+    pub fn frees_nonce(&self) -> bool {
+        *self == TxOutcome::Dropped { nonce_used: false }
+    }
+}
@@ -0,0 +1,8 @@
+// How our txs reach the block
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TxRoute {
+    // bundles sent to the MEV builders
+    Bundle,
+    // plain txs broadcasted to the public mempool, for chains or times without builders
+    PublicMempool,
+}
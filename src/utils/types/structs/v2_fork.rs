@@ -0,0 +1,62 @@
+use ethers::prelude::*;
+use ethers::utils::{ keccak256, get_create2_address_from_hash };
+use crate::utils::constants::V2_FORKS;
+
+
+// A Uniswap V2 style DEX
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct V2Fork {
+    pub name: &'static str,
+    pub factory: Address,
+    #[allow(dead_code)]
+    pub router: Address,
+    // init code hash of the pair contract, used to derive the pair addresses
+    pub init_code_hash: H256,
+    // swap fee in hundredths of a bip, eg. 3000 is 0.3%
+    pub fee: u32,
+}
+
+impl V2Fork {
+    pub fn new(
+        name: &'static str,
+        factory: Address,
+        router: Address,
+        init_code_hash: H256,
+        fee: u32
+    ) -> V2Fork {
+        V2Fork {
+            name,
+            factory,
+            router,
+            init_code_hash,
+            fee,
+        }
+    }
+
+    // CREATE2 address of the pair of two tokens
+    pub fn pair_for(&self, token_a: Address, token_b: Address) -> Address {
+        let (token_0, token_1) = if token_a < token_b {
+            (token_a, token_b)
+        } else {
+            (token_b, token_a)
+        };
+
+        let salt = keccak256([token_0.as_bytes(), token_1.as_bytes()].concat());
+
+        get_create2_address_from_hash(self.factory, salt, self.init_code_hash)
+    }
+}
+
+// get the registered fork of a factory
+pub fn get_v2_fork(factory: Address) -> Option<V2Fork> {
+    V2_FORKS.iter()
+        .find(|x| x.factory == factory)
+        .copied()
+}
+
+// finds the fork that deployed a pair by matching its CREATE2 address
+pub fn find_v2_fork_of_pair(pair: Address, token_0: Address, token_1: Address) -> Option<V2Fork> {
+    V2_FORKS.iter()
+        .find(|x| x.pair_for(token_0, token_1) == pair)
+        .copied()
+}
@@ -0,0 +1,42 @@
+use ethers::prelude::*;
+
+
+// ** Uniswap V2 pricing math, same as `Swapper._getAmountOut` in the contract **
+
+// fees are in hundredths of a bip, eg. 3000 is 0.3%
+const FEE_DENOMINATOR: u32 = 1000000;
+
+// given an input amount returns the maximum output amount
+pub fn get_amount_out(amount_in: U256, reserve_in: U256, reserve_out: U256, fee: u32) -> U256 {
+    if amount_in.is_zero() || reserve_in.is_zero() || reserve_out.is_zero() {
+        return U256::zero();
+    }
+    let amount_in_with_fee = amount_in * (FEE_DENOMINATOR - fee.min(FEE_DENOMINATOR));
+    let numerator = amount_in_with_fee * reserve_out;
+    let denominator = reserve_in * FEE_DENOMINATOR + amount_in_with_fee;
+    numerator / denominator
+}
+
+// given an output amount returns the required input amount
+pub fn get_amount_in(amount_out: U256, reserve_in: U256, reserve_out: U256, fee: u32) -> U256 {
+    if amount_out.is_zero() || reserve_in.is_zero() || amount_out >= reserve_out || fee >= FEE_DENOMINATOR {
+        return U256::zero();
+    }
+    let numerator = reserve_in * amount_out * FEE_DENOMINATOR;
+    let denominator = (reserve_out - amount_out) * (FEE_DENOMINATOR - fee);
+    numerator / denominator + 1
+}
+
+// sorts the reserves of a pair, returns (reserve_in, reserve_out)
+pub fn sort_reserves(
+    input_token: Address,
+    output_token: Address,
+    reserve_0: U256,
+    reserve_1: U256
+) -> (U256, U256) {
+    if input_token < output_token {
+        (reserve_0, reserve_1)
+    } else {
+        (reserve_1, reserve_0)
+    }
+}
@@ -0,0 +1,40 @@
+use ethers::prelude::*;
+use crate::utils::constants::V3_LIQUIDITY_TICK_WINDOW;
+
+
+// ** Uniswap V3 liquidity math **
+
+// estimates the base token a V3 pool holds within V3_LIQUIDITY_TICK_WINDOW ticks of the current price
+// assumes the active liquidity stays the same over the whole window
+//
+// base as token1: L * (sqrtP - sqrtP_lower)
+// base as token0: L * (1 / sqrtP - 1 / sqrtP_upper)
+// both come down to L * sqrtP^(+-1) * (1 - 1.0001^(-window / 2))
+pub fn estimate_base_liquidity(
+    sqrt_price_x96: U256,
+    liquidity: U256,
+    base_is_token0: bool,
+    base_balance: U256
+) -> U256 {
+    if sqrt_price_x96.is_zero() || liquidity.is_zero() {
+        return U256::zero();
+    }
+
+    let sqrt_price = u256_to_f64(sqrt_price_x96) / (2f64).powi(96);
+    let liquidity = u256_to_f64(liquidity);
+
+    let window_factor = 1.0 - (1.0001f64).powf(-(*V3_LIQUIDITY_TICK_WINDOW as f64) / 2.0);
+
+    let base = if base_is_token0 {
+        (liquidity / sqrt_price) * window_factor
+    } else {
+        liquidity * sqrt_price * window_factor
+    };
+
+    // we cant count more than the pool actually holds
+    U256::from(base.max(0.0).min(u128::MAX as f64) as u128).min(base_balance)
+}
+
+fn u256_to_f64(value: U256) -> f64 {
+    value.to_string().parse::<f64>().unwrap_or(0.0)
+}